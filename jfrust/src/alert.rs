@@ -0,0 +1,556 @@
+use std::{fmt, fs, path::Path, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cycle::GcCycle,
+    error::Result,
+    jfr::{ms_since, CollectionType, JfrEvent, JfrMain},
+    rates::rate_intervals,
+    stats::PauseReport,
+    MB,
+};
+
+/// GC ids cited per violation.
+const MAX_CITED: usize = 20;
+
+/*
+[[rule]]
+name = "long pauses"
+condition = "pause_p99 > 200ms"
+severity = "critical"
+
+[[rule]]
+name = "fast promotion"
+condition = "promotion_rate > 50MB/s"
+
+[[rule]]
+name = "any full GC"
+condition = "full_gcs > 0"
+severity = "info"
+*/
+#[derive(Deserialize, Debug, Clone)]
+struct RulesFile {
+    #[serde(default)]
+    rule: Vec<AlertRule>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    #[default]
+    Warning,
+    Critical,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlertRule {
+    pub name: String,
+    pub condition: Condition,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+/// What a condition compares. Per GC metrics are checked against every GC, the rest
+/// against the whole recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// Sum of the pauses of a GC.
+    Pause,
+    /// Longest pause of a GC.
+    LongestPause,
+    /// Between the GC and the one before it.
+    AllocationRate,
+    PromotionRate,
+    /// Heap used after a GC.
+    UsedAfter,
+    /// Percentiles of the longest pause of every GC, as `pauses` prints.
+    PauseP50,
+    PauseP90,
+    PauseP99,
+    PauseP999,
+    PauseMax,
+    PauseTotal,
+    Gcs,
+    YoungGcs,
+    MixedGcs,
+    FullGcs,
+    EvacuationFailures,
+    /// Share of the recording spent in pauses.
+    GcOverhead,
+    MeanAllocationRate,
+    MeanPromotionRate,
+}
+
+const METRICS: [(&str, Metric); 19] = [
+    ("pause", Metric::Pause),
+    ("longest_pause", Metric::LongestPause),
+    ("allocation_rate", Metric::AllocationRate),
+    ("promotion_rate", Metric::PromotionRate),
+    ("used_after", Metric::UsedAfter),
+    ("pause_p50", Metric::PauseP50),
+    ("pause_p90", Metric::PauseP90),
+    ("pause_p99", Metric::PauseP99),
+    ("pause_p999", Metric::PauseP999),
+    ("pause_max", Metric::PauseMax),
+    ("pause_total", Metric::PauseTotal),
+    ("gcs", Metric::Gcs),
+    ("young_gcs", Metric::YoungGcs),
+    ("mixed_gcs", Metric::MixedGcs),
+    ("full_gcs", Metric::FullGcs),
+    ("evacuation_failures", Metric::EvacuationFailures),
+    ("gc_overhead", Metric::GcOverhead),
+    ("mean_allocation_rate", Metric::MeanAllocationRate),
+    ("mean_promotion_rate", Metric::MeanPromotionRate),
+];
+
+impl Metric {
+    pub fn name(&self) -> &'static str {
+        METRICS
+            .iter()
+            .find(|(_, metric)| metric == self)
+            .map(|(name, _)| *name)
+            .unwrap_or_default()
+    }
+
+    /// The unit thresholds are in, which a condition may repeat after the number.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Metric::Pause
+            | Metric::LongestPause
+            | Metric::PauseP50
+            | Metric::PauseP90
+            | Metric::PauseP99
+            | Metric::PauseP999
+            | Metric::PauseMax
+            | Metric::PauseTotal => "ms",
+            Metric::AllocationRate
+            | Metric::PromotionRate
+            | Metric::MeanAllocationRate
+            | Metric::MeanPromotionRate => "MB/s",
+            Metric::UsedAfter => "MB",
+            Metric::GcOverhead => "%",
+            Metric::Gcs
+            | Metric::YoungGcs
+            | Metric::MixedGcs
+            | Metric::FullGcs
+            | Metric::EvacuationFailures => "",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Op {
+    fn holds(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Op::Gt => value > threshold,
+            Op::Ge => value >= threshold,
+            Op::Lt => value < threshold,
+            Op::Le => value <= threshold,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+        }
+    }
+}
+
+/// `<metric> <op> <threshold>[unit]`, e.g. "pause_p99 > 200ms".
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "String")]
+pub struct Condition {
+    pub metric: Metric,
+    pub op: Op,
+    pub threshold: f64,
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (at, op) = [(">=", Op::Ge), ("<=", Op::Le), (">", Op::Gt), ("<", Op::Lt)]
+            .into_iter()
+            .find_map(|(token, op)| Some((s.find(token)?, (token, op))))
+            .ok_or_else(|| format!("expected <metric> <op> <threshold>, got {:?}", s))?;
+        let name = s[..at].trim();
+        let value = s[at + op.0.len()..].trim();
+        let metric = METRICS
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, metric)| *metric)
+            .ok_or_else(|| {
+                format!(
+                    "unknown metric {:?}, expected one of {}",
+                    name,
+                    METRICS.map(|(name, _)| name).join(", ")
+                )
+            })?;
+        let number = value.strip_suffix(metric.unit()).unwrap_or(value).trim();
+        let threshold = number.parse::<f64>().map_err(|_| {
+            format!(
+                "expected a threshold in {:?} for {}, got {:?}",
+                metric.unit(),
+                name,
+                value
+            )
+        })?;
+        Ok(Condition {
+            metric,
+            op: op.1,
+            threshold,
+        })
+    }
+}
+
+impl TryFrom<String> for Condition {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}{}",
+            self.metric.name(),
+            self.op.as_str(),
+            self.threshold,
+            self.metric.unit()
+        )
+    }
+}
+
+pub fn load_rules(path: &Path) -> Result<Vec<AlertRule>> {
+    let rules: RulesFile = toml::from_str(&fs::read_to_string(path)?)?;
+    Ok(rules.rule)
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Violation {
+    pub rule: String,
+    pub condition: String,
+    pub severity: Severity,
+    /// The recording's value, or the worst of the GCs that broke a per GC rule.
+    pub value: f64,
+    /// GCs that broke the rule or were counted in it, the worst first.
+    pub gc_ids: Vec<u64>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct AlertReport {
+    pub rules: usize,
+    pub violations: Vec<Violation>,
+}
+
+impl AlertReport {
+    pub fn worst(&self) -> Option<Severity> {
+        self.violations.iter().map(|v| v.severity).max()
+    }
+}
+
+/// A metric of every GC, or of the recording with the GCs it counts.
+enum Values {
+    PerGc(Vec<(u64, f64)>),
+    Recording(f64, Vec<u64>),
+}
+
+fn values(jfr: &JfrMain, metric: Metric) -> Values {
+    let cycles = jfr.gc_cycles();
+    let per_gc = |f: &dyn Fn(&GcCycle) -> Option<f64>| {
+        cycles
+            .iter()
+            .filter_map(|cycle| Some((cycle.gc_id, f(cycle)?)))
+            .collect::<Vec<_>>()
+    };
+    let count = |f: &dyn Fn(&GcCycle) -> bool| {
+        let gc_ids = cycles
+            .iter()
+            .filter(|cycle| f(cycle))
+            .map(|cycle| cycle.gc_id)
+            .collect::<Vec<_>>();
+        Values::Recording(gc_ids.len() as f64, gc_ids)
+    };
+    let pauses = || PauseReport::new(&cycles).overall;
+    // GCs at or over a pause percentile
+    let slowest = |value: f64| {
+        let mut slow = cycles
+            .iter()
+            .filter(|cycle| cycle.longest_pause_ms >= value && value > 0f64)
+            .collect::<Vec<_>>();
+        slow.sort_by(|a, b| b.longest_pause_ms.total_cmp(&a.longest_pause_ms));
+        Values::Recording(value, slow.iter().map(|cycle| cycle.gc_id).collect())
+    };
+    let mean = |xs: Vec<f64>| xs.iter().sum::<f64>() / xs.len().max(1) as f64;
+    match metric {
+        Metric::Pause => Values::PerGc(per_gc(&|cycle| Some(cycle.sum_of_pauses_ms))),
+        Metric::LongestPause => Values::PerGc(per_gc(&|cycle| Some(cycle.longest_pause_ms))),
+        Metric::UsedAfter => Values::PerGc(per_gc(&|cycle| Some(cycle.after?.used as f64 / MB))),
+        Metric::AllocationRate => Values::PerGc(
+            rate_intervals(jfr)
+                .iter()
+                .map(|i| (i.gc_id, i.allocation_mb_s))
+                .collect(),
+        ),
+        Metric::PromotionRate => Values::PerGc(
+            rate_intervals(jfr)
+                .iter()
+                .map(|i| (i.gc_id, i.promotion_mb_s))
+                .collect(),
+        ),
+        Metric::PauseP50 => slowest(pauses().p50_ms),
+        Metric::PauseP90 => slowest(pauses().p90_ms),
+        Metric::PauseP99 => slowest(pauses().p99_ms),
+        Metric::PauseP999 => slowest(pauses().p999_ms),
+        Metric::PauseMax => slowest(pauses().max_ms),
+        Metric::PauseTotal => Values::Recording(pauses().total_ms, Vec::new()),
+        Metric::Gcs => count(&|_| true),
+        Metric::YoungGcs => count(&|cycle| cycle.name == "G1New"),
+        Metric::MixedGcs => count(&|cycle| matches!(cycle.collection_type, CollectionType::Mixed)),
+        Metric::FullGcs => count(&|cycle| cycle.name == "G1Full"),
+        Metric::EvacuationFailures => count(&|cycle| cycle.evacuation_failed),
+        Metric::GcOverhead => {
+            let span = jfr.start_time().and_then(|origin| {
+                let end = jfr
+                    .recording
+                    .events
+                    .iter()
+                    .filter_map(JfrEvent::start_time)
+                    .max()?;
+                Some(ms_since(origin, end))
+            });
+            let paused = cycles
+                .iter()
+                .map(|cycle| cycle.sum_of_pauses_ms)
+                .sum::<f64>();
+            match span {
+                Some(span) if span > 0f64 => Values::Recording(paused / span * 100f64, Vec::new()),
+                _ => Values::Recording(0f64, Vec::new()),
+            }
+        }
+        Metric::MeanAllocationRate => Values::Recording(
+            mean(
+                rate_intervals(jfr)
+                    .iter()
+                    .map(|i| i.allocation_mb_s)
+                    .collect(),
+            ),
+            Vec::new(),
+        ),
+        Metric::MeanPromotionRate => Values::Recording(
+            mean(
+                rate_intervals(jfr)
+                    .iter()
+                    .map(|i| i.promotion_mb_s)
+                    .collect(),
+            ),
+            Vec::new(),
+        ),
+    }
+}
+
+/// The value of a metric of the whole recording, `None` for per GC metrics.
+pub(crate) fn recording_value(jfr: &JfrMain, metric: Metric) -> Option<f64> {
+    match values(jfr, metric) {
+        Values::Recording(value, _) => Some(value),
+        Values::PerGc(_) => None,
+    }
+}
+
+pub fn evaluate(jfr: &JfrMain, rules: &[AlertRule]) -> AlertReport {
+    let mut report = AlertReport {
+        rules: rules.len(),
+        ..Default::default()
+    };
+    for rule in rules {
+        let Condition {
+            metric,
+            op,
+            threshold,
+        } = rule.condition;
+        let violation = match values(jfr, metric) {
+            Values::PerGc(mut values) => {
+                values.retain(|(_, value)| op.holds(*value, threshold));
+                // worst first, the highest for > and the lowest for <
+                values.sort_by(|a, b| match op {
+                    Op::Gt | Op::Ge => b.1.total_cmp(&a.1),
+                    Op::Lt | Op::Le => a.1.total_cmp(&b.1),
+                });
+                values
+                    .first()
+                    .map(|(_, worst)| (*worst, values.iter().map(|(gc_id, _)| *gc_id).collect()))
+            }
+            Values::Recording(value, gc_ids) => {
+                op.holds(value, threshold).then_some((value, gc_ids))
+            }
+        };
+        if let Some((value, mut gc_ids)) = violation {
+            gc_ids.truncate(MAX_CITED);
+            report.violations.push(Violation {
+                rule: rule.name.clone(),
+                condition: rule.condition.to_string(),
+                severity: rule.severity,
+                value,
+                gc_ids,
+            });
+        }
+    }
+    report
+        .violations
+        .sort_by_key(|v| std::cmp::Reverse(v.severity));
+    report
+}
+
+impl fmt::Display for AlertReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} rules violated",
+            self.violations.len(),
+            self.rules
+        )?;
+        for v in &self.violations {
+            writeln!(
+                f,
+                "{:<8} {}: {}, was {:.2}",
+                format!("{:?}", v.severity).to_lowercase(),
+                v.rule,
+                v.condition,
+                v.value
+            )?;
+            if !v.gc_ids.is_empty() {
+                writeln!(
+                    f,
+                    "         gc {}",
+                    v.gc_ids
+                        .iter()
+                        .map(u64::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(s: &str) -> Condition {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(condition("gcs > 5").op, Op::Gt);
+        assert_eq!(condition("gcs >= 5").op, Op::Ge);
+        assert_eq!(condition("gcs < 5").op, Op::Lt);
+        assert_eq!(condition("gcs <= 5").op, Op::Le);
+        // ">=" is not ">" with a threshold of "=5"
+        let ge = condition("full_gcs>=1");
+        assert_eq!(
+            (ge.metric, ge.op, ge.threshold),
+            (Metric::FullGcs, Op::Ge, 1f64)
+        );
+    }
+
+    #[test]
+    fn boundaries() {
+        assert!(!Op::Gt.holds(5f64, 5f64));
+        assert!(Op::Ge.holds(5f64, 5f64));
+        assert!(!Op::Lt.holds(5f64, 5f64));
+        assert!(Op::Le.holds(5f64, 5f64));
+        assert!(Op::Gt.holds(5.1, 5f64));
+        assert!(Op::Lt.holds(4.9, 5f64));
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(condition("pause_p99 > 200ms").threshold, 200f64);
+        assert_eq!(condition("pause_p99 > 200 ms").threshold, 200f64);
+        assert_eq!(condition("pause_p99 > 200").threshold, 200f64);
+        assert_eq!(condition("promotion_rate > 50MB/s").threshold, 50f64);
+        assert_eq!(condition("used_after >= 1.5MB").threshold, 1.5);
+        assert_eq!(condition("gc_overhead > 5%").threshold, 5f64);
+        assert_eq!(condition("  gcs   <   10  ").threshold, 10f64);
+    }
+
+    #[test]
+    fn wrong_units() {
+        assert_eq!(
+            "pause_p99 > 2s".parse::<Condition>(),
+            Err(r#"expected a threshold in "ms" for pause_p99, got "2s""#.to_string())
+        );
+        assert!("pause_p99 > 200msms".parse::<Condition>().is_err());
+        assert!("used_after > 5MB/s".parse::<Condition>().is_err());
+        assert!("gcs > 5%".parse::<Condition>().is_err());
+    }
+
+    #[test]
+    fn invalid_conditions() {
+        assert_eq!(
+            "pause_p99 200ms".parse::<Condition>(),
+            Err(r#"expected <metric> <op> <threshold>, got "pause_p99 200ms""#.to_string())
+        );
+        assert!("pause_p98 > 200ms"
+            .parse::<Condition>()
+            .unwrap_err()
+            .starts_with(r#"unknown metric "pause_p98", expected one of pause, "#));
+        assert!("pause_p99 > ".parse::<Condition>().is_err());
+        assert!("> 5".parse::<Condition>().is_err());
+    }
+
+    #[test]
+    fn displays_as_parsed() {
+        for s in ["pause_p99 > 200ms", "gcs <= 10", "gc_overhead >= 2.5%"] {
+            assert_eq!(condition(s).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn rules_file() {
+        let rules: RulesFile = toml::from_str(
+            r#"
+[[rule]]
+name = "long pauses"
+condition = "pause_p99 > 200ms"
+severity = "critical"
+
+[[rule]]
+name = "fast promotion"
+condition = "promotion_rate > 50MB/s"
+"#,
+        )
+        .unwrap();
+        assert_eq!(rules.rule.len(), 2);
+        assert_eq!(
+            rules.rule[1].condition,
+            condition("promotion_rate > 50MB/s")
+        );
+        assert!(toml::from_str::<RulesFile>(
+            r#"
+[[rule]]
+name = "typo"
+condition = "pause_p99 => 200ms"
+"#
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,322 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use plotly::{
+    common::{Mode, Visible},
+    layout::Axis,
+    Layout, Plot, Scatter,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compare::{self, CompareThresholds, ComparisonReport, Samples},
+    error::{Error, Result},
+    jfr::JfrMain,
+};
+
+/// Trend lines shown until hidden, the rest are in the legend.
+const SHOWN: [&str; 4] = ["pause_p99", "gc_overhead", "gcs", "mean_allocation_rate"];
+
+/*
+{
+  "name": "checkout",
+  "commit": "3f2a9c1",
+  "saved_at": "2026-10-18T18:20:07.118Z",
+  "recording": "checkout.jfr",
+  "jdk": "21.0.4",
+  "metrics": {"gc_overhead": 1.8, "gcs": 116.0, "pause_p99": 8.69, ...},
+  "flags": {"MaxGCPauseMillis": "200", "G1HeapRegionSize": "4194304", ...},
+  "samples": {"pauses": [4.1, 3.9, 12.6, ...], "periods": [[4.1, 910.2], ...], ...}
+}
+*/
+/// Summary of a recording kept to compare later recordings of the same workload with.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Baseline {
+    pub name: String,
+    pub commit: Option<String>,
+    pub saved_at: DateTime<Utc>,
+    /// As given on the command line.
+    pub recording: String,
+    pub jdk: Option<String>,
+    /// As [`compare::metrics`] has them.
+    pub metrics: BTreeMap<String, f64>,
+    /// Latest value of every recorded JVM flag.
+    pub flags: BTreeMap<String, String>,
+    /// To bootstrap the pause, overhead and rate metrics against.
+    pub samples: Samples,
+}
+
+impl Baseline {
+    pub fn of(jfr: &JfrMain, name: &str, commit: Option<&str>, recording: &str) -> Baseline {
+        Baseline {
+            name: name.to_string(),
+            commit: commit.map(str::to_string),
+            saved_at: Utc::now(),
+            recording: recording.to_string(),
+            jdk: jfr.jdk_version().map(|version| version.to_string()),
+            metrics: compare::metrics(jfr),
+            flags: jfr
+                .flags()
+                .into_values()
+                .map(|flag| {
+                    let value = match flag.value {
+                        serde_json::Value::String(s) => s,
+                        value => value.to_string(),
+                    };
+                    (flag.name, value)
+                })
+                .collect(),
+            samples: compare::samples(jfr),
+        }
+    }
+
+    /// The commit, or when the baseline was saved.
+    pub fn label(&self) -> String {
+        self.commit
+            .clone()
+            .unwrap_or_else(|| self.saved_at.format("%Y-%m-%d %H:%M:%S").to_string())
+    }
+}
+
+/// Names and commits as file names, anything but letters, digits, `-`, `_` and `.` is `_`.
+fn file_name(key: &str) -> String {
+    let name = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if name.trim_matches('.').is_empty() {
+        name.replace('.', "_")
+    } else {
+        name
+    }
+}
+
+/// Writes `<dir>/<name>/<commit>.json`, or the time it was saved without a commit,
+/// replacing the baseline of the same commit.
+pub fn save(dir: &Path, baseline: &Baseline) -> Result<PathBuf> {
+    let dir = dir.join(file_name(&baseline.name));
+    fs::create_dir_all(&dir)?;
+    let key = baseline
+        .commit
+        .clone()
+        .unwrap_or_else(|| baseline.saved_at.format("%Y%m%dT%H%M%S%.3fZ").to_string());
+    let path = dir.join(format!("{}.json", file_name(&key)));
+    fs::write(&path, serde_json::to_vec_pretty(baseline)?)?;
+    Ok(path)
+}
+
+/// Baselines of `name`, or of every name, the oldest first. None for a missing directory.
+pub fn list(dir: &Path, name: Option<&str>) -> Result<Vec<Baseline>> {
+    let dirs = match name {
+        Some(name) => vec![dir.join(file_name(name))],
+        None => match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .map(|entry| Ok(entry?.path()))
+                .collect::<io::Result<Vec<_>>>()?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        },
+    };
+    let mut baselines = Vec::new();
+    for dir in dirs.iter().filter(|dir| dir.is_dir()) {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let baseline = serde_json::from_slice(&fs::read(&path)?).map_err(|e| {
+                    Error::Baseline(format!("cannot parse baseline {}: {}", path.display(), e))
+                })?;
+                baselines.push(baseline);
+            }
+        }
+    }
+    baselines.sort_by_key(|b: &Baseline| b.saved_at);
+    Ok(baselines)
+}
+
+/// The baseline of `commit`, or the latest one.
+pub fn find<'a>(baselines: &'a [Baseline], commit: Option<&str>) -> Option<&'a Baseline> {
+    match commit {
+        Some(commit) => baselines
+            .iter()
+            .rev()
+            .find(|b| b.commit.as_deref() == Some(commit)),
+        None => baselines.last(),
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct BaselineList {
+    pub baselines: Vec<Baseline>,
+}
+
+/// A metric of every baseline and then of the recording.
+#[derive(Serialize, Debug, Clone)]
+pub struct TrendLine {
+    pub metric: String,
+    pub unit: String,
+    pub values: Vec<Option<f64>>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct TrendReport {
+    /// Label of the baseline the recording is compared with.
+    pub against: String,
+    pub comparison: ComparisonReport,
+    /// Labels of the baselines and "current", the oldest first.
+    pub runs: Vec<String>,
+    pub lines: Vec<TrendLine>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Trend {
+    pub plot: Plot,
+    pub report: TrendReport,
+}
+
+/// Compares with `against` and lines the recording up after `history`.
+pub fn trend_report(
+    jfr: &JfrMain,
+    history: &[Baseline],
+    against: &Baseline,
+    thresholds: CompareThresholds,
+) -> TrendReport {
+    let comparison = compare::compare_metrics(&against.metrics, &against.samples, jfr, thresholds);
+    let mut runs = history.iter().map(Baseline::label).collect::<Vec<_>>();
+    runs.push("current".to_string());
+    let lines = comparison
+        .differences
+        .iter()
+        .map(|d| TrendLine {
+            metric: d.metric.to_string(),
+            unit: d.unit.to_string(),
+            values: history
+                .iter()
+                .map(|b| b.metrics.get(d.metric).copied())
+                .chain([d.candidate])
+                .collect(),
+        })
+        .collect();
+    TrendReport {
+        against: against.label(),
+        comparison,
+        runs,
+        lines,
+    }
+}
+
+/// Every metric as a % of its first value, so that all fit one chart.
+pub fn trend(
+    jfr: &JfrMain,
+    history: &[Baseline],
+    against: &Baseline,
+    thresholds: CompareThresholds,
+) -> Trend {
+    let report = trend_report(jfr, history, against, thresholds);
+    let mut plot = Plot::new();
+    for line in &report.lines {
+        let Some(first) = line.values.iter().flatten().find(|v| **v != 0f64) else {
+            continue;
+        };
+        let trace = Scatter::new(
+            report.runs.clone(),
+            line.values
+                .iter()
+                .map(|v| v.map(|v| v / first * 100f64))
+                .collect(),
+        )
+        .name(&line.metric)
+        .mode(Mode::LinesMarkers)
+        .hover_text_array(
+            line.values
+                .iter()
+                .map(|v| {
+                    v.map(|v| format!("{:.2}{}", v, line.unit))
+                        .unwrap_or_default()
+                })
+                .collect(),
+        );
+        plot.add_trace(if SHOWN.contains(&line.metric.as_str()) {
+            trace
+        } else {
+            trace.visible(Visible::LegendOnly)
+        });
+    }
+    plot.set_layout(
+        Layout::new()
+            .x_axis(Axis::new().title("run"))
+            .y_axis(Axis::new().title("% of the first run")),
+    );
+    Trend { plot, report }
+}
+
+impl fmt::Display for BaselineList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:<20} {:<20} {:>8} {:>12} {:>10}",
+            "name", "commit", "saved", "jdk", "p99 (ms)", "overhead"
+        )?;
+        for b in &self.baselines {
+            let metric = |name: &str, unit: &str| {
+                b.metrics
+                    .get(name)
+                    .map(|v| format!("{:.2}{}", v, unit))
+                    .unwrap_or_else(|| "-".to_string())
+            };
+            writeln!(
+                f,
+                "{:<20} {:<20} {:<20} {:>8} {:>12} {:>10}",
+                b.name,
+                b.commit.as_deref().unwrap_or("-"),
+                b.saved_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                b.jdk.as_deref().unwrap_or("-"),
+                metric("pause_p99", ""),
+                metric("gc_overhead", "%")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TrendReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "against baseline {}", self.against)?;
+        write!(f, "{}", self.comparison)?;
+        if self.runs.len() <= 2 {
+            return Ok(());
+        }
+        writeln!(f)?;
+        let width = self.runs.iter().map(|run| run.len()).fold(12, usize::max);
+        write!(f, "{:<32}", "trend")?;
+        for run in &self.runs {
+            write!(f, " {:>width$}", run)?;
+        }
+        writeln!(f)?;
+        for line in &self.lines {
+            let label = if line.unit.is_empty() {
+                line.metric.clone()
+            } else {
+                format!("{} ({})", line.metric, line.unit)
+            };
+            write!(f, "  {:<30}", label)?;
+            for value in &line.values {
+                match value {
+                    Some(value) => write!(f, " {:>width$.2}", value)?,
+                    None => write!(f, " {:>width$}", "-")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
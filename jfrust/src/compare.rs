@@ -0,0 +1,706 @@
+use std::{collections::BTreeMap, fmt};
+
+use plotly::{common::Mode, layout::Axis, Layout, Plot, Scatter};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    alert::{recording_value, Metric},
+    jfr::{ms_since, JfrEvent, JfrMain},
+    promotion::premature_promotions,
+    rates::rate_intervals,
+    stats::{bootstrap, percentile_of, Bootstrap},
+    tenuring, MB,
+};
+
+/// Order the differences are listed in.
+const GROUPS: [&str; 5] = ["pauses", "gcs", "rates", "heap", "tenuring"];
+/// Fewer GCs or intervals than this in either recording make no interval worth trusting.
+const MIN_SAMPLES: usize = 10;
+
+/// A difference is a regression or an improvement past both.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct CompareThresholds {
+    /// Change relative to the baseline.
+    pub tolerance: f64,
+    /// Of the bootstrap interval of metrics computed from every GC or interval, which
+    /// must leave out 0.
+    pub confidence: f64,
+}
+
+impl Default for CompareThresholds {
+    fn default() -> Self {
+        CompareThresholds {
+            tolerance: 0.1,
+            confidence: 0.95,
+        }
+    }
+}
+
+/// Whether a lower value is better, `Neither` for metrics that follow the workload.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Better {
+    Lower,
+    Neither,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Improved,
+    Regressed,
+}
+
+/*
+{
+  "group": "pauses",
+  "metric": "pause_p99",
+  "unit": "ms",
+  "better": "lower",
+  "baseline": 12.1,
+  "candidate": 17.9,
+  "delta": 5.8,
+  "change": 0.479,
+  "bootstrap": {"confidence": 0.95, "low": 1.2, "high": 9.7, "p_value": 0.006},
+  "verdict": "regressed"
+}
+*/
+#[derive(Serialize, Debug, Clone)]
+pub struct Difference {
+    pub group: &'static str,
+    pub metric: &'static str,
+    pub unit: &'static str,
+    pub better: Better,
+    /// `None` when the recording lacks the events the metric comes from.
+    pub baseline: Option<f64>,
+    pub candidate: Option<f64>,
+    pub delta: Option<f64>,
+    /// Relative to the baseline, `None` when the baseline is 0.
+    pub change: Option<f64>,
+    /// Of `delta`, for pause, overhead and rate metrics with enough GCs in both recordings.
+    pub bootstrap: Option<Bootstrap>,
+    /// `None` within the tolerance, when neither way is better, or when a pause or rate
+    /// metric could be noise.
+    pub verdict: Option<Verdict>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ComparisonReport {
+    pub thresholds: CompareThresholds,
+    pub differences: Vec<Difference>,
+}
+
+impl ComparisonReport {
+    pub fn regressions(&self) -> impl Iterator<Item = &Difference> {
+        self.differences
+            .iter()
+            .filter(|d| d.verdict == Some(Verdict::Regressed))
+    }
+}
+
+/// The GCs of both recordings overlaid, by seconds since each recording started.
+#[derive(Serialize, Clone)]
+pub struct Comparison {
+    pub pauses: Plot,
+    pub heap: Plot,
+    pub rates: Plot,
+    pub report: ComparisonReport,
+}
+
+#[derive(Clone, Copy)]
+enum Statistic {
+    Percentile(f64),
+    Mean,
+}
+
+impl Statistic {
+    fn of(&self, values: &mut [f64]) -> f64 {
+        match self {
+            Statistic::Percentile(p) => percentile_of(values, *p),
+            Statistic::Mean => values.iter().sum::<f64>() / values.len().max(1) as f64,
+        }
+    }
+}
+
+/// What a metric is computed from, for every GC or interval.
+enum Sample {
+    Values(Vec<f64>, Statistic),
+    /// Pause time and the time until the next GC, the metric is the share of their sums.
+    Share(Vec<(f64, f64)>),
+}
+
+impl Sample {
+    fn len(&self) -> usize {
+        match self {
+            Sample::Values(values, _) => values.len(),
+            Sample::Share(periods) => periods.len(),
+        }
+    }
+}
+
+fn share(periods: &mut [(f64, f64)]) -> f64 {
+    let (paused, total) = periods
+        .iter()
+        .fold((0f64, 0f64), |(p, t), (paused, total)| {
+            (p + paused, t + total)
+        });
+    if total > 0f64 {
+        paused / total * 100f64
+    } else {
+        0f64
+    }
+}
+
+/// Every GC with its pauses and the time from its start to the start of the next, the
+/// first from the start of the recording and the last to its end, as gc_overhead spans it.
+fn periods(jfr: &JfrMain) -> Vec<(f64, f64)> {
+    let Some(origin) = jfr.start_time() else {
+        return Vec::new();
+    };
+    let end = jfr
+        .recording
+        .events
+        .iter()
+        .filter_map(JfrEvent::start_time)
+        .max()
+        .map(|end| ms_since(origin, end))
+        .unwrap_or(0f64);
+    let mut starts = jfr
+        .gc_cycles()
+        .iter()
+        .map(|cycle| {
+            let start = cycle
+                .start_time
+                .map(|start| ms_since(origin, start))
+                .unwrap_or(0f64);
+            (start, cycle.sum_of_pauses_ms)
+        })
+        .collect::<Vec<_>>();
+    starts.sort_by(|a, b| a.0.total_cmp(&b.0));
+    (0..starts.len())
+        .map(|i| {
+            let from = if i == 0 { 0f64 } else { starts[i].0 };
+            let to = starts.get(i + 1).map(|next| next.0).unwrap_or(end);
+            (starts[i].1, to - from)
+        })
+        .collect()
+}
+
+/*
+{
+  "pauses": [4.1, 3.9, 12.6, ...],
+  "periods": [[4.1, 910.2], [3.9, 402.7], ...],
+  "allocation_mb_s": [7795.5, 8029.0, ...],
+  "promotion_mb_s": [111.2, 73.7, ...]
+}
+*/
+/// The values of every GC and interval the pause, overhead and rate metrics are
+/// computed from, kept with a baseline to bootstrap against.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Samples {
+    /// Longest pause of every GC that paused, as PauseReport has them.
+    pub pauses: Vec<f64>,
+    /// Pauses of every GC and the time until the next, in ms.
+    pub periods: Vec<(f64, f64)>,
+    pub allocation_mb_s: Vec<f64>,
+    pub promotion_mb_s: Vec<f64>,
+}
+
+impl Samples {
+    fn of(&self, metric: Metric) -> Option<Sample> {
+        let (values, statistic) = match metric {
+            Metric::GcOverhead => return Some(Sample::Share(self.periods.clone())),
+            Metric::PauseP50 => (&self.pauses, Statistic::Percentile(50f64)),
+            Metric::PauseP90 => (&self.pauses, Statistic::Percentile(90f64)),
+            Metric::PauseP99 => (&self.pauses, Statistic::Percentile(99f64)),
+            Metric::PauseP999 => (&self.pauses, Statistic::Percentile(99.9)),
+            Metric::PauseMax => (&self.pauses, Statistic::Percentile(100f64)),
+            Metric::MeanAllocationRate => (&self.allocation_mb_s, Statistic::Mean),
+            Metric::MeanPromotionRate => (&self.promotion_mb_s, Statistic::Mean),
+            _ => return None,
+        };
+        Some(Sample::Values(values.clone(), statistic))
+    }
+}
+
+pub fn samples(jfr: &JfrMain) -> Samples {
+    let intervals = rate_intervals(jfr);
+    Samples {
+        pauses: jfr
+            .gc_cycles()
+            .iter()
+            .filter(|cycle| cycle.sum_of_pauses_ms > 0f64)
+            .map(|cycle| cycle.longest_pause_ms)
+            .collect(),
+        periods: periods(jfr),
+        allocation_mb_s: intervals.iter().map(|i| i.allocation_mb_s).collect(),
+        promotion_mb_s: intervals.iter().map(|i| i.promotion_mb_s).collect(),
+    }
+}
+
+/// A metric of one recording, `None` when it lacks the events the metric comes from.
+struct Measure {
+    group: &'static str,
+    metric: &'static str,
+    unit: &'static str,
+    better: Better,
+    value: Option<f64>,
+    /// The metric of [`Samples`] it is, if any.
+    sampled: Option<Metric>,
+    sample: Option<Sample>,
+}
+
+fn measures(jfr: &JfrMain) -> Vec<Measure> {
+    let cycles = jfr.gc_cycles();
+    // promotions are sampled by jdk.PromoteObjectInNewPLAB and jdk.PromoteObjectOutsidePLAB,
+    // which GC logs and default recordings lack
+    let samples_promotions = jfr.recording.events.iter().any(|evt| {
+        matches!(
+            evt,
+            JfrEvent::PromoteObjectInNewPLAB { .. } | JfrEvent::PromoteObjectOutsidePLAB { .. }
+        )
+    });
+    let metric = |metric: Metric| recording_value(jfr, metric);
+    let used_after = cycles
+        .iter()
+        .filter_map(|cycle| Some(cycle.after?.used as f64 / MB))
+        .collect::<Vec<_>>();
+    let thresholds = cycles
+        .iter()
+        .filter_map(|cycle| Some(cycle.tenuring_threshold? as f64))
+        .collect::<Vec<_>>();
+    let mean = |xs: &[f64]| (!xs.is_empty()).then(|| xs.iter().sum::<f64>() / xs.len() as f64);
+    let advice = tenuring::advise(jfr);
+    let promotions = premature_promotions(jfr);
+    let samples = samples(jfr);
+    [
+        ("pauses", Metric::PauseP50, Better::Lower),
+        ("pauses", Metric::PauseP90, Better::Lower),
+        ("pauses", Metric::PauseP99, Better::Lower),
+        ("pauses", Metric::PauseP999, Better::Lower),
+        ("pauses", Metric::PauseMax, Better::Lower),
+        ("pauses", Metric::PauseTotal, Better::Lower),
+        ("pauses", Metric::GcOverhead, Better::Lower),
+        ("gcs", Metric::Gcs, Better::Lower),
+        ("gcs", Metric::YoungGcs, Better::Lower),
+        // more mixed GCs can mean smaller ones
+        ("gcs", Metric::MixedGcs, Better::Neither),
+        ("gcs", Metric::FullGcs, Better::Lower),
+        ("gcs", Metric::EvacuationFailures, Better::Lower),
+        // allocation follows throughput as much as garbage
+        ("rates", Metric::MeanAllocationRate, Better::Neither),
+    ]
+    .into_iter()
+    .map(|(group, m, better)| (group, m.name(), m.unit(), better, metric(m), Some(m)))
+    .chain([
+        (
+            "gcs",
+            "concurrent_markings",
+            "",
+            Better::Neither,
+            Some(cycles.iter().filter(|cycle| cycle.name == "G1Old").count() as f64),
+            None,
+        ),
+        (
+            "rates",
+            Metric::MeanPromotionRate.name(),
+            Metric::MeanPromotionRate.unit(),
+            Better::Lower,
+            metric(Metric::MeanPromotionRate).filter(|_| samples_promotions),
+            Some(Metric::MeanPromotionRate).filter(|_| samples_promotions),
+        ),
+        (
+            "heap",
+            "mean_used_after",
+            "MB",
+            Better::Lower,
+            mean(&used_after),
+            None,
+        ),
+        (
+            "heap",
+            "max_used_after",
+            "MB",
+            Better::Lower,
+            used_after.iter().copied().reduce(f64::max),
+            None,
+        ),
+        (
+            "tenuring",
+            "mean_tenuring_threshold",
+            "",
+            Better::Neither,
+            mean(&thresholds),
+            None,
+        ),
+        (
+            "tenuring",
+            "recommended_threshold",
+            "",
+            Better::Neither,
+            (!advice.gc_pairs.is_empty()).then_some(advice.recommended_threshold as f64),
+            None,
+        ),
+        (
+            "tenuring",
+            "premature_promotions",
+            "",
+            Better::Lower,
+            samples_promotions.then_some(promotions.gcs.len() as f64),
+            None,
+        ),
+        (
+            "tenuring",
+            "premature_promotion_size",
+            "MB",
+            Better::Lower,
+            samples_promotions.then_some(promotions.bytes as f64 / MB),
+            None,
+        ),
+    ])
+    .map(|(group, metric, unit, better, value, sampled)| Measure {
+        group,
+        metric,
+        unit,
+        better,
+        value,
+        sampled,
+        sample: sampled.and_then(|m| samples.of(m)),
+    })
+    .collect::<Vec<_>>()
+}
+
+fn verdict(better: Better, baseline: f64, candidate: f64, tolerance: f64) -> Option<Verdict> {
+    if better == Better::Neither {
+        return None;
+    }
+    let gain = baseline - candidate;
+    if gain.abs() <= baseline.abs() * tolerance {
+        None
+    } else if gain > 0f64 {
+        Some(Verdict::Improved)
+    } else {
+        Some(Verdict::Regressed)
+    }
+}
+
+fn difference(b: Measure, c: Measure, thresholds: &CompareThresholds) -> Difference {
+    let both = b.value.zip(c.value);
+    let bootstrap = match (&b.sample, &c.sample) {
+        (Some(bs), Some(cs)) if bs.len().min(cs.len()) < MIN_SAMPLES => None,
+        (Some(Sample::Values(bs, statistic)), Some(Sample::Values(cs, _))) => {
+            bootstrap(bs, cs, |values| statistic.of(values), thresholds.confidence)
+        }
+        (Some(Sample::Share(bs)), Some(Sample::Share(cs))) => {
+            bootstrap(bs, cs, share, thresholds.confidence)
+        }
+        _ => None,
+    };
+    // a statistic of every GC must also stand out from the noise of resampling them
+    let meaningful = b.sample.is_none() || bootstrap.is_some_and(|ci| ci.significant());
+    Difference {
+        group: b.group,
+        metric: b.metric,
+        unit: b.unit,
+        better: b.better,
+        baseline: b.value,
+        candidate: c.value,
+        delta: both.map(|(b, c)| c - b),
+        change: both.filter(|(b, _)| *b != 0f64).map(|(b, c)| (c - b) / b),
+        bootstrap,
+        verdict: both.filter(|_| meaningful).and_then(|(b_value, c_value)| {
+            verdict(b.better, b_value, c_value, thresholds.tolerance)
+        }),
+    }
+}
+
+pub fn compare_report(
+    baseline: &JfrMain,
+    candidate: &JfrMain,
+    thresholds: CompareThresholds,
+) -> ComparisonReport {
+    let mut differences = measures(baseline)
+        .into_iter()
+        .zip(measures(candidate))
+        .map(|(b, c)| difference(b, c, &thresholds))
+        .collect::<Vec<_>>();
+    differences.sort_by_key(|d| GROUPS.iter().position(|group| *group == d.group));
+    ComparisonReport {
+        thresholds,
+        differences,
+    }
+}
+
+/// The metrics compared, by name, leaving out those the recording lacks the events for.
+pub fn metrics(jfr: &JfrMain) -> BTreeMap<String, f64> {
+    measures(jfr)
+        .into_iter()
+        .filter_map(|m| Some((m.metric.to_string(), m.value?)))
+        .collect()
+}
+
+/// Against metrics saved by [`metrics`] and the [`samples`] behind them, bootstrapped
+/// as in [`compare_report`].
+pub fn compare_metrics(
+    baseline: &BTreeMap<String, f64>,
+    samples: &Samples,
+    candidate: &JfrMain,
+    thresholds: CompareThresholds,
+) -> ComparisonReport {
+    let mut differences = measures(candidate)
+        .into_iter()
+        .map(|c| {
+            let b = Measure {
+                value: baseline.get(c.metric).copied(),
+                sample: c.sampled.and_then(|m| samples.of(m)),
+                ..c
+            };
+            difference(b, c, &thresholds)
+        })
+        .collect::<Vec<_>>();
+    differences.sort_by_key(|d| GROUPS.iter().position(|group| *group == d.group));
+    ComparisonReport {
+        thresholds,
+        differences,
+    }
+}
+
+/// Longest pause and heap used after every GC, in seconds since the recording started.
+fn gc_points(jfr: &JfrMain) -> Vec<(f64, f64, Option<f64>)> {
+    let Some(origin) = jfr.start_time() else {
+        return Vec::new();
+    };
+    let mut points = jfr
+        .gc_cycles()
+        .iter()
+        .filter_map(|cycle| {
+            Some((
+                ms_since(origin, cycle.start_time?) / 1000f64,
+                cycle.longest_pause_ms,
+                cycle.after.map(|heap| heap.used as f64 / MB),
+            ))
+        })
+        .collect::<Vec<_>>();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    points
+}
+
+pub fn compare(
+    baseline: &JfrMain,
+    candidate: &JfrMain,
+    thresholds: CompareThresholds,
+) -> Comparison {
+    let mut pauses = Plot::new();
+    let mut heap = Plot::new();
+    let mut rates = Plot::new();
+    for (name, jfr) in [("baseline", baseline), ("candidate", candidate)] {
+        let points = gc_points(jfr);
+        let paused = points.iter().filter(|(_, pause, _)| *pause > 0f64);
+        pauses.add_trace(
+            Scatter::new(
+                paused.clone().map(|(s, _, _)| *s).collect(),
+                paused.map(|(_, pause, _)| *pause).collect(),
+            )
+            .name(name)
+            .mode(Mode::Markers),
+        );
+        let used = points
+            .iter()
+            .filter_map(|(s, _, used)| Some((*s, (*used)?)))
+            .collect::<Vec<_>>();
+        heap.add_trace(
+            Scatter::new(
+                used.iter().map(|(s, _)| *s).collect(),
+                used.iter().map(|(_, used)| *used).collect(),
+            )
+            .name(name)
+            .mode(Mode::LinesMarkers),
+        );
+        let intervals = rate_intervals(jfr);
+        rates.add_trace(
+            Scatter::new(
+                intervals.iter().map(|i| i.at_s).collect(),
+                intervals.iter().map(|i| i.allocation_mb_s).collect(),
+            )
+            .name(name)
+            .mode(Mode::LinesMarkers),
+        );
+    }
+    for (plot, title) in [
+        (&mut pauses, "longest pause (ms)"),
+        (&mut heap, "heap used after GC (MB)"),
+        (&mut rates, "allocation (MB/s)"),
+    ] {
+        plot.set_layout(
+            Layout::new()
+                .x_axis(Axis::new().title("seconds since recording start"))
+                .y_axis(Axis::new().title(title)),
+        );
+    }
+    Comparison {
+        pauses,
+        heap,
+        rates,
+        report: compare_report(baseline, candidate, thresholds),
+    }
+}
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} regressions, over {:.0}% worse than the baseline",
+            self.regressions().count(),
+            self.thresholds.tolerance * 100f64
+        )?;
+        let bootstrapped = self.differences.iter().any(|d| d.bootstrap.is_some());
+        if bootstrapped {
+            write!(
+                f,
+                " and, for pauses, overhead and rates, outside the {:.0}% bootstrap interval",
+                self.thresholds.confidence * 100f64
+            )?;
+        }
+        writeln!(f)?;
+        write!(
+            f,
+            "{:<32} {:>12} {:>12} {:>9}",
+            "metric", "baseline", "candidate", "change"
+        )?;
+        if bootstrapped {
+            write!(f, " {:>22} {:>7}", "interval of delta", "p")?;
+        }
+        writeln!(f)?;
+        let mut group = "";
+        for d in &self.differences {
+            if d.group != group {
+                group = d.group;
+                writeln!(f, "{}", group)?;
+            }
+            let label = if d.unit.is_empty() {
+                d.metric.to_string()
+            } else {
+                format!("{} ({})", d.metric, d.unit)
+            };
+            let value = |value: Option<f64>| {
+                value
+                    .map(|value| format!("{:.2}", value))
+                    .unwrap_or_else(|| "-".to_string())
+            };
+            let mut row = format!(
+                "  {:<30} {:>12} {:>12} {:>9}",
+                label,
+                value(d.baseline),
+                value(d.candidate),
+                d.change
+                    .map(|change| format!("{:+.1}%", change * 100f64))
+                    .unwrap_or_else(|| "-".to_string()),
+            );
+            if bootstrapped {
+                row += &format!(
+                    " {:>22} {:>7}",
+                    d.bootstrap
+                        .map(|ci| format!("[{:+.2}, {:+.2}]", ci.low, ci.high))
+                        .unwrap_or_default(),
+                    d.bootstrap
+                        .map(|ci| format!("{:.3}", ci.p_value))
+                        .unwrap_or_default()
+                );
+            }
+            row += match d.verdict {
+                Some(Verdict::Regressed) => " REGRESSED",
+                Some(Verdict::Improved) => " improved",
+                None => "",
+            };
+            writeln!(f, "{}", row.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gclog;
+
+    /// Young GCs a second apart with the given pauses.
+    fn young_gcs(pauses: &[f64]) -> JfrMain {
+        let mut log = String::new();
+        for (gc_id, pause) in pauses.iter().enumerate() {
+            log += &format!(
+                "[{gc_id}.500s][info ][gc] GC({gc_id}) Pause Young (Normal) (G1 Evacuation Pause) 50M->10M(96M) {pause:.3}ms\n"
+            );
+        }
+        gclog::parse(log.as_bytes()).unwrap()
+    }
+
+    fn noisy(max: f64) -> JfrMain {
+        let mut pauses = (0..19).map(|i| 1f64 + (i % 9) as f64).collect::<Vec<_>>();
+        pauses.push(max);
+        young_gcs(&pauses)
+    }
+
+    fn verdict_of(report: &ComparisonReport, metric: &str) -> Option<Verdict> {
+        report
+            .differences
+            .iter()
+            .find(|d| d.metric == metric)
+            .unwrap()
+            .verdict
+    }
+
+    #[test]
+    fn noise_is_not_a_regression() {
+        let (baseline, candidate) = (noisy(10f64), noisy(12f64));
+        let thresholds = CompareThresholds::default();
+        let report = compare_report(&baseline, &candidate, thresholds);
+        let p99 = report
+            .differences
+            .iter()
+            .find(|d| d.metric == "pause_p99")
+            .unwrap();
+        assert_eq!(p99.change, Some(0.2));
+        assert!(!p99.bootstrap.unwrap().significant());
+        assert_eq!(p99.verdict, None);
+        // against a saved baseline the same
+        let saved = compare_metrics(
+            &metrics(&baseline),
+            &samples(&baseline),
+            &candidate,
+            thresholds,
+        );
+        assert_eq!(verdict_of(&saved, "pause_p99"), None);
+    }
+
+    #[test]
+    fn saved_baselines_compare_as_recordings() {
+        let baseline = young_gcs(&(0..30).map(|i| 2f64 + (i % 5) as f64).collect::<Vec<_>>());
+        let candidate = young_gcs(&(0..30).map(|i| 8f64 + (i % 5) as f64).collect::<Vec<_>>());
+        let thresholds = CompareThresholds::default();
+        let direct = compare_report(&baseline, &candidate, thresholds);
+        let saved = compare_metrics(
+            &metrics(&baseline),
+            &samples(&baseline),
+            &candidate,
+            thresholds,
+        );
+        assert_eq!(verdict_of(&direct, "pause_p99"), Some(Verdict::Regressed));
+        for (d, s) in direct.differences.iter().zip(&saved.differences) {
+            assert_eq!(d.metric, s.metric);
+            assert_eq!(d.verdict, s.verdict, "{}", d.metric);
+            assert_eq!(d.bootstrap, s.bootstrap, "{}", d.metric);
+        }
+    }
+
+    #[test]
+    fn samples_survive_a_round_trip() {
+        let recording = noisy(10f64);
+        let saved = samples(&recording);
+        assert_eq!(saved.pauses.len(), 20);
+        assert_eq!(saved.periods.len(), 20);
+        let json = serde_json::to_string(&saved).unwrap();
+        let loaded: Samples = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.pauses, saved.pauses);
+        assert_eq!(loaded.periods, saved.periods);
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::jfr::{dur_ms, CollectionType, GCWhen, JfrEvent, JfrMain};
+
+/// Heap occupancy on one side of a GC, in bytes.
+#[derive(Serialize, Default, Debug, Clone, Copy, PartialEq)]
+pub struct HeapUsage {
+    pub used: u64,
+    pub eden: u64,
+    pub survivor: u64,
+    /// Only known when read from a GC log, part of `old` otherwise.
+    pub humongous: Option<u64>,
+    pub old: u64,
+}
+
+impl HeapUsage {
+    pub fn young(&self) -> u64 {
+        self.eden + self.survivor
+    }
+}
+
+/// Events of a GC that do not add up. The affected fields are left at a safe value.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Anomaly {
+    /// No jdk.GarbageCollection, the recording probably started during the GC.
+    MissingGarbageCollection,
+    /// No jdk.GCHeapSummary or jdk.G1HeapSummary, `before` or `after` is None.
+    MissingHeapSummary { when: GCWhen },
+    /// Eden, survivor and humongous add up to more than the used heap, `old` is 0.
+    RegionsExceedHeap {
+        when: GCWhen,
+        used: u64,
+        regions: u64,
+    },
+    /// More bytes were promoted than eden and survivors held before the GC.
+    PromotedExceedsYoung { promoted: u64, young: u64 },
+}
+
+/// A single GC, gathered from the events sharing its `gcId`.
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct GcCycle {
+    pub gc_id: u64,
+    pub start_time: Option<DateTime<Utc>>,
+    /// G1New, G1Old or G1Full.
+    pub name: String,
+    pub cause: String,
+    pub collection_type: CollectionType,
+    /// Name of the last jdk.GCPhasePause, e.g. "GC Pause" or "Pause Cleanup".
+    pub pause_name: String,
+    /// From the start of the GC to its end, concurrent phases included.
+    pub duration_ms: f64,
+    pub sum_of_pauses_ms: f64,
+    pub longest_pause_ms: f64,
+    pub before: Option<HeapUsage>,
+    pub after: Option<HeapUsage>,
+    /// Size of the objects copied to old regions, jdk.PromoteObjectInNewPLAB and
+    /// jdk.PromoteObjectOutsidePLAB are sampled so this is a lower bound.
+    pub promoted: u64,
+    /// Bytes evacuated from the collection set, from jdk.EvacuationInformation.
+    pub copied: Option<u64>,
+    pub tenuring_threshold: Option<u64>,
+    /// Some objects could not be copied, from jdk.EvacuationFailed.
+    pub evacuation_failed: bool,
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl GcCycle {
+    pub fn title(&self) -> String {
+        if let CollectionType::Unknown = self.collection_type {
+            self.pause_name.clone()
+        } else {
+            format!("{:?}", self.collection_type,)
+        }
+    }
+}
+
+/// Heap summary fields as they arrive, from two different events.
+#[derive(Default)]
+struct HeapParts {
+    used: Option<u64>,
+    eden: Option<u64>,
+    survivor: Option<u64>,
+    humongous: Option<u64>,
+}
+
+impl HeapParts {
+    fn usage(&self, when: GCWhen, anomalies: &mut Vec<Anomaly>) -> Option<HeapUsage> {
+        let (Some(used), Some(eden), Some(survivor)) = (self.used, self.eden, self.survivor) else {
+            anomalies.push(Anomaly::MissingHeapSummary { when });
+            return None;
+        };
+        let regions = eden + survivor + self.humongous.unwrap_or(0);
+        let old = used.checked_sub(regions).unwrap_or_else(|| {
+            anomalies.push(Anomaly::RegionsExceedHeap {
+                when,
+                used,
+                regions,
+            });
+            0
+        });
+        Some(HeapUsage {
+            used,
+            eden,
+            survivor,
+            humongous: self.humongous,
+            old,
+        })
+    }
+}
+
+#[derive(Default)]
+struct CycleParts {
+    cycle: GcCycle,
+    has_garbage_collection: bool,
+    before: HeapParts,
+    after: HeapParts,
+}
+
+impl CycleParts {
+    fn heap(&mut self, when: GCWhen) -> &mut HeapParts {
+        match when {
+            GCWhen::Before => &mut self.before,
+            GCWhen::After => &mut self.after,
+        }
+    }
+
+    fn add(&mut self, evt: &JfrEvent) {
+        let cycle = &mut self.cycle;
+        match evt {
+            JfrEvent::GarbageCollection { values } => {
+                self.has_garbage_collection = true;
+                cycle.start_time = Some(values.start_time);
+                cycle.name = values.name.clone();
+                cycle.cause = values.cause.clone();
+                cycle.duration_ms = dur_ms(&values.duration);
+                cycle.sum_of_pauses_ms = dur_ms(&values.sum_of_pauses);
+                cycle.longest_pause_ms = dur_ms(&values.longest_pauses);
+            }
+            JfrEvent::G1GarbageCollection { values } => cycle.collection_type = values.type_,
+            JfrEvent::GCPhasePause { values } => cycle.pause_name = values.name.clone(),
+            JfrEvent::YoungGarbageCollection { values } => {
+                cycle.tenuring_threshold = Some(values.tenuring_threshold)
+            }
+            JfrEvent::PromoteObjectOutsidePLAB { values } if values.tenured => {
+                cycle.promoted += values.object_size
+            }
+            JfrEvent::PromoteObjectInNewPLAB { values } if values.tenured => {
+                cycle.promoted += values.object_size
+            }
+            JfrEvent::EvacuationFailed { .. } => cycle.evacuation_failed = true,
+            JfrEvent::EvacuationInformation { values } => {
+                *cycle.copied.get_or_insert(0) += values.bytes_copied
+            }
+            // a concurrent cycle has a summary around each of its pauses,
+            // keep the first before and the last after
+            JfrEvent::GCHeapSummary { values } => {
+                let heap = self.heap(values.when);
+                if values.when == GCWhen::After || heap.used.is_none() {
+                    heap.used = Some(values.heap_used);
+                }
+            }
+            JfrEvent::G1HeapSummary { values } => {
+                let heap = self.heap(values.when);
+                if values.when == GCWhen::After || heap.eden.is_none() {
+                    heap.eden = Some(values.eden_used);
+                    heap.survivor = Some(values.survivor_used);
+                    heap.humongous = values.humongous_used;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> GcCycle {
+        let mut anomalies = Vec::new();
+        if !self.has_garbage_collection {
+            anomalies.push(Anomaly::MissingGarbageCollection);
+        }
+        let before = self.before.usage(GCWhen::Before, &mut anomalies);
+        let after = self.after.usage(GCWhen::After, &mut anomalies);
+        if let Some(before) = before {
+            if self.cycle.promoted > before.young() {
+                anomalies.push(Anomaly::PromotedExceedsYoung {
+                    promoted: self.cycle.promoted,
+                    young: before.young(),
+                });
+            }
+        }
+        self.cycle.before = before;
+        self.cycle.after = after;
+        self.cycle.anomalies = anomalies;
+        self.cycle
+    }
+}
+
+impl JfrMain {
+    /// One [`GcCycle`] per GC, ordered by `gcId`.
+    pub fn gc_cycles(&self) -> Vec<GcCycle> {
+        let mut gc_id_to_cycle: BTreeMap<u64, CycleParts> = BTreeMap::new();
+        for evt in &self.recording.events {
+            let Some(gc_id) = evt.gc_id() else {
+                continue;
+            };
+            let parts = gc_id_to_cycle.entry(gc_id).or_default();
+            parts.cycle.gc_id = gc_id;
+            parts.add(evt);
+        }
+        gc_id_to_cycle
+            .into_values()
+            .map(CycleParts::finish)
+            .collect()
+    }
+}
@@ -0,0 +1,131 @@
+use std::{collections::BTreeMap, fmt, io};
+
+use serde::Serialize;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The `jfr` tool could not be found, or does not support `print --json`.
+    JfrTool(String),
+    /// `jfr` exited with an error, with its stderr.
+    JfrFailed(String),
+    Json(serde_json::Error),
+    Xml(quick_xml::Error),
+    UnknownFormat(String),
+    /// Not even the first chunk of a recording is complete.
+    Truncated(String),
+    /// An alert rules file that is not valid TOML or has an unknown metric.
+    Rules(toml::de::Error),
+    /// An overlays file that is not valid TOML or has an empty event type.
+    Overlays(toml::de::Error),
+    /// A stored baseline that is missing or cannot be read back.
+    Baseline(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::JfrTool(msg) => write!(f, "{}", msg),
+            Error::JfrFailed(stderr) => write!(f, "jfr failed: {}", stderr.trim()),
+            Error::Json(e) => write!(f, "cannot parse jfr JSON: {}", e),
+            Error::Xml(e) => write!(f, "cannot parse jfr XML: {}", e),
+            Error::UnknownFormat(path) => {
+                write!(f, "{}: not a JFR recording, JSON, XML or GC log", path)
+            }
+            Error::Truncated(msg) => write!(f, "truncated recording: {}", msg),
+            Error::Rules(e) => write!(f, "cannot parse rules: {}", e),
+            Error::Overlays(e) => write!(f, "cannot parse overlays: {}", e),
+            Error::Baseline(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::Xml(e) => Some(e),
+            Error::Rules(e) | Error::Overlays(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(e: quick_xml::Error) -> Self {
+        Error::Xml(e)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Error::Rules(e)
+    }
+}
+
+/// What was skipped while loading a recording.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Diagnostics {
+    /// Count of skipped events by event type and reason.
+    pub skipped: BTreeMap<String, BTreeMap<String, usize>>,
+    /// Set when the end of the recording was missing and the rest was loaded.
+    pub truncated: Option<String>,
+    /// What may be missing although nothing was skipped, e.g. a `jfr` tool older than
+    /// the JDK that recorded.
+    pub warnings: Vec<String>,
+}
+
+impl Diagnostics {
+    pub fn skip(&mut self, event_type: &str, reason: String) {
+        *self
+            .skipped
+            .entry(event_type.to_string())
+            .or_default()
+            .entry(reason)
+            .or_default() += 1;
+    }
+
+    pub fn skipped_events(&self) -> usize {
+        self.skipped.values().flat_map(|r| r.values()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.skipped.is_empty() && self.truncated.is_none() && self.warnings.is_empty()
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for warning in &self.warnings {
+            writeln!(f, "warning: {}", warning)?;
+        }
+        if let Some(truncated) = &self.truncated {
+            writeln!(f, "truncated: {}", truncated)?;
+        }
+        if !self.skipped.is_empty() {
+            writeln!(f, "skipped {} events:", self.skipped_events())?;
+        }
+        for (event_type, reasons) in &self.skipped {
+            for (reason, count) in reasons {
+                writeln!(f, "  {:>6} {}: {}", count, event_type, reason)?;
+            }
+        }
+        Ok(())
+    }
+}
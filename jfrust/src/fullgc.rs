@@ -0,0 +1,385 @@
+use std::fmt;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+
+use crate::{
+    cycle::GcCycle,
+    jfr::{JfrEvent, JfrMain},
+    MB,
+};
+
+/// GCs before a full GC searched for what led to it.
+const LOOKBACK: usize = 10;
+/// Share of the heap in humongous regions that by itself explains a full GC.
+const HUMONGOUS_SHARE: f64 = 0.5;
+
+/// Causes of GCs the application or a tool asked for.
+const EXPLICIT: [&str; 6] = [
+    "System.gc()",
+    "Diagnostic Command",
+    "Heap Dump Initiated GC",
+    "Heap Inspection Initiated GC",
+    "JvmtiEnv ForceGarbageCollection",
+    "WhiteBox Initiated Full GC",
+];
+
+/// Causes of the full GC G1 runs when a young GC could not free enough, named
+/// "G1 Compaction Pause" from JDK 12 on.
+const ALLOCATION_FAILURE: [&str; 2] = ["G1 Compaction Pause", "Allocation Failure"];
+
+/// Why a full GC probably happened, from its cause and the GCs before it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FullGcCause {
+    /// System.gc(), jcmd GC.run or a heap dump.
+    Explicit { cause: String },
+    /// Class metadata reached MetaspaceSize or MaxMetaspaceSize.
+    Metaspace { cause: String },
+    /// Young GCs before it ran out of space to copy to.
+    /// `bytes` not copied is only known from jdk.EvacuationFailed, not from GC logs.
+    EvacuationFailure {
+        gc_ids: Vec<u64>,
+        bytes: Option<u64>,
+    },
+    /// Humongous allocations started GCs before it, or filled most of the heap.
+    HumongousAllocation {
+        gc_ids: Vec<u64>,
+        humongous_before: Option<u64>,
+    },
+    /// A concurrent marking was still running, old regions filled before mixed GCs could start.
+    MarkingNotFinished { marking_gc_id: u64, running_ms: f64 },
+    /// None of the above, the live set may not fit the heap.
+    AllocationFailure { cause: String },
+}
+
+impl fmt::Display for FullGcCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids = |gc_ids: &[u64]| {
+            gc_ids
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            FullGcCause::Explicit { cause } => write!(f, "explicitly requested, {}", cause),
+            FullGcCause::Metaspace { cause } => write!(f, "metaspace full, {}", cause),
+            FullGcCause::EvacuationFailure { gc_ids, bytes } => {
+                write!(f, "evacuation failed in gc {}", ids(gc_ids))?;
+                match bytes {
+                    Some(bytes) => write!(f, ", {} bytes not copied", bytes),
+                    None => Ok(()),
+                }
+            }
+            FullGcCause::HumongousAllocation {
+                gc_ids,
+                humongous_before,
+            } => {
+                write!(f, "humongous allocation")?;
+                if !gc_ids.is_empty() {
+                    write!(f, " started gc {}", ids(gc_ids))?;
+                }
+                if let Some(humongous) = humongous_before {
+                    write!(f, ", {:.1}MB humongous", *humongous as f64 / MB)?;
+                }
+                Ok(())
+            }
+            FullGcCause::MarkingNotFinished {
+                marking_gc_id,
+                running_ms,
+            } => write!(
+                f,
+                "marking gc {} still running after {:.1}ms",
+                marking_gc_id, running_ms
+            ),
+            FullGcCause::AllocationFailure { cause } => write!(
+                f,
+                "{}, with no other cause seen: the live set may not fit the heap",
+                cause
+            ),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FullGc {
+    pub gc_id: u64,
+    pub start_time: Option<DateTime<Utc>>,
+    pub cause: String,
+    pub pause_ms: f64,
+    pub used_before: Option<u64>,
+    pub used_after: Option<u64>,
+    pub causes: Vec<FullGcCause>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct FullGcReport {
+    pub gcs: usize,
+    pub pause_ms: f64,
+    pub full_gcs: Vec<FullGc>,
+}
+
+/// G1Full in JFR, or a GC log pause G1 ran as a compaction.
+fn is_full(cycle: &GcCycle) -> bool {
+    cycle.name == "G1Full" || cycle.cause == ALLOCATION_FAILURE[0]
+}
+
+/// `before` are the GCs since the previous full GC, `marking` the latest concurrent marking.
+fn explain(
+    full: &GcCycle,
+    before: &[GcCycle],
+    marking: Option<&GcCycle>,
+    failed_bytes: &dyn Fn(u64) -> Option<u64>,
+) -> Vec<FullGcCause> {
+    let mut causes = Vec::new();
+    let cause = full.cause.clone();
+    if EXPLICIT.contains(&full.cause.as_str()) {
+        return vec![FullGcCause::Explicit { cause }];
+    }
+    if full.cause.starts_with("Metadata GC") {
+        return vec![FullGcCause::Metaspace { cause }];
+    }
+    let failed = before
+        .iter()
+        .filter(|cycle| cycle.evacuation_failed)
+        .map(|cycle| cycle.gc_id)
+        .collect::<Vec<_>>();
+    if !failed.is_empty() {
+        causes.push(FullGcCause::EvacuationFailure {
+            bytes: failed
+                .iter()
+                .filter_map(|gc_id| failed_bytes(*gc_id))
+                .reduce(|a, b| a + b),
+            gc_ids: failed,
+        });
+    }
+    let humongous = before
+        .iter()
+        .chain([full])
+        .filter(|cycle| cycle.cause == "G1 Humongous Allocation")
+        .map(|cycle| cycle.gc_id)
+        .collect::<Vec<_>>();
+    let humongous_before = full.before.and_then(|before| before.humongous);
+    let humongous_heap = full
+        .before
+        .zip(humongous_before)
+        .is_some_and(|(before, humongous)| {
+            humongous as f64 >= before.used as f64 * HUMONGOUS_SHARE
+        });
+    if !humongous.is_empty() || humongous_heap {
+        causes.push(FullGcCause::HumongousAllocation {
+            gc_ids: humongous,
+            humongous_before,
+        });
+    }
+    // only a marking that had old regions fill up explains a failed allocation
+    let allocation_failed = ALLOCATION_FAILURE.contains(&full.cause.as_str());
+    if let (Some(start), Some(marking)) = (full.start_time, marking.filter(|_| allocation_failed)) {
+        if let Some(marking_start) = marking.start_time {
+            let marking_end =
+                marking_start + TimeDelta::microseconds((marking.duration_ms * 1000f64) as i64);
+            if marking_end >= start {
+                causes.push(FullGcCause::MarkingNotFinished {
+                    marking_gc_id: marking.gc_id,
+                    running_ms: (start - marking_start).num_microseconds().unwrap_or(0) as f64
+                        / 1000f64,
+                });
+            }
+        }
+    }
+    if causes.is_empty() {
+        causes.push(FullGcCause::AllocationFailure { cause });
+    }
+    causes
+}
+
+pub fn full_gcs(jfr: &JfrMain) -> FullGcReport {
+    let failed_bytes = |gc_id: u64| {
+        jfr.recording
+            .events
+            .iter()
+            .filter_map(|evt| match evt {
+                JfrEvent::EvacuationFailed { values } if values.gc_id == gc_id => {
+                    Some(values.evacuation_failed.as_ref()?.total_size)
+                }
+                _ => None,
+            })
+            .reduce(|a, b| a + b)
+    };
+    let mut cycles = jfr.gc_cycles();
+    cycles.sort_by_key(|cycle| (cycle.start_time, cycle.gc_id));
+    let mut report = FullGcReport {
+        gcs: cycles.len(),
+        ..Default::default()
+    };
+    let mut since_full = 0;
+    for (ix, cycle) in cycles.iter().enumerate() {
+        if !is_full(cycle) {
+            continue;
+        }
+        // what happened since the previous full GC, at most LOOKBACK GCs back
+        let before = &cycles[since_full.max(ix.saturating_sub(LOOKBACK))..ix];
+        let marking = cycles[..ix]
+            .iter()
+            .rev()
+            .find(|cycle| cycle.name == "G1Old");
+        since_full = ix + 1;
+        report.pause_ms += cycle.sum_of_pauses_ms;
+        report.full_gcs.push(FullGc {
+            gc_id: cycle.gc_id,
+            start_time: cycle.start_time,
+            cause: cycle.cause.clone(),
+            pause_ms: cycle.sum_of_pauses_ms,
+            used_before: cycle.before.map(|heap| heap.used),
+            used_after: cycle.after.map(|heap| heap.used),
+            causes: explain(cycle, before, marking, &failed_bytes),
+        });
+    }
+    report
+}
+
+impl fmt::Display for FullGcReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} full GCs of {}, {:.1}ms of pauses",
+            self.full_gcs.len(),
+            self.gcs,
+            self.pause_ms
+        )?;
+        let mb = |bytes: Option<u64>| {
+            bytes
+                .map(|bytes| format!("{:.1}MB", bytes as f64 / MB))
+                .unwrap_or_else(|| "-".to_string())
+        };
+        for full in &self.full_gcs {
+            writeln!(
+                f,
+                "gc {} ({}): {}->{} in {:.1}ms",
+                full.gc_id,
+                full.cause,
+                mb(full.used_before),
+                mb(full.used_after),
+                full.pause_ms
+            )?;
+            for cause in &full.causes {
+                writeln!(f, "  {}", cause)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gclog;
+
+    /// Causes of each full GC of a GC log.
+    fn causes(log: &str) -> Vec<Vec<FullGcCause>> {
+        let jfr = gclog::parse(log.as_bytes()).unwrap();
+        full_gcs(&jfr)
+            .full_gcs
+            .into_iter()
+            .map(|full| full.causes)
+            .collect()
+    }
+
+    #[test]
+    fn explicit() {
+        let log = "\
+[1.000s][info ][gc] GC(0) Pause Young (Normal) (G1 Evacuation Pause) (Evacuation Failure) 95M->94M(96M) 8.000ms
+[2.000s][info ][gc] GC(1) Pause Full (System.gc()) 50M->10M(96M) 100.000ms
+";
+        assert_eq!(
+            causes(log),
+            [[FullGcCause::Explicit {
+                cause: "System.gc()".to_string()
+            }]]
+        );
+    }
+
+    #[test]
+    fn metaspace() {
+        let log = "\
+[2.000s][info ][gc] GC(1) Pause Full (Metadata GC Clear Soft References) 50M->10M(96M) 100.000ms
+";
+        assert_eq!(
+            causes(log),
+            [[FullGcCause::Metaspace {
+                cause: "Metadata GC Clear Soft References".to_string()
+            }]]
+        );
+    }
+
+    #[test]
+    fn evacuation_failure() {
+        let log = "\
+[1.000s][info ][gc] GC(0) Pause Young (Normal) (G1 Evacuation Pause) 60M->20M(96M) 8.000ms
+[1.500s][info ][gc] GC(1) Pause Young (Normal) (G1 Evacuation Pause) (Evacuation Failure) 95M->94M(96M) 8.000ms
+[2.000s][info ][gc] GC(2) Pause Full (G1 Compaction Pause) 94M->30M(96M) 100.000ms
+";
+        assert_eq!(
+            causes(log),
+            [[FullGcCause::EvacuationFailure {
+                gc_ids: vec![1],
+                bytes: None
+            }]]
+        );
+    }
+
+    #[test]
+    fn humongous_allocation() {
+        let log = "\
+[1.000s][info ][gc] GC(0) Pause Young (Normal) (G1 Humongous Allocation) 90M->80M(96M) 8.000ms
+[2.000s][info ][gc] GC(1) Pause Full (G1 Compaction Pause) 94M->30M(96M) 100.000ms
+";
+        assert_eq!(
+            causes(log),
+            [[FullGcCause::HumongousAllocation {
+                gc_ids: vec![0],
+                humongous_before: None
+            }]]
+        );
+    }
+
+    #[test]
+    fn marking_not_finished() {
+        let log = "\
+[1.000s][info ][gc] GC(0) Pause Young (Concurrent Start) (G1 Evacuation Pause) 70M->60M(96M) 3.000ms
+[1.000s][info ][gc] GC(1) Concurrent Mark Cycle
+[2.000s][info ][gc] GC(2) Pause Full (G1 Compaction Pause) 94M->30M(96M) 100.000ms
+[3.000s][info ][gc] GC(1) Concurrent Mark Cycle 2000.000ms
+";
+        let [causes] = &causes(log)[..] else {
+            panic!("one full GC");
+        };
+        let [FullGcCause::MarkingNotFinished {
+            marking_gc_id,
+            running_ms,
+        }] = causes[..]
+        else {
+            panic!("{:?}", causes);
+        };
+        assert_eq!(marking_gc_id, 1);
+        assert!((running_ms - 900f64).abs() < 1f64, "{running_ms}");
+    }
+
+    #[test]
+    fn no_cause_seen() {
+        // the marking finished before the full GC
+        let log = "\
+[1.000s][info ][gc] GC(0) Pause Young (Concurrent Start) (G1 Evacuation Pause) 70M->60M(96M) 3.000ms
+[1.000s][info ][gc] GC(1) Concurrent Mark Cycle
+[1.500s][info ][gc] GC(1) Concurrent Mark Cycle 500.000ms
+[2.000s][info ][gc] GC(2) Pause Full (G1 Compaction Pause) 94M->30M(96M) 100.000ms
+";
+        assert_eq!(
+            causes(log),
+            [[FullGcCause::AllocationFailure {
+                cause: "G1 Compaction Pause".to_string()
+            }]]
+        );
+    }
+}
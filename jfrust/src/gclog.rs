@@ -0,0 +1,830 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde_json::Value;
+
+use crate::jfr::{
+    CollectionType, EvacuationFailed, Flag, G1GarbageCollection, G1HeapSummary, GCHeapSummary,
+    GCPhasePause, GCWhen, GarbageCollection, JfrEvent, JfrMain, JfrRecording, OldGarbageCollection,
+    TenuringDistribution, VirtualSpace, YoungGarbageCollection,
+};
+
+/*
+Unified logging output of `-Xlog:gc*,gc+age=trace`:
+
+[0.919s][info ][gc,start       ] GC(5) Pause Young (Normal) (G1 Evacuation Pause)
+[0.919s][debug][gc,age         ] GC(5) Desired survivor size 3670016 bytes, new threshold 15 (max threshold 15)
+[0.924s][info ][gc,phases      ] GC(5)   Evacuate Collection Set: 4.1ms
+[0.924s][trace][gc,age         ] GC(5) - age   1:    1231400 bytes,    1231400 total
+[0.924s][info ][gc,heap        ] GC(5) Eden regions: 53->0(52)
+[0.924s][info ][gc,heap        ] GC(5) Survivor regions: 3->4(7)
+[0.924s][info ][gc,heap        ] GC(5) Humongous regions: 0->0
+[0.924s][debug][gc,heap        ] GC(5)  garbage-first heap   total 98304K, used 6700K [0x00000000f8000000, 0x0000000100000000)
+[0.924s][info ][gc             ] GC(5) Pause Young (Normal) (G1 Evacuation Pause) 58M->6M(96M) 4.875ms
+*/
+
+const REGION_SIZE: &str = "region size: ";
+
+/// Whether `head`, the first bytes of a file, look like unified JVM logging output.
+pub fn looks_like_gc_log(head: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(head);
+    head.lines()
+        .find(|line| !line.trim().is_empty())
+        .is_some_and(|line| line.starts_with('[') && line.contains("][gc"))
+}
+
+/// Builds the JFR events jfrust knows about from a `-Xlog:gc*` log.
+pub fn parse<R: BufRead>(reader: R) -> io::Result<JfrMain> {
+    let mut parser = GcLogParser {
+        region_size: 1024 * 1024,
+        ..Default::default()
+    };
+    for line in reader.lines() {
+        parser.line(&line?);
+    }
+    Ok(JfrMain {
+        recording: JfrRecording {
+            events: parser.events,
+            ..Default::default()
+        },
+    })
+}
+
+#[derive(Default)]
+struct Pause {
+    start_time: DateTime<Utc>,
+    heap_before: Option<u64>,
+    heap_after: Option<u64>,
+    in_heap_after: bool,
+    eden: Option<(u64, u64, u64)>,
+    survivor: Option<(u64, u64)>,
+    humongous: Option<(u64, u64)>,
+    tenuring_threshold: Option<u64>,
+    /// "To-space exhausted", or "(Evacuation Failure)" after the cause from JDK 20.
+    evacuation_failed: bool,
+    phases: Vec<(String, f64)>,
+}
+
+#[derive(Default)]
+struct ConcurrentCycle {
+    start_time: DateTime<Utc>,
+    cause: String,
+    pauses_ms: Vec<f64>,
+}
+
+#[derive(Default)]
+struct GcLogParser {
+    region_size: u64,
+    /// From "Heap Max Capacity: 96M", JDK 17 and later.
+    max_heap: Option<u64>,
+    events: Vec<JfrEvent>,
+    pauses: HashMap<u64, Pause>,
+    cycles: HashMap<u64, ConcurrentCycle>,
+    concurrent_start_cause: String,
+}
+
+impl GcLogParser {
+    fn line(&mut self, line: &str) {
+        let mut rest = line;
+        let mut decorations = Vec::new();
+        while let Some(stripped) = rest.strip_prefix('[') {
+            let Some((decoration, tail)) = stripped.split_once(']') else {
+                return;
+            };
+            decorations.push(decoration.trim());
+            rest = tail;
+        }
+        let Some(ts) = decorations.iter().find_map(|d| parse_decoration_time(d)) else {
+            return;
+        };
+        let msg = rest.strip_prefix(' ').unwrap_or(rest);
+        let Some(gc_msg) = msg.strip_prefix("GC(") else {
+            // "Heap region size: 1M" up to JDK 16, "Heap Region Size: 1M" from JDK 17
+            if let Some(ix) = msg.to_ascii_lowercase().find(REGION_SIZE) {
+                let size = &msg[ix + REGION_SIZE.len()..];
+                self.region_size = parse_size(size.trim()).unwrap_or(self.region_size);
+                self.flag(ts, "G1HeapRegionSize", self.region_size);
+            } else if let Some(size) = msg.strip_prefix("Heap Max Capacity: ") {
+                self.max_heap = parse_size(size.trim());
+                if let Some(max_heap) = self.max_heap {
+                    self.flag(ts, "MaxHeapSize", max_heap);
+                }
+            }
+            return;
+        };
+        let Some((gc_id, msg)) = gc_msg.split_once(')') else {
+            return;
+        };
+        let Ok(gc_id) = gc_id.parse::<u64>() else {
+            return;
+        };
+        let msg = msg.strip_prefix(' ').unwrap_or(msg);
+        let is_phase = decorations.iter().any(|d| d.starts_with("gc,phases"));
+        self.gc_line(gc_id, ts, msg, is_phase);
+    }
+
+    /// The flags gc+init logs, as the jdk.UnsignedLongFlag a recording would have.
+    fn flag(&mut self, ts: DateTime<Utc>, name: &str, value: u64) {
+        self.events.push(JfrEvent::UnsignedLongFlag {
+            values: Flag {
+                start_time: ts,
+                name: name.to_string(),
+                value: Value::from(value),
+                origin: "GC log".to_string(),
+            },
+        });
+    }
+
+    fn gc_line(&mut self, gc_id: u64, ts: DateTime<Utc>, msg: &str, is_phase: bool) {
+        let trimmed = msg.trim();
+        if is_phase {
+            // only the first level phases, "  Evacuate Collection Set: 4.1ms"
+            if msg.starts_with("  ") && !msg.starts_with("   ") {
+                if let Some((name, ms)) = trimmed.rsplit_once(": ") {
+                    if let Some(ms) = parse_ms(ms) {
+                        if let Some(pause) = self.pauses.get_mut(&gc_id) {
+                            pause.phases.push((name.to_string(), ms));
+                        }
+                    }
+                }
+            }
+            return;
+        }
+        if trimmed.starts_with("Pause ") {
+            match split_pause_end(trimmed) {
+                Some((name, heap, ms)) => self.pause_end(gc_id, ts, name, heap, ms),
+                None => {
+                    self.pauses.insert(
+                        gc_id,
+                        Pause {
+                            start_time: ts,
+                            ..Default::default()
+                        },
+                    );
+                }
+            }
+        } else if trimmed.starts_with("Concurrent Cycle")
+            || trimmed.starts_with("Concurrent Mark Cycle")
+            || trimmed.starts_with("Concurrent Undo Cycle")
+        {
+            match trimmed.rsplit_once(' ').and_then(|(_, ms)| parse_ms(ms)) {
+                Some(ms) => self.cycle_end(gc_id, ts, ms),
+                None => {
+                    self.cycles.insert(
+                        gc_id,
+                        ConcurrentCycle {
+                            start_time: ts,
+                            cause: self.concurrent_start_cause.clone(),
+                            pauses_ms: Vec::new(),
+                        },
+                    );
+                }
+            }
+        } else if let Some(transition) = trimmed.strip_prefix("Eden regions: ") {
+            let region_size = self.region_size;
+            if let (Some(pause), Some((before, after, total))) =
+                (self.pauses.get_mut(&gc_id), parse_regions(transition))
+            {
+                pause.eden = Some((
+                    before * region_size,
+                    after * region_size,
+                    total.unwrap_or(before) * region_size,
+                ));
+            }
+        } else if let Some(transition) = trimmed.strip_prefix("Survivor regions: ") {
+            let region_size = self.region_size;
+            if let (Some(pause), Some((before, after, _))) =
+                (self.pauses.get_mut(&gc_id), parse_regions(transition))
+            {
+                pause.survivor = Some((before * region_size, after * region_size));
+            }
+        } else if let Some(transition) = trimmed.strip_prefix("Humongous regions: ") {
+            let region_size = self.region_size;
+            if let (Some(pause), Some((before, after, _))) =
+                (self.pauses.get_mut(&gc_id), parse_regions(transition))
+            {
+                pause.humongous = Some((before * region_size, after * region_size));
+            }
+        } else if trimmed == "To-space exhausted" {
+            if let Some(pause) = self.pauses.get_mut(&gc_id) {
+                pause.evacuation_failed = true;
+            }
+        } else if trimmed.starts_with("Heap after GC") {
+            if let Some(pause) = self.pauses.get_mut(&gc_id) {
+                pause.in_heap_after = true;
+            }
+        } else if let Some(heap) = trimmed.strip_prefix("garbage-first heap") {
+            // " garbage-first heap   total 98304K, used 59728K [0x..., 0x...)"
+            let used = heap
+                .split_once("used ")
+                .and_then(|(_, used)| used.split_whitespace().next())
+                .and_then(|used| parse_size(used.trim_end_matches(',')));
+            if let (Some(pause), Some(used)) = (self.pauses.get_mut(&gc_id), used) {
+                if pause.in_heap_after {
+                    pause.heap_after = Some(used);
+                } else {
+                    pause.heap_before = Some(used);
+                }
+            }
+        } else if let Some(region_size) = trimmed
+            .strip_prefix("region size ")
+            .and_then(|rest| rest.split(',').next())
+            .and_then(parse_size)
+        {
+            self.region_size = region_size;
+        } else if let Some((_, threshold)) = trimmed.split_once("new threshold ") {
+            let threshold = threshold
+                .split_whitespace()
+                .next()
+                .and_then(|t| t.parse().ok());
+            if let Some(pause) = self.pauses.get_mut(&gc_id) {
+                pause.tenuring_threshold = threshold;
+            }
+        } else if let Some(age) = trimmed.strip_prefix("- age") {
+            // "- age   1:    1231400 bytes,    1231400 total"
+            let Some((age, size)) = age.split_once(':') else {
+                return;
+            };
+            let size = size.split_whitespace().next().and_then(|s| s.parse().ok());
+            if let (Ok(age), Some(size)) = (age.trim().parse(), size) {
+                self.events.push(JfrEvent::TenuringDistribution {
+                    values: TenuringDistribution {
+                        start_time: ts,
+                        gc_id,
+                        age,
+                        size,
+                    },
+                });
+            }
+        }
+    }
+
+    fn pause_end(
+        &mut self,
+        gc_id: u64,
+        ts: DateTime<Utc>,
+        name: &str,
+        heap: HeapTransition,
+        ms: f64,
+    ) {
+        let pause = self.pauses.remove(&gc_id).unwrap_or_else(|| Pause {
+            start_time: ts - ms_to_delta(ms),
+            ..Default::default()
+        });
+        let start_time = pause.start_time;
+        let (kind, mut groups) = split_groups(name);
+        let failed = groups.len();
+        groups.retain(|group| !group.starts_with("Evacuation Failure"));
+        if pause.evacuation_failed || groups.len() < failed {
+            self.events.push(JfrEvent::EvacuationFailed {
+                values: EvacuationFailed {
+                    start_time,
+                    gc_id,
+                    evacuation_failed: None,
+                },
+            });
+        }
+        if kind == "Pause Remark" || kind == "Pause Cleanup" {
+            if let Some(cycle) = self.cycles.get_mut(&gc_id) {
+                cycle.pauses_ms.push(ms);
+            }
+            self.push_phase_pause(gc_id, start_time, kind, ms);
+            return;
+        }
+        let cause = groups.last().cloned().unwrap_or_default();
+        let full = kind == "Pause Full";
+        let collection_type = match (kind, groups.first().map(String::as_str)) {
+            ("Pause Initial Mark", _) => CollectionType::ConcurrentStart,
+            ("Pause Mixed", _) => CollectionType::Mixed,
+            ("Pause Young", Some("Normal")) => CollectionType::Normal,
+            ("Pause Young", Some("Concurrent Start")) => CollectionType::ConcurrentStart,
+            ("Pause Young", Some("Initial Mark")) => CollectionType::ConcurrentStart,
+            ("Pause Young", Some("Prepare Mixed")) => CollectionType::PrepareMixed,
+            ("Pause Young", Some("Mixed")) => CollectionType::Mixed,
+            ("Pause Young", _) if groups.len() < 2 => CollectionType::Normal,
+            _ => CollectionType::Unknown,
+        };
+        if collection_type == CollectionType::ConcurrentStart {
+            self.concurrent_start_cause = cause.clone();
+        }
+        let duration = ms_to_duration(ms);
+        let heap_before = pause.heap_before.unwrap_or(heap.before);
+        let heap_after = pause.heap_after.unwrap_or(heap.after);
+        for (when, ts, heap_used) in [
+            (GCWhen::Before, start_time, heap_before),
+            (GCWhen::After, ts, heap_after),
+        ] {
+            self.events.push(JfrEvent::GCHeapSummary {
+                values: GCHeapSummary {
+                    start_time: ts,
+                    when,
+                    gc_id,
+                    heap_used,
+                    heap_space: self.max_heap.map(|reserved_size| VirtualSpace {
+                        committed_size: heap.committed.unwrap_or(reserved_size),
+                        reserved_size,
+                    }),
+                },
+            });
+        }
+        if let (Some((eden_before, eden_after, eden_total)), Some((surv_before, surv_after))) =
+            (pause.eden, pause.survivor)
+        {
+            let (humongous_before, humongous_after) = match pause.humongous {
+                Some((before, after)) => (Some(before), Some(after)),
+                None => (None, None),
+            };
+            for (when, ts, eden_used, survivor_used, humongous_used) in [
+                (
+                    GCWhen::Before,
+                    start_time,
+                    eden_before,
+                    surv_before,
+                    humongous_before,
+                ),
+                (GCWhen::After, ts, eden_after, surv_after, humongous_after),
+            ] {
+                self.events.push(JfrEvent::G1HeapSummary {
+                    values: G1HeapSummary {
+                        start_time: ts,
+                        when,
+                        gc_id,
+                        eden_used,
+                        eden_total,
+                        survivor_used,
+                        humongous_used,
+                        number_of_regions: None,
+                    },
+                });
+            }
+        }
+        self.events.push(JfrEvent::GarbageCollection {
+            values: GarbageCollection {
+                start_time,
+                duration,
+                gc_id,
+                name: if full { "G1Full" } else { "G1New" }.to_string(),
+                cause,
+                sum_of_pauses: duration,
+                longest_pauses: duration,
+            },
+        });
+        if full {
+            self.events.push(JfrEvent::OldGarbageCollection {
+                values: OldGarbageCollection {
+                    start_time,
+                    duration,
+                    gc_id,
+                },
+            });
+        } else {
+            self.events.push(JfrEvent::YoungGarbageCollection {
+                values: YoungGarbageCollection {
+                    start_time,
+                    duration,
+                    gc_id,
+                    tenuring_threshold: pause.tenuring_threshold.unwrap_or_default(),
+                },
+            });
+            self.events.push(JfrEvent::G1GarbageCollection {
+                values: G1GarbageCollection {
+                    start_time,
+                    gc_id,
+                    type_: collection_type,
+                },
+            });
+        }
+        self.push_phase_pause(gc_id, start_time, "GC Pause", ms);
+        let mut phase_start = start_time;
+        for (name, ms) in pause.phases {
+            self.events.push(JfrEvent::GCPhasePauseLevel1 {
+                values: GCPhasePause {
+                    start_time: phase_start,
+                    duration: ms_to_duration(ms),
+                    event_thread: None,
+                    gc_id,
+                    name,
+                },
+            });
+            phase_start += ms_to_delta(ms);
+        }
+    }
+
+    fn push_phase_pause(&mut self, gc_id: u64, start_time: DateTime<Utc>, name: &str, ms: f64) {
+        self.events.push(JfrEvent::GCPhasePause {
+            values: GCPhasePause {
+                start_time,
+                duration: ms_to_duration(ms),
+                event_thread: None,
+                gc_id,
+                name: name.to_string(),
+            },
+        });
+    }
+
+    fn cycle_end(&mut self, gc_id: u64, ts: DateTime<Utc>, ms: f64) {
+        let cycle = self
+            .cycles
+            .remove(&gc_id)
+            .unwrap_or_else(|| ConcurrentCycle {
+                start_time: ts - ms_to_delta(ms),
+                ..Default::default()
+            });
+        let duration = ms_to_duration(ms);
+        let sum_of_pauses = cycle.pauses_ms.iter().sum::<f64>();
+        let longest_pause = cycle.pauses_ms.iter().copied().fold(0f64, f64::max);
+        self.events.push(JfrEvent::GarbageCollection {
+            values: GarbageCollection {
+                start_time: cycle.start_time,
+                duration,
+                gc_id,
+                name: "G1Old".to_string(),
+                cause: cycle.cause,
+                sum_of_pauses: ms_to_duration(sum_of_pauses),
+                longest_pauses: ms_to_duration(longest_pause),
+            },
+        });
+        self.events.push(JfrEvent::OldGarbageCollection {
+            values: OldGarbageCollection {
+                start_time: cycle.start_time,
+                duration,
+                gc_id,
+            },
+        });
+    }
+}
+
+/// Wall clock decorations (`time`, `utctime`) are preferred, uptime decorations
+/// are taken as an offset from the epoch.
+fn parse_decoration_time(decoration: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_str(decoration, "%Y-%m-%dT%H:%M:%S%.f%z") {
+        return Some(dt.with_timezone(&Utc));
+    }
+    let nanos = if let Some(ms) = decoration.strip_suffix("ms") {
+        ms.parse::<f64>().ok()? * 1e6
+    } else if let Some(ns) = decoration.strip_suffix("ns") {
+        ns.parse::<f64>().ok()?
+    } else if let Some(s) = decoration.strip_suffix('s') {
+        s.parse::<f64>().ok()? * 1e9
+    } else {
+        return None;
+    };
+    Some(DateTime::UNIX_EPOCH + TimeDelta::nanoseconds(nanos as i64))
+}
+
+/// "58M->6M(96M)", the heap used before and after a pause and committed after it.
+struct HeapTransition {
+    before: u64,
+    after: u64,
+    committed: Option<u64>,
+}
+
+/// Splits "Pause Young (Normal) (G1 Evacuation Pause) 58M->6M(96M) 4.875ms".
+fn split_pause_end(msg: &str) -> Option<(&str, HeapTransition, f64)> {
+    let (rest, ms) = msg.rsplit_once(' ')?;
+    let ms = parse_ms(ms)?;
+    let (name, transition) = rest.rsplit_once(' ')?;
+    let (before, after) = transition.split_once("->")?;
+    let (after, committed) = after.split_once('(').unwrap_or((after, ""));
+    let committed = parse_size(committed.trim_end_matches(')'));
+    let heap = HeapTransition {
+        before: parse_size(before)?,
+        after: parse_size(after)?,
+        committed,
+    };
+    Some((name, heap, ms))
+}
+
+/// Splits "Pause Full (System.gc())" into the pause kind and its parenthesized groups.
+fn split_groups(name: &str) -> (&str, Vec<String>) {
+    let kind = name.split(" (").next().unwrap_or(name);
+    let mut groups = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in name[kind.len()..].chars() {
+        match c {
+            '(' => {
+                if depth > 0 {
+                    current.push(c);
+                }
+                depth += 1;
+            }
+            ')' => {
+                depth -= 1;
+                if depth > 0 {
+                    current.push(c);
+                } else {
+                    groups.push(std::mem::take(&mut current));
+                }
+            }
+            _ if depth > 0 => current.push(c),
+            _ => {}
+        }
+    }
+    (kind, groups)
+}
+
+/// Parses "53->0(52)" or "2->2".
+fn parse_regions(transition: &str) -> Option<(u64, u64, Option<u64>)> {
+    let (before, after) = transition.trim().split_once("->")?;
+    let (after, total) = match after.split_once('(') {
+        Some((after, total)) => (after, total.trim_end_matches(')').parse().ok()),
+        None => (after, None),
+    };
+    Some((before.parse().ok()?, after.parse().ok()?, total))
+}
+
+fn parse_ms(ms: &str) -> Option<f64> {
+    ms.strip_suffix("ms")?.parse().ok()
+}
+
+fn parse_size(size: &str) -> Option<u64> {
+    let (num, unit) = size.split_at(size.find(|c: char| c.is_ascii_alphabetic())?);
+    let num = num.parse::<f64>().ok()?;
+    let unit = match unit {
+        "B" => 1u64,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return None,
+    };
+    Some((num * unit as f64) as u64)
+}
+
+fn ms_to_delta(ms: f64) -> TimeDelta {
+    TimeDelta::nanoseconds((ms * 1e6) as i64)
+}
+
+fn ms_to_duration(ms: f64) -> Duration {
+    Duration::from_secs_f64(ms.max(0f64) / 1000f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cycle::GcCycle;
+
+    fn parse_log(log: &str) -> Vec<JfrEvent> {
+        parse(log.as_bytes()).unwrap().recording.events
+    }
+
+    /// Eden before a young GC, with `init` logging the region size.
+    fn eden_before(init: &str) -> Option<u64> {
+        let log = format!(
+            "{init}
+[0.919s][info ][gc,start       ] GC(5) Pause Young (Normal) (G1 Evacuation Pause)
+[0.924s][info ][gc,heap        ] GC(5) Eden regions: 53->0(52)
+[0.924s][info ][gc,heap        ] GC(5) Survivor regions: 3->4(7)
+[0.924s][info ][gc             ] GC(5) Pause Young (Normal) (G1 Evacuation Pause) 58M->6M(96M) 4.875ms"
+        );
+        parse_log(&log).iter().find_map(|evt| match evt {
+            JfrEvent::G1HeapSummary { values } if values.when == GCWhen::Before => {
+                Some(values.eden_used)
+            }
+            _ => None,
+        })
+    }
+
+    fn region_size_flag(init: &str) -> Option<Value> {
+        parse_log(init).into_iter().find_map(|evt| match evt {
+            JfrEvent::UnsignedLongFlag { values } if values.name == "G1HeapRegionSize" => {
+                Some(values.value)
+            }
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn region_size_jdk11() {
+        let init = "[0.004s][info][gc,heap] Heap region size: 4M";
+        assert_eq!(region_size_flag(init), Some(Value::from(4u64 << 20)));
+        assert_eq!(eden_before(init), Some(53 * (4 << 20)));
+    }
+
+    #[test]
+    fn region_size_jdk17() {
+        let init = "[0.004s][info ][gc,init       ] Heap Region Size: 8M";
+        assert_eq!(region_size_flag(init), Some(Value::from(8u64 << 20)));
+        assert_eq!(eden_before(init), Some(53 * (8 << 20)));
+    }
+
+    #[test]
+    fn region_size_default() {
+        let init = "[0.004s][info ][gc,init       ] CPUs: 1 total, 1 available";
+        assert_eq!(region_size_flag(init), None);
+        assert_eq!(eden_before(init), Some(53 << 20));
+    }
+
+    const LOG: &str = "\
+[0.003s][info ][gc,init       ] Heap Region Size: 1M
+[0.003s][info ][gc,init       ] Heap Max Capacity: 128M
+[0.919s][info ][gc,start       ] GC(5) Pause Young (Normal) (G1 Evacuation Pause)
+[0.919s][debug][gc,age         ] GC(5) Desired survivor size 3670016 bytes, new threshold 7 (max threshold 15)
+[0.924s][info ][gc,phases      ] GC(5)   Evacuate Collection Set: 4.1ms
+[0.924s][info ][gc,phases      ] GC(5)     Ext Root Scanning (ms):   Min:  0.1
+[0.924s][info ][gc,phases      ] GC(5)   Post Evacuate Collection Set: 0.5ms
+[0.924s][trace][gc,age         ] GC(5) - age   1:    1231400 bytes,    1231400 total
+[0.924s][trace][gc,age         ] GC(5) - age   2:     524288 bytes,    1755688 total
+[0.924s][info ][gc,heap        ] GC(5) Eden regions: 53->0(52)
+[0.924s][info ][gc,heap        ] GC(5) Survivor regions: 3->4(7)
+[0.924s][info ][gc,heap        ] GC(5) Humongous regions: 2->1
+[0.924s][info ][gc             ] GC(5) Pause Young (Normal) (G1 Evacuation Pause) 58M->6M(96M) 4.875ms
+[1.100s][info ][gc,start       ] GC(6) Pause Young (Concurrent Start) (G1 Humongous Allocation)
+[1.103s][info ][gc             ] GC(6) Pause Young (Concurrent Start) (G1 Humongous Allocation) 70M->20M(96M) 3.250ms
+[1.103s][info ][gc             ] GC(7) Concurrent Mark Cycle
+[1.150s][info ][gc             ] GC(7) Pause Remark 30M->30M(96M) 1.500ms
+[1.160s][info ][gc             ] GC(7) Pause Cleanup 30M->30M(96M) 0.250ms
+[1.170s][info ][gc             ] GC(7) Concurrent Mark Cycle 67.000ms
+[2.000s][info ][gc,start       ] GC(8) Pause Young (Normal) (G1 Evacuation Pause)
+[2.002s][info ][gc             ] GC(8) To-space exhausted
+[2.010s][info ][gc             ] GC(8) Pause Young (Normal) (G1 Evacuation Pause) 95M->95M(96M) 10.000ms
+[2.100s][info ][gc             ] GC(9) Pause Young (Normal) (G1 Evacuation Pause) (Evacuation Failure) 95M->94M(96M) 8.000ms
+[2.200s][info ][gc,start       ] GC(10) Pause Full (System.gc())
+[2.300s][info ][gc             ] GC(10) Pause Full (System.gc()) 94M->12M(96M) 100.125ms
+";
+
+    fn cycles() -> Vec<GcCycle> {
+        parse(LOG.as_bytes()).unwrap().gc_cycles()
+    }
+
+    #[test]
+    fn young_pause() {
+        let cycles = cycles();
+        let gc = &cycles[0];
+        assert_eq!(gc.gc_id, 5);
+        assert_eq!(gc.name, "G1New");
+        assert_eq!(gc.cause, "G1 Evacuation Pause");
+        assert_eq!(gc.collection_type, CollectionType::Normal);
+        assert_eq!(gc.longest_pause_ms, 4.875);
+        assert_eq!(gc.tenuring_threshold, Some(7));
+        assert!(!gc.evacuation_failed);
+        assert!(gc.anomalies.is_empty(), "{:?}", gc.anomalies);
+        let before = gc.before.unwrap();
+        assert_eq!(before.used, 58 << 20);
+        assert_eq!(before.eden, 53 << 20);
+        assert_eq!(before.survivor, 3 << 20);
+        assert_eq!(before.humongous, Some(2 << 20));
+        let after = gc.after.unwrap();
+        assert_eq!(after.used, 6 << 20);
+        assert_eq!(after.eden, 0);
+        assert_eq!(after.survivor, 4 << 20);
+    }
+
+    #[test]
+    fn first_level_phases_and_ages() {
+        let jfr = parse(LOG.as_bytes()).unwrap();
+        let phases = jfr
+            .recording
+            .events
+            .iter()
+            .filter_map(|evt| match evt {
+                JfrEvent::GCPhasePauseLevel1 { values } => Some(values.name.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            phases,
+            ["Evacuate Collection Set", "Post Evacuate Collection Set"]
+        );
+        let ages = jfr
+            .recording
+            .events
+            .iter()
+            .filter_map(|evt| match evt {
+                JfrEvent::TenuringDistribution { values } => Some((values.age, values.size)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ages, [(1, 1231400), (2, 524288)]);
+    }
+
+    #[test]
+    fn concurrent_cycle() {
+        let cycles = cycles();
+        assert_eq!(cycles[1].collection_type, CollectionType::ConcurrentStart);
+        assert_eq!(cycles[1].cause, "G1 Humongous Allocation");
+        let old = &cycles[2];
+        assert_eq!(old.gc_id, 7);
+        assert_eq!(old.name, "G1Old");
+        // the cause of the concurrent start that began it
+        assert_eq!(old.cause, "G1 Humongous Allocation");
+        assert_eq!(old.duration_ms, 67f64);
+        assert_eq!(old.sum_of_pauses_ms, 1.75);
+        assert_eq!(old.longest_pause_ms, 1.5);
+    }
+
+    #[test]
+    fn evacuation_failures() {
+        let cycles = cycles();
+        assert!(cycles[3].evacuation_failed);
+        assert!(cycles[4].evacuation_failed);
+        assert_eq!(cycles[4].cause, "G1 Evacuation Pause");
+        assert_eq!(cycles[4].collection_type, CollectionType::Normal);
+    }
+
+    #[test]
+    fn full_gc() {
+        let cycles = cycles();
+        let full = &cycles[5];
+        assert_eq!(full.name, "G1Full");
+        assert_eq!(full.cause, "System.gc()");
+        assert_eq!(full.longest_pause_ms, 100.125);
+        // no region lines, only the heap transition
+        assert!(full.before.is_none());
+        let heap = parse(LOG.as_bytes())
+            .unwrap()
+            .recording
+            .events
+            .into_iter()
+            .filter_map(|evt| match evt {
+                JfrEvent::GCHeapSummary { values } if values.gc_id == 10 => {
+                    Some((values.heap_used, values.heap_space.map(|s| s.reserved_size)))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            heap,
+            [(94 << 20, Some(128 << 20)), (12 << 20, Some(128 << 20))]
+        );
+        assert_eq!(cycles.len(), 6);
+    }
+
+    #[test]
+    fn sub_millisecond_pauses_are_kept() {
+        assert_eq!(ms_to_duration(4.875), Duration::from_micros(4875));
+        assert_eq!(ms_to_duration(0.25), Duration::from_micros(250));
+        assert_eq!(ms_to_duration(-1f64), Duration::ZERO);
+    }
+
+    #[test]
+    fn groups() {
+        assert_eq!(
+            split_groups("Pause Full (System.gc())"),
+            ("Pause Full", vec!["System.gc()".to_string()])
+        );
+        assert_eq!(
+            split_groups("Pause Young (Normal) (G1 Evacuation Pause)"),
+            (
+                "Pause Young",
+                vec!["Normal".to_string(), "G1 Evacuation Pause".to_string()]
+            )
+        );
+        assert_eq!(split_groups("Pause Remark"), ("Pause Remark", vec![]));
+    }
+
+    #[test]
+    fn sizes_and_transitions() {
+        assert_eq!(parse_size("1024K"), Some(1 << 20));
+        assert_eq!(parse_size("1.5M"), Some(3 << 19));
+        assert_eq!(parse_size("2G"), Some(2 << 30));
+        assert_eq!(parse_size("98566144B"), Some(98566144));
+        assert_eq!(parse_size("12"), None);
+        assert_eq!(parse_size("12Q"), None);
+        assert_eq!(parse_regions("53->0(52)"), Some((53, 0, Some(52))));
+        assert_eq!(parse_regions(" 2->1"), Some((2, 1, None)));
+        assert_eq!(parse_regions("2"), None);
+        let (name, heap, ms) =
+            split_pause_end("Pause Young (Normal) (G1 Evacuation Pause) 58M->6M(96M) 4.875ms")
+                .unwrap();
+        assert_eq!(name, "Pause Young (Normal) (G1 Evacuation Pause)");
+        assert_eq!((heap.before, heap.after), (58 << 20, 6 << 20));
+        assert_eq!(heap.committed, Some(96 << 20));
+        assert_eq!(ms, 4.875);
+        assert!(split_pause_end("Pause Young (Normal) (G1 Evacuation Pause)").is_none());
+    }
+
+    #[test]
+    fn decoration_times() {
+        let epoch = DateTime::UNIX_EPOCH;
+        assert_eq!(
+            parse_decoration_time("0.919s"),
+            Some(epoch + TimeDelta::milliseconds(919))
+        );
+        assert_eq!(
+            parse_decoration_time("919ms"),
+            Some(epoch + TimeDelta::milliseconds(919))
+        );
+        assert_eq!(
+            parse_decoration_time("2026-10-18T18:20:07.118+0000"),
+            DateTime::parse_from_rfc3339("2026-10-18T18:20:07.118Z")
+                .ok()
+                .map(|dt| dt.with_timezone(&Utc))
+        );
+        assert_eq!(parse_decoration_time("info"), None);
+    }
+
+    #[test]
+    fn detects_gc_logs() {
+        assert!(looks_like_gc_log(LOG.as_bytes()));
+        assert!(looks_like_gc_log(
+            b"\n[2026-10-18T18:20:07.118+0000][gc] Using G1"
+        ));
+        assert!(!looks_like_gc_log(b"{\"recording\": {}}"));
+    }
+}
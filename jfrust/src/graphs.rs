@@ -0,0 +1,207 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use plotly::{Bar, Plot, Scatter};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::jfr::{CollectionType, JfrEvent, JfrMain};
+
+/// Share of the x axis a full GC highlight covers at least, a pause of 100ms is too
+/// narrow to see on an axis of minutes.
+const MIN_HIGHLIGHT: f64 = 0.01;
+
+/// Where the GC bars are placed on the x axis.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeAxis {
+    /// One GC after the other, evenly spaced.
+    #[default]
+    Index,
+    /// Seconds since the JVM started, or since the recording started for GC logs.
+    Uptime,
+    /// Milliseconds since the epoch, for a plotly date axis.
+    WallClock,
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Graphs {
+    pub ages: Vec<Scatter<u64, u64>>,
+    pub gcs: Vec<Bar<f64, u64>>,
+    pub gcs_labels: Vec<String>,
+    pub gcs_ticks: Vec<f64>,
+    /// Width of each GC bar, in x axis units. Empty on the index axis.
+    pub gcs_widths: Vec<f64>,
+    /// x ranges of the full GCs, drawn behind their bars and widened to be seen.
+    pub gcs_highlights: Vec<(f64, f64)>,
+    pub time_axis: TimeAxis,
+}
+
+impl JfrMain {
+    pub fn to_graphs(
+        &self,
+        collection_type_filter: HashSet<CollectionType>,
+        time_axis: TimeAxis,
+    ) -> Graphs {
+        let mut graphs = Graphs {
+            time_axis,
+            ..Default::default()
+        };
+        let origin = self.jvm_start_time().or_else(|| self.start_time());
+        let mut x_axis: Vec<f64> = Vec::new();
+        let mut heap: Vec<u64> = Vec::new();
+        let mut young: Vec<u64> = Vec::new();
+        let mut tenured: Vec<u64> = Vec::new();
+        let mut survivors: Vec<u64> = Vec::new();
+        let mut text_array = Vec::<String>::new();
+        let mut ix = 0;
+        for cycle in self.gc_cycles() {
+            let gc_id = cycle.gc_id;
+            if collection_type_filter.contains(&cycle.collection_type) {
+                continue;
+            }
+            const FACTOR: f64 = 2.3f64;
+            // the before and after bars share the pause
+            let (before_x, after_x, half) = match (time_axis, cycle.start_time, origin) {
+                (TimeAxis::Index, _, _) => (ix as f64 * FACTOR, ix as f64 * FACTOR + 1f64, 1f64),
+                (TimeAxis::Uptime, Some(start_time), Some(origin)) => {
+                    let start = (start_time - origin).num_microseconds().unwrap_or(0) as f64 / 1e6;
+                    let half = cycle.sum_of_pauses_ms / 2000f64;
+                    graphs.gcs_widths.extend([half, half]);
+                    (start + half / 2f64, start + half * 1.5, half)
+                }
+                (TimeAxis::WallClock, Some(start_time), _) => {
+                    let start = start_time.timestamp_micros() as f64 / 1e3;
+                    let half = cycle.sum_of_pauses_ms / 2f64;
+                    graphs.gcs_widths.extend([half, half]);
+                    (start + half / 2f64, start + half * 1.5, half)
+                }
+                _ => continue,
+            };
+            if cycle.name == "G1Full" {
+                graphs
+                    .gcs_highlights
+                    .push((before_x - half / 2f64, after_x + half / 2f64));
+            }
+            let before = cycle.before.unwrap_or_default();
+            let after = cycle.after.unwrap_or_default();
+            // promoted bytes are taken out of eden first, then out of the survivors
+            let promoted = cycle.promoted.min(before.young());
+            x_axis.push(before_x);
+            heap.push(before.used.saturating_sub(before.young()));
+            young.push(before.eden.saturating_sub(promoted));
+            tenured.push(promoted);
+            survivors.push(before.survivor - promoted.saturating_sub(before.eden));
+            text_array.push(format!("[{}] before gc", gc_id));
+            graphs.gcs_labels.push(cycle.title());
+            graphs.gcs_ticks.push(before_x);
+
+            x_axis.push(after_x);
+            heap.push(
+                after
+                    .used
+                    .saturating_sub(after.young())
+                    .saturating_sub(promoted),
+            );
+            young.push(after.eden);
+            tenured.push(promoted);
+            survivors.push(after.survivor);
+            text_array.push(format!("[{}] after gc", gc_id));
+            ix += 1;
+        }
+        if let Some((first, last)) = x_axis.iter().copied().minmax().into_option() {
+            let min_width = (last - first) * MIN_HIGHLIGHT;
+            for (x0, x1) in &mut graphs.gcs_highlights {
+                let widen = (min_width - (*x1 - *x0)).max(0f64) / 2f64;
+                *x0 -= widen;
+                *x1 += widen;
+            }
+        }
+        graphs.gcs.push(
+            *Bar::new(x_axis.clone(), heap)
+                .name("old")
+                .text_array(text_array.clone()),
+        );
+        graphs.gcs.push(
+            *Bar::new(x_axis.clone(), tenured)
+                .name("tenured")
+                .text_array(text_array.clone()),
+        );
+        graphs.gcs.push(
+            *Bar::new(x_axis.clone(), young)
+                .name("young")
+                .text_array(text_array.clone()),
+        );
+        graphs.gcs.push(
+            *Bar::new(x_axis.clone(), survivors)
+                .name("survivors")
+                .text_array(text_array),
+        );
+        let tenure_vec = &self
+            .recording
+            .events
+            .iter()
+            .filter_map(|e| {
+                if let JfrEvent::TenuringDistribution { values } = e {
+                    Some(values)
+                } else {
+                    None
+                }
+            })
+            .collect_vec();
+
+        let by_gc_id = tenure_vec.iter().chunk_by(|e| e.gc_id);
+        for (_gc_id, tenures) in by_gc_id.into_iter() {
+            let mut ages = Vec::<u64>::new();
+            let mut sizes = Vec::<u64>::new();
+            for tenure in tenures {
+                ages.push(tenure.age);
+                sizes.push(tenure.size);
+            }
+            let trace = Scatter::new(ages, sizes);
+            graphs.ages.push(*trace);
+        }
+        graphs
+    }
+}
+
+/// plotly.rs takes a single width for all the bars of a trace, the time axes need one per bar.
+pub fn with_bar_widths(plot: &Plot, widths: &[f64]) -> Value {
+    let mut plot = serde_json::to_value(plot).unwrap_or(Value::Null);
+    if let Some(traces) = plot.get_mut("data").and_then(Value::as_array_mut) {
+        for trace in traces {
+            trace["width"] = Value::from(widths);
+        }
+    }
+    plot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gclog;
+
+    const LOG: &str = "\
+[0.500s][info ][gc] GC(0) Pause Young (Normal) (G1 Evacuation Pause) 50M->10M(96M) 5.000ms
+[300.000s][info ][gc] GC(1) Pause Full (System.gc()) 50M->10M(96M) 100.000ms
+[600.500s][info ][gc] GC(2) Pause Young (Normal) (G1 Evacuation Pause) 50M->10M(96M) 5.000ms
+";
+
+    #[test]
+    fn full_gc_highlight_is_widened_on_a_time_axis() {
+        let jfr = gclog::parse(LOG.as_bytes()).unwrap();
+        let graphs = jfr.to_graphs(HashSet::new(), TimeAxis::Uptime);
+        let [(x0, x1)] = graphs.gcs_highlights[..] else {
+            panic!("{:?}", graphs.gcs_highlights);
+        };
+        assert!(x1 - x0 >= 600f64 * MIN_HIGHLIGHT, "{x0}..{x1}");
+        assert!(x0 < 300f64 && x1 > 300.1, "{x0}..{x1}");
+    }
+
+    #[test]
+    fn full_gc_highlight_keeps_its_bars_on_the_index_axis() {
+        let jfr = gclog::parse(LOG.as_bytes()).unwrap();
+        let graphs = jfr.to_graphs(HashSet::new(), TimeAxis::Index);
+        assert_eq!(graphs.gcs_highlights, vec![(2.3 - 0.5, 2.3 + 1.5)]);
+    }
+}
@@ -0,0 +1,376 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    alert::{recording_value, Metric},
+    jfr::{Flag, JfrMain},
+    leak::leak_report,
+    MB,
+};
+
+/// Live set growth that would fill the heap within this long scores 0.
+const EXHAUSTION_SOON_S: f64 = 3600f64;
+/// Beyond this long a leak still costs half the points.
+const EXHAUSTION_LATE_S: f64 = 24f64 * 3600f64;
+
+/*
+{
+  "name": "pause_p99",
+  "value": 8.69,
+  "unit": "ms",
+  "score": 100.0,
+  "weight": 0.25,
+  "lost": 0.0,
+  "detail": "good up to 100ms, bad from 400ms, half and twice MaxGCPauseMillis"
+}
+*/
+/// One of the things the score is made of.
+#[derive(Serialize, Debug, Clone)]
+pub struct Factor {
+    pub name: &'static str,
+    /// `None` when the recording lacks what the factor comes from, it then does not count.
+    pub value: Option<f64>,
+    pub unit: &'static str,
+    /// 0 to 100.
+    pub score: Option<f64>,
+    /// Share of the score, of the factors that count.
+    pub weight: f64,
+    /// Points of the 100 this factor cost, the factors' add up to 100 less the score.
+    pub lost: f64,
+    pub detail: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct HealthReport {
+    /// 0 to 100, the weighted mean of the factors' scores, `None` without GCs to score.
+    pub score: Option<f64>,
+    pub factors: Vec<Factor>,
+}
+
+impl HealthReport {
+    /// The factor that cost the most points.
+    pub fn worst(&self) -> Option<&Factor> {
+        self.factors
+            .iter()
+            .filter(|f| f.lost > 0f64)
+            .max_by(|a, b| a.lost.total_cmp(&b.lost))
+    }
+}
+
+/// 100 up to `good`, 0 from `bad` and linear in between.
+fn scale(value: f64, good: f64, bad: f64) -> f64 {
+    if value <= good {
+        100f64
+    } else if value >= bad {
+        0f64
+    } else {
+        100f64 * (bad - value) / (bad - good)
+    }
+}
+
+/// A metric of the recording on a scale, `weight` before the weights of the factors
+/// that count are made to add up to 1.
+fn scaled(
+    jfr: &JfrMain,
+    name: &'static str,
+    metric: Metric,
+    weight: f64,
+    good: f64,
+    bad: f64,
+) -> Factor {
+    let value = recording_value(jfr, metric);
+    Factor {
+        name,
+        value,
+        unit: metric.unit(),
+        score: value.map(|value| scale(value, good, bad)),
+        weight,
+        lost: 0f64,
+        detail: format!(
+            "good up to {}{unit}, bad from {}{unit}",
+            good,
+            bad,
+            unit = metric.unit()
+        ),
+    }
+}
+
+/// A growing live set costs half the points, all of them when it fills the heap soon.
+fn heap_trend(jfr: &JfrMain, weight: f64) -> Factor {
+    let leak = leak_report(jfr);
+    let (score, detail) = match (&leak.trend, leak.leak, leak.exhaustion_in_s) {
+        (None, _, _) => (None, "too few live set estimates for a trend".to_string()),
+        (Some(_), false, _) => (Some(100f64), "live set not growing".to_string()),
+        (Some(_), true, Some(exhaustion)) => (
+            // the sooner the worse
+            Some(scale(-exhaustion, -EXHAUSTION_LATE_S, -EXHAUSTION_SOON_S) / 2f64),
+            format!("live set growing, heap full in {:.0}s", exhaustion),
+        ),
+        (Some(_), true, None) => (
+            Some(50f64),
+            "live set growing, heap size unknown".to_string(),
+        ),
+    };
+    Factor {
+        name: "heap_trend",
+        value: leak.trend.map(|trend| trend.slope / MB),
+        unit: "MB/s",
+        score,
+        weight,
+        lost: 0f64,
+        detail,
+    }
+}
+
+pub fn health(jfr: &JfrMain) -> HealthReport {
+    let target = jfr
+        .flags()
+        .get("MaxGCPauseMillis")
+        .and_then(Flag::number)
+        .unwrap_or(200f64);
+    let mut factors = vec![
+        Factor {
+            detail: format!(
+                "good up to {}ms, bad from {}ms, half and twice MaxGCPauseMillis",
+                target / 2f64,
+                target * 2f64
+            ),
+            ..scaled(
+                jfr,
+                "pause_p99",
+                Metric::PauseP99,
+                25f64,
+                target / 2f64,
+                target * 2f64,
+            )
+        },
+        Factor {
+            detail: format!(
+                "good up to {}ms, bad from {}ms, MaxGCPauseMillis and 5 times it",
+                target,
+                target * 5f64
+            ),
+            ..scaled(
+                jfr,
+                "pause_max",
+                Metric::PauseMax,
+                10f64,
+                target,
+                target * 5f64,
+            )
+        },
+        scaled(jfr, "gc_overhead", Metric::GcOverhead, 25f64, 2f64, 20f64),
+        scaled(jfr, "full_gcs", Metric::FullGcs, 15f64, 0f64, 5f64),
+        scaled(
+            jfr,
+            "evacuation_failures",
+            Metric::EvacuationFailures,
+            10f64,
+            0f64,
+            5f64,
+        ),
+        heap_trend(jfr, 15f64),
+    ];
+    // counts and pause statistics are 0 without GCs, which is not a healthy 100
+    if !jfr
+        .gc_cycles()
+        .iter()
+        .any(|cycle| cycle.sum_of_pauses_ms > 0f64)
+    {
+        for factor in &mut factors {
+            factor.value = None;
+            factor.score = None;
+            factor.detail = "no GCs".to_string();
+        }
+    }
+    let total = factors
+        .iter()
+        .filter(|f| f.score.is_some())
+        .map(|f| f.weight)
+        .sum::<f64>();
+    for factor in &mut factors {
+        factor.weight = match factor.score {
+            Some(_) if total > 0f64 => factor.weight / total,
+            _ => 0f64,
+        };
+        factor.lost = factor.weight * (100f64 - factor.score.unwrap_or(100f64));
+    }
+    HealthReport {
+        score: (total > 0f64).then(|| 100f64 - factors.iter().map(|f| f.lost).sum::<f64>()),
+        factors,
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RecordingHealth {
+    pub recording: String,
+    pub health: HealthReport,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct HealthRanking {
+    /// The lowest score first, those without GCs last.
+    pub recordings: Vec<RecordingHealth>,
+}
+
+pub fn rank(mut recordings: Vec<RecordingHealth>) -> HealthRanking {
+    let score = |r: &RecordingHealth| r.health.score.unwrap_or(f64::INFINITY);
+    recordings.sort_by(|a, b| score(a).total_cmp(&score(b)));
+    HealthRanking { recordings }
+}
+
+impl fmt::Display for HealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(score) = self.score else {
+            return writeln!(f, "health: no GCs");
+        };
+        writeln!(f, "health {:.0}/100", score)?;
+        writeln!(
+            f,
+            "{:<22} {:>12} {:>7} {:>7} {:>7}",
+            "factor", "value", "score", "weight", "lost"
+        )?;
+        for factor in &self.factors {
+            writeln!(
+                f,
+                "{:<22} {:>12} {:>7} {:>6.0}% {:>7.1}  {}",
+                factor.name,
+                factor
+                    .value
+                    .map(|v| format!("{:.2}{}", v, factor.unit))
+                    .unwrap_or_else(|| "-".to_string()),
+                factor
+                    .score
+                    .map(|s| format!("{:.0}", s))
+                    .unwrap_or_else(|| "-".to_string()),
+                factor.weight * 100f64,
+                factor.lost,
+                factor.detail
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for HealthRanking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>5} {:<28} recording", "score", "worst factor")?;
+        for r in &self.recordings {
+            writeln!(
+                f,
+                "{:>5} {:<28} {}",
+                r.health
+                    .score
+                    .map(|score| format!("{:.0}", score))
+                    .unwrap_or_else(|| "-".to_string()),
+                match (r.health.score, r.health.worst()) {
+                    (None, _) => "no GCs".to_string(),
+                    (Some(_), Some(w)) => format!("{} (-{:.1})", w.name, w.lost),
+                    (Some(_), None) => "-".to_string(),
+                },
+                r.recording
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gclog, jfr::JfrRecording};
+
+    /// Young GCs a second apart with the given pauses, and a full GC if `full`.
+    fn recording(pauses: &[f64], full: bool) -> JfrMain {
+        let mut log = String::new();
+        for (gc_id, pause) in pauses.iter().enumerate() {
+            log += &format!(
+                "[{gc_id}.500s][info ][gc] GC({gc_id}) Pause Young (Normal) (G1 Evacuation Pause) 50M->10M(96M) {pause:.3}ms\n"
+            );
+        }
+        if full {
+            log += "[99.000s][info ][gc] GC(99) Pause Full (System.gc()) 50M->10M(96M) 100.000ms\n";
+        }
+        gclog::parse(log.as_bytes()).unwrap()
+    }
+
+    fn factor<'a>(report: &'a HealthReport, name: &str) -> &'a Factor {
+        report.factors.iter().find(|f| f.name == name).unwrap()
+    }
+
+    #[test]
+    fn no_gcs_no_score() {
+        let report = health(&JfrMain {
+            recording: JfrRecording::default(),
+        });
+        assert_eq!(report.score, None);
+        assert!(report
+            .factors
+            .iter()
+            .all(|f| f.value.is_none() && f.score.is_none() && f.lost == 0f64));
+        assert!(report.worst().is_none());
+    }
+
+    #[test]
+    fn unscored_recordings_rank_last() {
+        let ranking = rank(vec![
+            RecordingHealth {
+                recording: "idle".to_string(),
+                health: health(&JfrMain {
+                    recording: JfrRecording::default(),
+                }),
+            },
+            RecordingHealth {
+                recording: "busy".to_string(),
+                health: health(&recording(&[2f64; 20], true)),
+            },
+            RecordingHealth {
+                recording: "quiet".to_string(),
+                health: health(&recording(&[2f64; 20], false)),
+            },
+        ]);
+        let order = ranking
+            .recordings
+            .iter()
+            .map(|r| r.recording.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(order, ["busy", "quiet", "idle"]);
+    }
+
+    #[test]
+    fn weights_of_scored_factors_add_up() {
+        let report = health(&recording(&[2f64; 20], true));
+        let weights = report
+            .factors
+            .iter()
+            .filter(|f| f.score.is_some())
+            .map(|f| f.weight)
+            .sum::<f64>();
+        assert!((weights - 1f64).abs() < 1e-9);
+        let lost = report.factors.iter().map(|f| f.lost).sum::<f64>();
+        assert!((report.score.unwrap() + lost - 100f64).abs() < 1e-9);
+        // one of 5 full GCs that score 0
+        assert_eq!(factor(&report, "full_gcs").score, Some(80f64));
+    }
+
+    #[test]
+    fn pauses_against_the_pause_target() {
+        // the default MaxGCPauseMillis of 200ms, p99 good up to 100ms and bad from 400ms
+        let report = health(&recording(&[250f64; 20], false));
+        let p99 = factor(&report, "pause_p99");
+        assert_eq!(p99.value, Some(250f64));
+        assert_eq!(p99.score, Some(50f64));
+        assert_eq!(factor(&report, "pause_max").score, Some(93.75));
+    }
+
+    #[test]
+    fn scales() {
+        assert_eq!(scale(1f64, 2f64, 20f64), 100f64);
+        assert_eq!(scale(2f64, 2f64, 20f64), 100f64);
+        assert_eq!(scale(11f64, 2f64, 20f64), 50f64);
+        assert_eq!(scale(20f64, 2f64, 20f64), 0f64);
+        assert_eq!(scale(0f64, 0f64, 5f64), 100f64);
+        assert_eq!(scale(40f64, 2f64, 20f64), 0f64);
+    }
+}
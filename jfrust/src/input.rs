@@ -0,0 +1,503 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Write},
+    path::Path,
+};
+
+use flate2::read::MultiGzDecoder;
+use quick_xml::{events::Event, Reader};
+use serde_json::{Map, Number, Value};
+
+use crate::{
+    error::{Error, Result},
+    gclog,
+    jdk::JfrTool,
+    jfr::{JfrMain, JfrRecording},
+};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const JFR_MAGIC: &[u8] = b"FLR\0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Binary JFR recording, converted with `jfr print --json`.
+    Jfr,
+    /// Output of `jfr print --json`.
+    Json,
+    /// Output of `jfr print --xml`.
+    Xml,
+    /// Unified GC log, `-Xlog:gc*`.
+    GcLog,
+}
+
+impl Format {
+    pub fn detect(head: &[u8]) -> Option<Format> {
+        if head.starts_with(JFR_MAGIC) {
+            return Some(Format::Jfr);
+        }
+        match head.iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'{') => Some(Format::Json),
+            Some(b'<') => Some(Format::Xml),
+            _ if gclog::looks_like_gc_log(head) => Some(Format::GcLog),
+            _ => None,
+        }
+    }
+}
+
+/// Loads a recording from `path`, or stdin when `path` is `-`.
+/// The format is detected from the content, and gzip or zstd compressed input is
+/// decompressed on the fly. Binary recordings are converted with the `jfr` tool,
+/// see [`JfrTool::locate`] for how `jfr_bin` is used.
+/// Recordings cut short by a crashed JVM are loaded up to the missing part, see
+/// [`JfrRecording::diagnostics`](crate::jfr::JfrRecording::diagnostics).
+pub fn load(path: &str, jfr_bin: Option<&Path>) -> Result<JfrMain> {
+    let mut raw = Vec::new();
+    if path == "-" {
+        io::stdin().lock().read_to_end(&mut raw)?;
+    } else {
+        File::open(path)?.read_to_end(&mut raw)?;
+    }
+    let (data, compressed) = if raw.starts_with(GZIP_MAGIC) {
+        let mut data = Vec::new();
+        MultiGzDecoder::new(raw.as_slice()).read_to_end(&mut data)?;
+        (data, true)
+    } else if raw.starts_with(ZSTD_MAGIC) {
+        let mut data = Vec::new();
+        ruzstd::decoding::StreamingDecoder::new(raw.as_slice())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?
+            .read_to_end(&mut data)?;
+        (data, true)
+    } else {
+        (raw, false)
+    };
+    let head = &data[..data.len().min(4096)];
+    match Format::detect(head) {
+        Some(Format::Jfr) => load_jfr(path, &data, compressed, jfr_bin),
+        Some(Format::Json) => load_json(&data),
+        Some(Format::Xml) => Ok(serde_json::from_value(xml_to_json(&data)?)?),
+        Some(Format::GcLog) => Ok(gclog::parse(BufReader::new(data.as_slice()))?),
+        None => Err(Error::UnknownFormat(path.to_string())),
+    }
+}
+
+fn load_jfr(path: &str, data: &[u8], compressed: bool, jfr_bin: Option<&Path>) -> Result<JfrMain> {
+    let complete = complete_chunks(data);
+    if complete == 0 {
+        return Err(Error::Truncated(format!(
+            "{}: the first chunk is incomplete",
+            path
+        )));
+    }
+    let truncated = (complete < data.len()).then(|| {
+        format!(
+            "dropped the incomplete chunk at offset {}, {} bytes",
+            complete,
+            data.len() - complete
+        )
+    });
+    let mut jfr_main = if path != "-" && !compressed && truncated.is_none() {
+        jfr_print_json(jfr_bin, Path::new(path))?
+    } else {
+        // removed when dropped, `jfr` can only read recordings from a file
+        let mut tmp = tempfile::Builder::new()
+            .prefix("jfrust-")
+            .suffix(".jfr")
+            .tempfile()?;
+        tmp.write_all(&data[..complete])?;
+        tmp.flush()?;
+        jfr_print_json(jfr_bin, tmp.path())?
+    };
+    jfr_main.recording.diagnostics.truncated = truncated;
+    Ok(jfr_main)
+}
+
+/// Length of the complete chunks at the start of a recording.
+/// A chunk header is `FLR\0`, major and minor version as u16 and the chunk size as u64,
+/// big endian. The last chunk of a JVM that crashed while recording has its size
+/// still at 0, or the file ends before it does.
+fn complete_chunks(data: &[u8]) -> usize {
+    let mut offset = 0;
+    while let Some(header) = data.get(offset..offset + 16) {
+        if !header.starts_with(JFR_MAGIC) {
+            break;
+        }
+        let mut size = [0u8; 8];
+        size.copy_from_slice(&header[8..16]);
+        let size = usize::try_from(u64::from_be_bytes(size)).unwrap_or(usize::MAX);
+        if size < header.len() || offset.saturating_add(size) > data.len() {
+            break;
+        }
+        offset += size;
+    }
+    offset
+}
+
+fn jfr_print_json(jfr_bin: Option<&Path>, path: &Path) -> Result<JfrMain> {
+    let jfr = JfrTool::locate(jfr_bin)?;
+    let mut jfr_main: JfrMain = serde_json::from_slice(&jfr.print_json(path)?)?;
+    if let (Some(tool), Some(recorded)) = (jfr.version, jfr_main.jdk_version()) {
+        if tool.feature < recorded.feature {
+            jfr_main.recording.diagnostics.warnings.push(format!(
+                "{} is from JDK {}, events recorded by JDK {} may be missing",
+                jfr.path.display(),
+                tool,
+                recorded
+            ));
+        }
+    }
+    Ok(jfr_main)
+}
+
+fn load_json(data: &[u8]) -> Result<JfrMain> {
+    match serde_json::from_slice(data) {
+        Err(e) if e.is_eof() => truncated_json(data).ok_or(Error::Json(e)),
+        res => Ok(res?),
+    }
+}
+
+/// Reads the events of a `jfr print --json` output that was cut short,
+/// up to the first incomplete one.
+fn truncated_json(data: &[u8]) -> Option<JfrMain> {
+    let events = data.windows(8).position(|w| w == b"\"events\"")? + 8;
+    let mut rest = data[events..]
+        .trim_ascii_start()
+        .strip_prefix(b":")?
+        .trim_ascii_start()
+        .strip_prefix(b"[")?;
+    let mut recording = JfrRecording::default();
+    let mut parsed = 0;
+    loop {
+        let mut values = serde_json::Deserializer::from_slice(rest).into_iter::<Value>();
+        let Some(Ok(value)) = values.next() else {
+            break;
+        };
+        parsed += 1;
+        recording.push_value(value);
+        rest = rest[values.byte_offset()..].trim_ascii_start();
+        match rest.strip_prefix(b",") {
+            Some(next) => rest = next,
+            None => break,
+        }
+    }
+    recording.diagnostics.truncated = Some(format!("the JSON ends after {} events", parsed));
+    Some(JfrMain { recording })
+}
+
+/*
+<recording xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <events>
+    <event type="jdk.GCPhasePause">
+      <value name="startTime">2024-07-21T14:02:03.138233875+03:00</value>
+      <value name="duration">PT0.000017041S</value>
+      <struct name="eventThread">
+        <value name="osName">G1 Main Marker</value>
+        <value name="javaName" xsi:nil="true"/>
+      </struct>
+      <array name="frames" size="1">
+        <struct index="0">...</struct>
+      </array>
+    </event>
+  </events>
+</recording>
+*/
+/// Converts `jfr print --xml` output to the shape `jfr print --json` produces.
+fn xml_to_json(data: &[u8]) -> Result<Value> {
+    enum Node {
+        Event(String, Map<String, Value>),
+        Object(Option<String>, Map<String, Value>),
+        Array(Option<String>, Vec<Value>),
+        Text(Option<String>, String),
+    }
+    fn attr(e: &quick_xml::events::BytesStart, name: &str) -> Option<String> {
+        e.try_get_attribute(name)
+            .ok()
+            .flatten()
+            .map(|a| a.value.into_owned())
+    }
+    fn add(stack: &mut [Node], name: Option<String>, value: Value) {
+        match stack.last_mut() {
+            Some(Node::Event(_, map)) | Some(Node::Object(_, map)) => {
+                map.insert(name.unwrap_or_default(), value);
+            }
+            Some(Node::Array(_, vec)) => vec.push(value),
+            _ => {}
+        }
+    }
+
+    let mut reader = Reader::from_reader(data);
+    let mut buf = Vec::new();
+    let mut events = Vec::new();
+    let mut stack: Vec<Node> = Vec::new();
+    loop {
+        buf.clear();
+        let event = reader.read_event_into(&mut buf)?;
+        let is_empty = matches!(event, Event::Empty(_));
+        match event {
+            Event::Start(e) | Event::Empty(e) => {
+                let name = attr(&e, "name");
+                let node = match e.local_name().as_ref() {
+                    "event" => Node::Event(attr(&e, "type").unwrap_or_default(), Map::new()),
+                    _ if attr(&e, "xsi:nil").as_deref() == Some("true") => {
+                        Node::Text(name, "null".to_string())
+                    }
+                    "struct" => Node::Object(name, Map::new()),
+                    "array" => Node::Array(name, Vec::new()),
+                    "value" => Node::Text(name, String::new()),
+                    _ => continue,
+                };
+                stack.push(node);
+                if !is_empty {
+                    continue;
+                }
+            }
+            Event::Text(t) => {
+                if let Some(Node::Text(_, text)) = stack.last_mut() {
+                    text.push_str(&t.xml10_content());
+                }
+                continue;
+            }
+            Event::GeneralRef(r) => {
+                if let Some(Node::Text(_, text)) = stack.last_mut() {
+                    match r.resolve_char_ref().ok().flatten() {
+                        Some(c) => text.push(c),
+                        None => text.push_str(match &*r {
+                            "lt" => "<",
+                            "gt" => ">",
+                            "amp" => "&",
+                            "apos" => "'",
+                            "quot" => "\"",
+                            other => other,
+                        }),
+                    }
+                }
+                continue;
+            }
+            Event::End(e) => {
+                if !matches!(
+                    e.local_name().as_ref(),
+                    "event" | "struct" | "array" | "value"
+                ) {
+                    continue;
+                }
+            }
+            Event::Eof => break,
+            _ => continue,
+        }
+        // closing the node on top of the stack
+        let Some(node) = stack.pop() else {
+            continue;
+        };
+        match node {
+            Node::Event(type_, values) => {
+                let mut event = Map::new();
+                event.insert("type".to_string(), Value::String(type_));
+                event.insert("values".to_string(), Value::Object(values));
+                events.push(Value::Object(event));
+            }
+            Node::Object(name, values) => add(&mut stack, name, Value::Object(values)),
+            Node::Array(name, values) => add(&mut stack, name, Value::Array(values)),
+            Node::Text(name, text) => add(&mut stack, name, typed_value(text)),
+        }
+    }
+    let mut recording = Map::new();
+    recording.insert("events".to_string(), Value::Array(events));
+    let mut main = Map::new();
+    main.insert("recording".to_string(), Value::Object(recording));
+    Ok(Value::Object(main))
+}
+
+/// XML loses the JSON types, numbers and booleans are recovered from the text when
+/// that keeps it as it was, "007" or "1.50" stay strings. String fields take numbers
+/// back as text.
+fn typed_value(text: String) -> Value {
+    match text.as_str() {
+        "null" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => {
+            let n = if let Ok(n) = text.parse::<u64>() {
+                Some(Number::from(n))
+            } else if let Ok(n) = text.parse::<i64>() {
+                Some(Number::from(n))
+            } else {
+                text.parse::<f64>().ok().and_then(Number::from_f64)
+            };
+            match n {
+                Some(n) if n.to_string() == text => Value::Number(n),
+                _ => Value::String(text),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::jfr::JfrEvent;
+
+    fn chunk(size: u64, len: usize) -> Vec<u8> {
+        let mut chunk = JFR_MAGIC.to_vec();
+        chunk.extend_from_slice(&2u16.to_be_bytes());
+        chunk.extend_from_slice(&1u16.to_be_bytes());
+        chunk.extend_from_slice(&size.to_be_bytes());
+        chunk.resize(len, 0);
+        chunk
+    }
+
+    #[test]
+    fn complete_recording() {
+        let data = [chunk(100, 100), chunk(64, 64)].concat();
+        assert_eq!(complete_chunks(&data), 164);
+    }
+
+    #[test]
+    fn chunk_size_still_zero() {
+        let data = [chunk(100, 100), chunk(0, 50)].concat();
+        assert_eq!(complete_chunks(&data), 100);
+    }
+
+    #[test]
+    fn chunk_cut_short() {
+        let data = [chunk(100, 100), chunk(64, 40)].concat();
+        assert_eq!(complete_chunks(&data), 100);
+        assert_eq!(complete_chunks(&chunk(100, 99)), 0);
+        // not even a whole header
+        assert_eq!(complete_chunks(&chunk(100, 100)[..10]), 0);
+    }
+
+    #[test]
+    fn chunk_sizes_out_of_range() {
+        assert_eq!(complete_chunks(&chunk(8, 64)), 0);
+        assert_eq!(complete_chunks(&chunk(u64::MAX, 64)), 0);
+        assert_eq!(complete_chunks(b"not a recording at all"), 0);
+        assert_eq!(complete_chunks(&[]), 0);
+    }
+
+    #[test]
+    fn detects_formats() {
+        assert_eq!(Format::detect(&chunk(64, 64)), Some(Format::Jfr));
+        assert_eq!(Format::detect(b"  \n{\"recording\""), Some(Format::Json));
+        assert_eq!(Format::detect(b"<?xml version"), Some(Format::Xml));
+        assert_eq!(
+            Format::detect(b"[0.003s][info ][gc] Using G1"),
+            Some(Format::GcLog)
+        );
+        assert_eq!(Format::detect(b"hello"), None);
+    }
+
+    #[test]
+    fn xml_events() {
+        let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<recording xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <events>
+    <event type="jdk.GCPhasePause">
+      <value name="startTime">2024-07-21T14:02:03.138233875+03:00</value>
+      <value name="duration">PT0.000017041S</value>
+      <value name="gcId">12</value>
+      <value name="name">Pause &lt;Remark&gt; &amp; &#x41;</value>
+      <struct name="eventThread">
+        <value name="osName">G1 Main Marker</value>
+        <value name="javaName" xsi:nil="true"/>
+      </struct>
+      <array name="frames" size="2">
+        <struct index="0"><value name="lineNumber">-1</value></struct>
+        <struct index="1"><value name="truncated">false</value></struct>
+      </array>
+    </event>
+    <event type="jdk.Empty"/>
+  </events>
+</recording>"#;
+        assert_eq!(
+            xml_to_json(xml).unwrap(),
+            json!({"recording": {"events": [
+                {
+                    "type": "jdk.GCPhasePause",
+                    "values": {
+                        "startTime": "2024-07-21T14:02:03.138233875+03:00",
+                        "duration": "PT0.000017041S",
+                        "gcId": 12,
+                        "name": "Pause <Remark> & A",
+                        "eventThread": {"osName": "G1 Main Marker", "javaName": null},
+                        "frames": [{"lineNumber": -1}, {"truncated": false}],
+                    },
+                },
+                {"type": "jdk.Empty", "values": {}},
+            ]}})
+        );
+    }
+
+    #[test]
+    fn xml_without_events() {
+        assert_eq!(
+            xml_to_json(b"<recording><events></events></recording>").unwrap(),
+            json!({"recording": {"events": []}})
+        );
+    }
+
+    #[test]
+    fn typed_values() {
+        assert_eq!(typed_value("42".to_string()), json!(42));
+        assert_eq!(typed_value("-3".to_string()), json!(-3));
+        assert_eq!(typed_value("0.5".to_string()), json!(0.5));
+        assert_eq!(typed_value("NaN".to_string()), json!("NaN"));
+        assert_eq!(typed_value("true".to_string()), json!(true));
+        assert_eq!(typed_value("G1New".to_string()), json!("G1New"));
+        // only when the text would come back the same
+        assert_eq!(typed_value("007".to_string()), json!("007"));
+        assert_eq!(typed_value("1.50".to_string()), json!("1.50"));
+        assert_eq!(typed_value("+5".to_string()), json!("+5"));
+        assert_eq!(typed_value("1e3".to_string()), json!("1e3"));
+    }
+
+    #[test]
+    fn xml_strings_that_look_like_numbers() {
+        let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<recording>
+  <events>
+    <event type="jdk.GCPhasePause">
+      <value name="startTime">2024-07-21T14:02:03.138233875+03:00</value>
+      <value name="duration">PT0.000017041S</value>
+      <value name="gcId">12</value>
+      <value name="name">42</value>
+      <struct name="eventThread">
+        <value name="osName">7</value>
+        <value name="osThreadId">14595</value>
+      </struct>
+    </event>
+    <event type="jdk.StringFlag">
+      <value name="startTime">2024-07-21T14:02:03.138233875+03:00</value>
+      <value name="name">200</value>
+      <value name="value">200</value>
+      <value name="origin">true</value>
+    </event>
+  </events>
+</recording>"#;
+        let jfr: JfrMain = serde_json::from_value(xml_to_json(xml).unwrap()).unwrap();
+        assert!(jfr.recording.diagnostics.skipped.is_empty());
+        assert_eq!(jfr.recording.events.len(), 2);
+        match &jfr.recording.events[0] {
+            JfrEvent::GCPhasePause { values } => {
+                assert_eq!(values.name, "42");
+                assert_eq!(values.event_thread.as_ref().unwrap().os_name, "7");
+            }
+            evt => panic!("{:?}", evt),
+        }
+        let flags = jfr.flags();
+        assert_eq!(flags["200"].origin, "true");
+        assert_eq!(flags["200"].number(), Some(200f64));
+    }
+
+    #[test]
+    fn truncated_events() {
+        let data =
+            br#"{"recording": {"events": [{"type": "jdk.A", "values": {}}, {"type": "jdk.B", "val"#;
+        let jfr = load_json(data).unwrap();
+        assert_eq!(
+            jfr.recording.diagnostics.truncated.as_deref(),
+            Some("the JSON ends after 1 events")
+        );
+    }
+}
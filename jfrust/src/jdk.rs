@@ -0,0 +1,205 @@
+use std::{
+    env, fmt, fs, iter,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::error::{Error, Result};
+
+/// Version of a JDK, `1.8.0_292` is feature 8 update 292, `17.0.15+6` is feature 17 update 15.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct JdkVersion {
+    pub feature: u32,
+    pub update: u32,
+}
+
+impl JdkVersion {
+    pub fn parse(version: &str) -> Option<JdkVersion> {
+        let version = version.trim().trim_matches('"');
+        let version = version.split(['+', '-', ' ']).next()?;
+        let mut parts = version.split(['.', '_']);
+        let mut feature = parts.next()?.parse().ok()?;
+        if feature == 1 {
+            // 1.8.0_292
+            feature = parts.next()?.parse().ok()?;
+            parts.next();
+        } else {
+            // 17.0.15, the interim version is always 0
+            parts.next();
+        }
+        let update = parts.next().and_then(|u| u.parse().ok()).unwrap_or(0);
+        Some(JdkVersion { feature, update })
+    }
+
+    /// Reads JAVA_VERSION from the `release` file of a JDK installation.
+    pub fn of_java_home(java_home: &Path) -> Option<JdkVersion> {
+        let release = fs::read_to_string(java_home.join("release")).ok()?;
+        release
+            .lines()
+            .find_map(|line| line.strip_prefix("JAVA_VERSION="))
+            .and_then(JdkVersion::parse)
+    }
+}
+
+impl fmt::Display for JdkVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.0.{}", self.feature, self.update)
+    }
+}
+
+/// The `jfr` command line tool used to convert recordings to JSON.
+#[derive(Debug, Clone)]
+pub struct JfrTool {
+    pub path: PathBuf,
+    pub version: Option<JdkVersion>,
+}
+
+impl JfrTool {
+    /// Looks for `jfr` in `jfr_bin`, `$JAVA_HOME/bin`, the `java.home` of the `java`
+    /// on the PATH, and finally the PATH itself.
+    pub fn locate(jfr_bin: Option<&Path>) -> Result<JfrTool> {
+        let path = match jfr_bin {
+            Some(jfr_bin) if jfr_bin.is_file() => jfr_bin.to_path_buf(),
+            Some(jfr_bin) => {
+                return Err(Error::JfrTool(format!(
+                    "{} does not exist",
+                    jfr_bin.display()
+                )))
+            }
+            None => env::var_os("JAVA_HOME")
+                .map(PathBuf::from)
+                .into_iter()
+                .chain(iter::once_with(java_home_property).flatten())
+                .map(|java_home| bin(&java_home, "jfr"))
+                .chain(iter::once_with(|| search_path("jfr")).flatten())
+                .find(|jfr| jfr.is_file())
+                .ok_or_else(|| {
+                    Error::JfrTool("cannot find jfr, set JAVA_HOME or use --jfr-bin".to_string())
+                })?,
+        };
+        let path = path.canonicalize().unwrap_or(path);
+        // <java.home>/bin/jfr
+        let version = path
+            .parent()
+            .and_then(Path::parent)
+            .and_then(JdkVersion::of_java_home);
+        let tool = JfrTool { path, version };
+        tool.validate()?;
+        Ok(tool)
+    }
+
+    /// `print --json` is missing from the `jfr` shipped with some JDK 8 and 11 updates.
+    fn validate(&self) -> Result<()> {
+        let out = Command::new(&self.path).args(["help", "print"]).output()?;
+        let help = String::from_utf8_lossy(&out.stdout) + String::from_utf8_lossy(&out.stderr);
+        if !help.contains("--json") {
+            return Err(Error::JfrTool(format!(
+                "{}{} does not support print --json",
+                self.path.display(),
+                self.version
+                    .map(|v| format!(" (JDK {})", v))
+                    .unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn print_json(&self, recording: &Path) -> Result<Vec<u8>> {
+        let out = Command::new(&self.path)
+            .arg("print")
+            .arg("--json")
+            .arg(recording)
+            .output()?;
+        if !out.status.success() {
+            return Err(Error::JfrFailed(
+                String::from_utf8_lossy(&out.stderr).into_owned(),
+            ));
+        }
+        Ok(out.stdout)
+    }
+}
+
+fn bin(java_home: &Path, tool: &str) -> PathBuf {
+    java_home
+        .join("bin")
+        .join(format!("{}{}", tool, env::consts::EXE_SUFFIX))
+}
+
+fn search_path(tool: &str) -> Vec<PathBuf> {
+    env::var_os("PATH")
+        .map(|path| {
+            env::split_paths(&path)
+                .map(|dir| dir.join(format!("{}{}", tool, env::consts::EXE_SUFFIX)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `java -XshowSettings:properties -version` prints `java.home = ...` to stderr.
+/// With JDK 8 that is the `jre` directory, the tools are one level up.
+fn java_home_property() -> Vec<PathBuf> {
+    let Ok(out) = Command::new("java")
+        .args(["-XshowSettings:properties", "-version"])
+        .output()
+    else {
+        return Vec::new();
+    };
+    let settings = String::from_utf8_lossy(&out.stderr);
+    let Some(java_home) = settings
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("java.home = "))
+        .map(PathBuf::from)
+    else {
+        return Vec::new();
+    };
+    let mut homes = vec![java_home.clone()];
+    if java_home.ends_with("jre") {
+        homes.extend(java_home.parent().map(Path::to_path_buf));
+    }
+    homes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn version(feature: u32, update: u32) -> Option<JdkVersion> {
+        Some(JdkVersion { feature, update })
+    }
+
+    #[test]
+    fn versions() {
+        assert_eq!(JdkVersion::parse("11.0.2+9"), version(11, 2));
+        assert_eq!(JdkVersion::parse("17"), version(17, 0));
+        assert_eq!(JdkVersion::parse("21.0.4"), version(21, 4));
+        assert_eq!(JdkVersion::parse("1.8.0_392"), version(8, 392));
+        assert_eq!(JdkVersion::parse("\"17.0.15\""), version(17, 15));
+        assert_eq!(JdkVersion::parse("22-ea"), version(22, 0));
+        assert_eq!(JdkVersion::parse(""), None);
+        assert_eq!(JdkVersion::parse("openjdk"), None);
+    }
+
+    #[test]
+    fn ordering_and_display() {
+        assert!(JdkVersion::parse("1.8.0_392") < JdkVersion::parse("11.0.2+9"));
+        assert!(JdkVersion::parse("17.0.2") < JdkVersion::parse("17.0.15"));
+        assert_eq!(
+            JdkVersion::parse("1.8.0_392").unwrap().to_string(),
+            "8.0.392"
+        );
+    }
+
+    #[test]
+    fn release_file() {
+        let java_home = tempfile::tempdir().unwrap();
+        assert_eq!(JdkVersion::of_java_home(java_home.path()), None);
+        fs::write(
+            java_home.path().join("release"),
+            "IMPLEMENTOR=\"Eclipse Adoptium\"\nJAVA_VERSION=\"21.0.4\"\n",
+        )
+        .unwrap();
+        assert_eq!(JdkVersion::of_java_home(java_home.path()), version(21, 4));
+    }
+}
@@ -1,6 +1,9 @@
 use chrono::{DateTime, Utc};
-use iso8601::Duration;
-use serde::{Deserialize, Deserializer};
+use serde::{de::SeqAccess, de::Visitor, Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, time::Duration};
+
+use crate::{error::Diagnostics, jdk::JdkVersion};
 
 /*
 {
@@ -15,9 +18,24 @@ pub struct JfrMain {
     pub recording: JfrRecording,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(from = "RawRecording")]
 pub struct JfrRecording {
     pub events: Vec<JfrEvent>,
+    /// Events that could not be parsed, and whether the recording was cut short.
+    pub diagnostics: Diagnostics,
+}
+
+#[derive(Deserialize)]
+struct RawRecording {
+    #[serde(deserialize_with = "deser_events")]
+    events: JfrRecording,
+}
+
+impl From<RawRecording> for JfrRecording {
+    fn from(raw: RawRecording) -> Self {
+        raw.events
+    }
 }
 /*
 {
@@ -39,7 +57,7 @@ pub struct TenuringDistribution {
     pub age: u64,
     pub size: u64,
 }
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GCWhen {
     #[serde(rename = "Before GC")]
     Before,
@@ -77,6 +95,9 @@ pub enum GCWhen {
 pub struct PromoteObjectOutsidePLAB {
     #[serde(deserialize_with = "deser_ts_ms")]
     pub start_time: DateTime<Utc>,
+    /// The GC worker that copied the object.
+    #[serde(default)]
+    pub event_thread: Option<EventThread>,
     pub gc_id: u64,
     pub object_size: u64,
     pub tenuring_age: u64,
@@ -117,6 +138,9 @@ pub struct PromoteObjectOutsidePLAB {
 pub struct PromoteObjectInNewPLAB {
     #[serde(deserialize_with = "deser_ts_ms")]
     pub start_time: DateTime<Utc>,
+    /// The GC worker that copied the object.
+    #[serde(default)]
+    pub event_thread: Option<EventThread>,
     pub gc_id: u64,
     pub object_size: u64,
     pub tenuring_age: u64,
@@ -148,6 +172,15 @@ pub struct GCHeapSummary {
     pub gc_id: u64,
     #[serde(rename = "heapUsed")]
     pub heap_used: u64,
+    /// Missing when read from a GC log without "Heap Max Capacity".
+    #[serde(rename = "heapSpace", default)]
+    pub heap_space: Option<VirtualSpace>,
+}
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VirtualSpace {
+    pub committed_size: u64,
+    pub reserved_size: u64,
 }
 /*
 "startTime": "2024-07-01T09:20:16.230469750+02:00",
@@ -171,6 +204,44 @@ pub struct G1HeapSummary {
     pub eden_total: u64,
     #[serde(rename = "survivorUsedSize")]
     pub survivor_used: u64,
+    /// Not recorded by JFR, only known when read from a GC log.
+    #[serde(skip)]
+    pub humongous_used: Option<u64>,
+    /// Committed regions, missing when read from a GC log.
+    #[serde(rename = "numberOfRegions", default)]
+    pub number_of_regions: Option<u64>,
+}
+/*
+{
+  "type": "jdk.EvacuationInformation",
+  "values": {
+    "startTime": "2026-10-18T18:08:12.812474662Z",
+    "gcId": 3,
+    "cSetRegions": 12,
+    "cSetUsedBefore": 12570088,
+    "cSetUsedAfter": 0,
+    "allocationRegions": 2,
+    "allocationRegionsUsedBefore": 0,
+    "allocationRegionsUsedAfter": 1848336,
+    "bytesCopied": 1848336,
+    "regionsFreed": 12
+  }
+}
+*/
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EvacuationInformation {
+    #[serde(deserialize_with = "deser_ts_ms")]
+    pub start_time: DateTime<Utc>,
+    pub gc_id: u64,
+    #[serde(rename = "cSetRegions")]
+    pub cset_regions: u64,
+    #[serde(rename = "cSetUsedBefore")]
+    pub cset_used_before: u64,
+    #[serde(rename = "cSetUsedAfter")]
+    pub cset_used_after: u64,
+    pub bytes_copied: u64,
+    pub regions_freed: u64,
 }
 /*
 {
@@ -235,7 +306,9 @@ pub struct GarbageCollection {
     pub duration: Duration,
     #[serde(rename = "gcId")]
     pub gc_id: u64,
+    #[serde(deserialize_with = "deser_text")]
     pub name: String,
+    #[serde(deserialize_with = "deser_text")]
     pub cause: String,
     #[serde(rename = "sumOfPauses", deserialize_with = "deser_dur")]
     pub sum_of_pauses: Duration,
@@ -263,10 +336,13 @@ pub struct GarbageCollection {
 */
 #[derive(Deserialize, Debug, Clone)]
 pub struct EventThread {
-    #[serde(rename = "osName")]
+    #[serde(rename = "osName", deserialize_with = "deser_text")]
     pub os_name: String,
     #[serde(rename = "osThreadId")]
     pub os_thread_id: u64,
+    /// Only reported by JDK 19 and later.
+    #[serde(rename = "virtual", default)]
+    pub is_virtual: Option<bool>,
 }
 #[derive(Deserialize, Debug, Clone)]
 pub struct GCPhasePause {
@@ -274,10 +350,12 @@ pub struct GCPhasePause {
     pub start_time: DateTime<Utc>,
     #[serde(deserialize_with = "deser_dur")]
     pub duration: Duration,
-    #[serde(rename = "eventThread")]
-    pub event_thread: EventThread,
+    /// Missing when the pause was read from a GC log.
+    #[serde(rename = "eventThread", default)]
+    pub event_thread: Option<EventThread>,
     #[serde(rename = "gcId")]
     pub gc_id: u64,
+    #[serde(deserialize_with = "deser_text")]
     pub name: String,
 }
 
@@ -290,12 +368,17 @@ pub struct GCPhasePause {
   "type": "Normal"
 }
 */
-#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[derive(
+    Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, PartialOrd, Eq, Ord, Hash,
+)]
 pub enum CollectionType {
+    /// JDK 11 to 14 call a young GC during a concurrent marking "During Mark".
+    #[serde(alias = "During Mark")]
     Normal,
     #[serde(rename = "Prepare Mixed")]
     PrepareMixed,
-    #[serde(rename = "Concurrent Start")]
+    /// Called "Initial Mark" up to JDK 14.
+    #[serde(rename = "Concurrent Start", alias = "Initial Mark")]
     ConcurrentStart,
     Mixed,
     #[serde(other)]
@@ -312,6 +395,187 @@ pub struct G1GarbageCollection {
     #[serde(rename = "type")]
     pub type_: CollectionType,
 }
+/*
+"type": "jdk.G1MMU",
+"values": {
+  "startTime": "2026-10-18T18:08:12.812050762Z",
+  "gcId": 3,
+  "timeSlice": "PT0.201S",
+  "gcTime": "PT0.004S",
+  "pauseTarget": "PT0.2S"
+}
+*/
+/// G1's own bookkeeping of GC time in the latest `time_slice`, see -XX:MaxGCPauseMillis
+/// and -XX:GCPauseIntervalMillis.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct G1MMU {
+    #[serde(deserialize_with = "deser_ts_ms")]
+    pub start_time: DateTime<Utc>,
+    pub gc_id: u64,
+    #[serde(deserialize_with = "deser_dur")]
+    pub time_slice: Duration,
+    #[serde(deserialize_with = "deser_dur")]
+    pub gc_time: Duration,
+    #[serde(deserialize_with = "deser_dur")]
+    pub pause_target: Duration,
+}
+/*
+"type": "jdk.EvacuationFailed",
+"values": {
+  "startTime": "2026-10-18T18:44:47.372465031Z",
+  "gcId": 26,
+  "evacuationFailed": {
+    "objectCount": 52,
+    "firstSize": 2936,
+    "smallestSize": 128,
+    "totalSize": 84536
+  }
+}
+*/
+/// A young GC that could not copy some objects, survivor and old regions ran out.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EvacuationFailed {
+    #[serde(deserialize_with = "deser_ts_ms")]
+    pub start_time: DateTime<Utc>,
+    pub gc_id: u64,
+    /// Missing when read from a GC log.
+    #[serde(default)]
+    pub evacuation_failed: Option<CopyFailed>,
+}
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyFailed {
+    pub object_count: u64,
+    pub total_size: u64,
+}
+/*
+"type": "jdk.G1EvacuationYoungStatistics",
+"values": {
+  "startTime": "2026-10-18T18:08:12.812016927Z",
+  "statistics": {
+    "gcId": 3,
+    "allocated": 1433600,
+    "wasted": 2376,
+    "used": 1263184,
+    "undoWaste": 0,
+    "regionEndWaste": 152576,
+    "regionsRefilled": 2,
+    "numPlabsFilled": 0,
+    "directAllocated": 262160,
+    "numDirectAllocated": 0,
+    "failureUsed": 0,
+    "failureWaste": 0
+  }
+}
+*/
+/// PLAB allocation into survivor or old regions during a GC, in bytes.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct G1EvacuationStatistics {
+    pub gc_id: u64,
+    /// Given out as PLABs.
+    pub allocated: u64,
+    /// Left unused at the end of retired PLABs.
+    pub wasted: u64,
+    pub used: u64,
+    /// Of objects another worker copied first.
+    pub undo_waste: u64,
+    /// Left at the end of regions too full for another PLAB.
+    pub region_end_waste: u64,
+    pub regions_refilled: u64,
+    pub num_plabs_filled: u64,
+    /// Objects too large for a PLAB, allocated directly in the region.
+    pub direct_allocated: u64,
+    pub num_direct_allocated: u64,
+    /// Of PLABs in regions that failed evacuation.
+    pub failure_used: u64,
+    pub failure_waste: u64,
+}
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct G1EvacuationStatisticsEvent {
+    #[serde(deserialize_with = "deser_ts_ms")]
+    pub start_time: DateTime<Utc>,
+    pub statistics: G1EvacuationStatistics,
+}
+/*
+"type": "jdk.UnsignedLongFlag",
+"values": {
+  "startTime": "2026-10-18T18:08:12.779618295Z",
+  "name": "MaxGCPauseMillis",
+  "value": 200,
+  "origin": "Default"
+}
+*/
+/// A JVM flag, from jdk.IntFlag, jdk.BooleanFlag and the other flag events, which are
+/// written at the start and end of every chunk.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Flag {
+    #[serde(deserialize_with = "deser_ts_ms")]
+    pub start_time: DateTime<Utc>,
+    #[serde(deserialize_with = "deser_text")]
+    pub name: String,
+    pub value: Value,
+    /// "Default", "Command line", "Ergonomic" and so on.
+    #[serde(deserialize_with = "deser_text")]
+    pub origin: String,
+}
+
+impl Flag {
+    /// Numeric value, `jfr print --xml` writes numbers and booleans as text.
+    pub fn number(&self) -> Option<f64> {
+        match &self.value {
+            Value::Number(n) => n.as_f64(),
+            Value::Bool(b) => Some(if *b { 1f64 } else { 0f64 }),
+            Value::String(s) => match s.as_str() {
+                "true" => Some(1f64),
+                "false" => Some(0f64),
+                _ => s.parse().ok(),
+            },
+            _ => None,
+        }
+    }
+}
+
+/*
+"type": "jdk.JVMInformation",
+"values": {
+  "startTime": "2026-10-18T18:08:12.775768609Z",
+  "jvmName": "OpenJDK 64-Bit Server VM",
+  "jvmVersion": "OpenJDK 64-Bit Server VM (17.0.15+6-Debian-1deb12u1) for linux-amd64 JRE (17.0.15+6-Debian-1deb12u1), built on May  1 2025 09:21:09 by \"buildd\" with gcc 12.2.0",
+  "jvmArguments": "-XX:+UseG1GC -Xmx128m",
+  "jvmFlags": null,
+  "javaArguments": "App",
+  "jvmStartTime": "2026-10-18T18:08:11.983Z",
+  "pid": 5237
+}
+*/
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JVMInformation {
+    #[serde(deserialize_with = "deser_ts_ms")]
+    pub start_time: DateTime<Utc>,
+    #[serde(deserialize_with = "deser_text")]
+    pub jvm_name: String,
+    #[serde(deserialize_with = "deser_text")]
+    pub jvm_version: String,
+    #[serde(default, deserialize_with = "deser_opt_text")]
+    pub jvm_arguments: Option<String>,
+    #[serde(deserialize_with = "deser_ts_ms")]
+    pub jvm_start_time: DateTime<Utc>,
+}
+
+impl JVMInformation {
+    /// The version in the first parentheses of `jvmVersion`.
+    pub fn jdk_version(&self) -> Option<JdkVersion> {
+        let (_, version) = self.jvm_version.split_once('(')?;
+        JdkVersion::parse(version.split(')').next()?)
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(tag = "type")]
 pub enum JfrEvent {
@@ -329,16 +593,100 @@ pub enum JfrEvent {
     OldGarbageCollection { values: OldGarbageCollection },
     #[serde(rename = "jdk.GCPhasePause")]
     GCPhasePause { values: GCPhasePause },
+    #[serde(rename = "jdk.GCPhasePauseLevel1")]
+    GCPhasePauseLevel1 { values: GCPhasePause },
     #[serde(rename = "jdk.YoungGarbageCollection")]
     YoungGarbageCollection { values: YoungGarbageCollection },
     #[serde(rename = "jdk.PromoteObjectOutsidePLAB")]
     PromoteObjectOutsidePLAB { values: PromoteObjectOutsidePLAB },
     #[serde(rename = "jdk.PromoteObjectInNewPLAB")]
     PromoteObjectInNewPLAB { values: PromoteObjectInNewPLAB },
+    #[serde(rename = "jdk.EvacuationInformation")]
+    EvacuationInformation { values: EvacuationInformation },
+    #[serde(rename = "jdk.G1MMU")]
+    G1MMU { values: G1MMU },
+    #[serde(rename = "jdk.EvacuationFailed")]
+    EvacuationFailed { values: EvacuationFailed },
+    #[serde(rename = "jdk.G1EvacuationYoungStatistics")]
+    G1EvacuationYoungStatistics { values: G1EvacuationStatisticsEvent },
+    #[serde(rename = "jdk.G1EvacuationOldStatistics")]
+    G1EvacuationOldStatistics { values: G1EvacuationStatisticsEvent },
+    #[serde(rename = "jdk.JVMInformation")]
+    JVMInformation { values: JVMInformation },
+    #[serde(rename = "jdk.IntFlag")]
+    IntFlag { values: Flag },
+    #[serde(rename = "jdk.UnsignedIntFlag")]
+    UnsignedIntFlag { values: Flag },
+    #[serde(rename = "jdk.LongFlag")]
+    LongFlag { values: Flag },
+    #[serde(rename = "jdk.UnsignedLongFlag")]
+    UnsignedLongFlag { values: Flag },
+    #[serde(rename = "jdk.DoubleFlag")]
+    DoubleFlag { values: Flag },
+    #[serde(rename = "jdk.BooleanFlag")]
+    BooleanFlag { values: Flag },
+    #[serde(rename = "jdk.StringFlag")]
+    StringFlag { values: Flag },
+    /// An application event, that is any event type outside the `jdk.` namespace.
+    #[serde(skip_deserializing)]
+    Custom(CustomEvent),
     #[serde(other)]
     Unkown,
 }
 
+/*
+{
+  "type": "com.acme.RequestCompleted",
+  "values": {
+    "startTime": "2024-07-21T11:47:47.368046372+02:00",
+    "duration": "PT0.012S",
+    "eventThread": {...},
+    "latency": "PT0.0113S"
+  }
+}
+*/
+#[derive(Debug, Clone)]
+pub struct CustomEvent {
+    pub event_type: String,
+    pub start_time: Option<DateTime<Utc>>,
+    pub duration: Option<Duration>,
+    pub values: Map<String, Value>,
+}
+
+impl CustomEvent {
+    fn from_value(event_type: &str, value: &Value) -> Option<CustomEvent> {
+        let values = value.get("values")?.as_object()?.clone();
+        let start_time = values
+            .get("startTime")
+            .and_then(Value::as_str)
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|dt| dt.with_timezone(&Utc));
+        let duration = values
+            .get("duration")
+            .and_then(Value::as_str)
+            .and_then(parse_duration);
+        Some(CustomEvent {
+            event_type: event_type.to_string(),
+            start_time,
+            duration,
+            values,
+        })
+    }
+
+    /// Numeric value of `field`. Timespans are converted to milliseconds, numbers
+    /// `jfr print --xml` kept as text are parsed.
+    pub fn field(&self, field: &str) -> Option<f64> {
+        match self.values.get(field)? {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => parse_duration(s)
+                .map(|d| dur_ms(&d))
+                .or_else(|| s.parse().ok().filter(|n: &f64| n.is_finite())),
+            Value::Bool(b) => Some(if *b { 1f64 } else { 0f64 }),
+            _ => None,
+        }
+    }
+}
+
 impl JfrEvent {
     pub fn gc_id(&self) -> Option<u64> {
         match &self {
@@ -352,11 +700,243 @@ impl JfrEvent {
             JfrEvent::OldGarbageCollection { values } => Some(values.gc_id),
             JfrEvent::YoungGarbageCollection { values } => Some(values.gc_id),
             JfrEvent::GCPhasePause { values } => Some(values.gc_id),
+            JfrEvent::GCPhasePauseLevel1 { values } => Some(values.gc_id),
+            JfrEvent::EvacuationInformation { values } => Some(values.gc_id),
+            JfrEvent::G1MMU { values } => Some(values.gc_id),
+            JfrEvent::EvacuationFailed { values } => Some(values.gc_id),
+            JfrEvent::G1EvacuationYoungStatistics { values }
+            | JfrEvent::G1EvacuationOldStatistics { values } => Some(values.statistics.gc_id),
+            JfrEvent::JVMInformation { .. }
+            | JfrEvent::IntFlag { .. }
+            | JfrEvent::UnsignedIntFlag { .. }
+            | JfrEvent::LongFlag { .. }
+            | JfrEvent::UnsignedLongFlag { .. }
+            | JfrEvent::DoubleFlag { .. }
+            | JfrEvent::BooleanFlag { .. }
+            | JfrEvent::StringFlag { .. }
+            | JfrEvent::Custom(_)
+            | JfrEvent::Unkown => None,
+        }
+    }
+
+    pub fn flag(&self) -> Option<&Flag> {
+        match &self {
+            JfrEvent::IntFlag { values }
+            | JfrEvent::UnsignedIntFlag { values }
+            | JfrEvent::LongFlag { values }
+            | JfrEvent::UnsignedLongFlag { values }
+            | JfrEvent::DoubleFlag { values }
+            | JfrEvent::BooleanFlag { values }
+            | JfrEvent::StringFlag { values } => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn start_time(&self) -> Option<DateTime<Utc>> {
+        match &self {
+            JfrEvent::TenuringDistribution { values } => Some(values.start_time),
+            JfrEvent::GCHeapSummary { values } => Some(values.start_time),
+            JfrEvent::G1HeapSummary { values } => Some(values.start_time),
+            JfrEvent::PromoteObjectOutsidePLAB { values } => Some(values.start_time),
+            JfrEvent::PromoteObjectInNewPLAB { values } => Some(values.start_time),
+            JfrEvent::G1GarbageCollection { values } => Some(values.start_time),
+            JfrEvent::GarbageCollection { values } => Some(values.start_time),
+            JfrEvent::OldGarbageCollection { values } => Some(values.start_time),
+            JfrEvent::YoungGarbageCollection { values } => Some(values.start_time),
+            JfrEvent::GCPhasePause { values } => Some(values.start_time),
+            JfrEvent::GCPhasePauseLevel1 { values } => Some(values.start_time),
+            JfrEvent::EvacuationInformation { values } => Some(values.start_time),
+            JfrEvent::G1MMU { values } => Some(values.start_time),
+            JfrEvent::EvacuationFailed { values } => Some(values.start_time),
+            JfrEvent::G1EvacuationYoungStatistics { values }
+            | JfrEvent::G1EvacuationOldStatistics { values } => Some(values.start_time),
+            JfrEvent::JVMInformation { values } => Some(values.start_time),
+            JfrEvent::IntFlag { values }
+            | JfrEvent::UnsignedIntFlag { values }
+            | JfrEvent::LongFlag { values }
+            | JfrEvent::UnsignedLongFlag { values }
+            | JfrEvent::DoubleFlag { values }
+            | JfrEvent::BooleanFlag { values }
+            | JfrEvent::StringFlag { values } => Some(values.start_time),
+            JfrEvent::Custom(custom) => custom.start_time,
             JfrEvent::Unkown => None,
         }
     }
 }
 
+impl JfrMain {
+    /// Timestamp of the earliest event, used as the origin of time based charts.
+    pub fn start_time(&self) -> Option<DateTime<Utc>> {
+        self.recording
+            .events
+            .iter()
+            .filter_map(JfrEvent::start_time)
+            .min()
+    }
+
+    /// Events from `from` up to `to`, every event of a GC goes with its start.
+    /// jdk.JVMInformation and the flags describe the whole recording and are kept.
+    pub fn between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> JfrMain {
+        let gc_starts = self
+            .gc_cycles()
+            .into_iter()
+            .filter_map(|cycle| Some((cycle.gc_id, cycle.start_time?)))
+            .collect::<HashMap<_, _>>();
+        let events = self
+            .recording
+            .events
+            .iter()
+            .filter(|evt| {
+                let start = match evt.gc_id().and_then(|gc_id| gc_starts.get(&gc_id)) {
+                    Some(start) => Some(*start),
+                    None => evt.start_time(),
+                };
+                matches!(evt, JfrEvent::JVMInformation { .. })
+                    || evt.flag().is_some()
+                    || start.is_some_and(|start| start >= from && start < to)
+            })
+            .cloned()
+            .collect();
+        JfrMain {
+            recording: JfrRecording {
+                events,
+                diagnostics: self.recording.diagnostics.clone(),
+            },
+        }
+    }
+
+    /// GC pauses as (start, end) in ms since `origin`, sorted by start.
+    pub fn pause_intervals(&self, origin: DateTime<Utc>) -> Vec<(f64, f64)> {
+        let mut pauses = self
+            .recording
+            .events
+            .iter()
+            .filter_map(|e| {
+                if let JfrEvent::GCPhasePause { values } = e {
+                    let start = ms_since(origin, values.start_time);
+                    Some((start, start + dur_ms(&values.duration)))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        pauses.sort_by(|a, b| a.0.total_cmp(&b.0));
+        pauses
+    }
+
+    /// When the recorded JVM started, GC logs do not have it.
+    pub fn jvm_start_time(&self) -> Option<DateTime<Utc>> {
+        self.recording.events.iter().find_map(|e| {
+            if let JfrEvent::JVMInformation { values } = e {
+                Some(values.jvm_start_time)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Version of the JVM that made the recording.
+    pub fn jdk_version(&self) -> Option<JdkVersion> {
+        self.recording.events.iter().find_map(|e| {
+            if let JfrEvent::JVMInformation { values } = e {
+                values.jdk_version()
+            } else {
+                None
+            }
+        })
+    }
+
+    /// JVM flags by name, the latest value of each.
+    pub fn flags(&self) -> HashMap<String, Flag> {
+        self.recording
+            .events
+            .iter()
+            .filter_map(JfrEvent::flag)
+            .map(|flag| (flag.name.clone(), flag.clone()))
+            .collect()
+    }
+}
+
+pub fn ms_since(origin: DateTime<Utc>, ts: DateTime<Utc>) -> f64 {
+    (ts - origin).num_microseconds().unwrap_or(i64::MAX) as f64 / 1000f64
+}
+
+/// Duration in fractional milliseconds.
+pub fn dur_ms(dur: &Duration) -> f64 {
+    dur.as_secs_f64() * 1000f64
+}
+
+/// Parses the ISO 8601 durations `java.time.Duration` prints, e.g. `PT0.004761741S` or
+/// `PT1M2.5S`, keeping the nanoseconds JFR records.
+pub fn parse_duration(dur: &str) -> Option<Duration> {
+    let mut rest = dur.strip_prefix("PT").filter(|rest| !rest.is_empty())?;
+    let mut secs = 0f64;
+    while !rest.is_empty() {
+        let unit_ix = rest.find(['H', 'M', 'S'])?;
+        let value = rest[..unit_ix].parse::<f64>().ok()?;
+        secs += value
+            * match &rest[unit_ix..unit_ix + 1] {
+                "H" => 3600f64,
+                "M" => 60f64,
+                _ => 1f64,
+            };
+        rest = &rest[unit_ix + 1..];
+    }
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+impl JfrRecording {
+    /// Adds an event given as `jfr print --json` produces it.
+    /// Events that fail to parse are counted in the diagnostics instead.
+    pub fn push_value(&mut self, value: Value) {
+        let type_ = value.get("type").and_then(Value::as_str);
+        let evt = match JfrEvent::deserialize(&value) {
+            Ok(evt) => evt,
+            Err(e) => {
+                self.diagnostics
+                    .skip(type_.unwrap_or("<no type>"), skip_reason(&e));
+                return;
+            }
+        };
+        let evt = match (&evt, type_) {
+            // we keep application events, the rest of the JDK events are dropped
+            (JfrEvent::Unkown, Some(type_)) if !type_.starts_with("jdk.") => {
+                CustomEvent::from_value(type_, &value)
+                    .map(JfrEvent::Custom)
+                    .unwrap_or(evt)
+            }
+            _ => evt,
+        };
+        self.events.push(evt);
+    }
+}
+
+fn deser_events<'de, D>(deserializer: D) -> Result<JfrRecording, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct EventsVisitor;
+    impl<'de> Visitor<'de> for EventsVisitor {
+        type Value = JfrRecording;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a list of JFR events")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut recording = JfrRecording::default();
+            while let Some(value) = seq.next_element::<Value>()? {
+                recording.push_value(value);
+            }
+            Ok(recording)
+        }
+    }
+    deserializer.deserialize_seq(EventsVisitor)
+}
+
 fn deser_ts_ms<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
 where
     D: Deserializer<'de>,
@@ -367,14 +947,200 @@ where
         .map_err(|e| serde::de::Error::custom(e.to_string()))
 }
 
+/// Why an event was skipped without what is particular to it, the value and where it
+/// was, so that the events broken the same way are counted together:
+/// `invalid type: string "abc", expected u64 at line 3 column 7` is
+/// `invalid type: string, expected u64`.
+fn skip_reason(e: &serde_json::Error) -> String {
+    let msg = e.to_string();
+    let mut rest = match (e.line(), msg.rfind(" at line ")) {
+        (1.., Some(at)) => &msg[..at],
+        _ => msg.as_str(),
+    };
+    let mut reason = String::new();
+    // the value follows its kind, quoted or in backticks
+    for kind in [
+        "string ",
+        "integer ",
+        "floating point ",
+        "boolean ",
+        "char ",
+        "variant ",
+    ] {
+        let Some(at) = rest.find(kind) else {
+            continue;
+        };
+        let value = &rest[at + kind.len()..];
+        let end = match value.chars().next() {
+            Some(quote @ ('"' | '`')) => value[1..].find(quote).map(|end| end + 2),
+            _ => None,
+        };
+        if let Some(end) = end {
+            reason.push_str(rest[..at + kind.len()].trim_end());
+            rest = &value[end..];
+        }
+    }
+    reason.push_str(rest);
+    reason
+}
+
+/// A string, `jfr print --xml` has no types so text that looks like a number or a
+/// boolean, a thread named "42", arrives as one.
+fn deser_text<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deser_opt_text(deserializer)?.ok_or_else(|| serde::de::Error::custom("expected a string"))
+}
+
+fn deser_opt_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(None),
+        Value::String(text) => Ok(Some(text)),
+        Value::Number(n) => Ok(Some(n.to_string())),
+        Value::Bool(b) => Ok(Some(b.to_string())),
+        _ => Err(serde::de::Error::custom("expected a string")),
+    }
+}
+
 #[allow(unused)]
 fn deser_dur<'de, D>(deserializer: D) -> Result<Duration, D::Error>
 where
     D: Deserializer<'de>,
 {
     let dur_str = String::deserialize(deserializer)?; // Deserialize as i64
-    let dur = dur_str
-        .parse::<Duration>()
-        .map_err(|e| serde::de::Error::custom(e.to_string()))?;
+    let dur = parse_duration(&dur_str)
+        .ok_or_else(|| serde::de::Error::custom("expected an ISO 8601 duration"))?;
     Ok(dur)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn java_durations() {
+        assert_eq!(parse_duration("PT0S"), Some(Duration::ZERO));
+        assert_eq!(
+            parse_duration("PT0.004761741S"),
+            Some(Duration::from_nanos(4_761_741))
+        );
+        assert_eq!(
+            parse_duration("PT0.000017041S"),
+            Some(Duration::from_nanos(17_041))
+        );
+        assert_eq!(
+            parse_duration("PT12.5S"),
+            Some(Duration::from_millis(12_500))
+        );
+        assert_eq!(
+            parse_duration("PT1M2.5S"),
+            Some(Duration::from_millis(62_500))
+        );
+        assert_eq!(parse_duration("PT2H"), Some(Duration::from_secs(7_200)));
+        assert_eq!(
+            parse_duration("PT1H1M1.000000001S"),
+            Some(Duration::new(3_661, 1))
+        );
+        assert_eq!(
+            parse_duration("PT26H3M12.123456789S"),
+            Some(Duration::new(93_792, 123_456_789))
+        );
+    }
+
+    #[test]
+    fn invalid_durations() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("PT"), None);
+        assert_eq!(parse_duration("P1D"), None);
+        assert_eq!(parse_duration("0.5S"), None);
+        assert_eq!(parse_duration("PT0.5"), None);
+        assert_eq!(parse_duration("PTxS"), None);
+        assert_eq!(parse_duration("PT-0.5S"), None);
+        assert_eq!(parse_duration("PTNaNS"), None);
+    }
+
+    #[test]
+    fn collection_types_of_older_jdks() {
+        let types = [
+            "Normal",
+            "During Mark",
+            "Initial Mark",
+            "Concurrent Start",
+            "Full",
+        ]
+        .map(|name| serde_json::from_value::<CollectionType>(Value::from(name)).unwrap());
+        assert_eq!(
+            types,
+            [
+                CollectionType::Normal,
+                CollectionType::Normal,
+                CollectionType::ConcurrentStart,
+                CollectionType::ConcurrentStart,
+                CollectionType::Unknown,
+            ]
+        );
+    }
+
+    #[test]
+    fn skipped_events_of_a_type_share_a_reason() {
+        let mut recording = JfrRecording::default();
+        for gc_id in ["abc", "xyz"] {
+            recording.push_value(serde_json::json!({
+                "type": "jdk.GCPhasePause",
+                "values": {
+                    "startTime": "2026-10-18T18:20:07.118Z",
+                    "duration": "PT0.001S",
+                    "gcId": gc_id,
+                    "name": "GC Pause",
+                },
+            }));
+        }
+        for duration in ["PTxS", "PT1.5"] {
+            recording.push_value(serde_json::json!({
+                "type": "jdk.GCPhasePause",
+                "values": {
+                    "startTime": "2026-10-18T18:20:07.118Z",
+                    "duration": duration,
+                    "gcId": 1,
+                    "name": "GC Pause",
+                },
+            }));
+        }
+        assert!(recording.events.is_empty());
+        let reasons = &recording.diagnostics.skipped["jdk.GCPhasePause"];
+        assert_eq!(reasons.len(), 2, "{:?}", reasons);
+        assert_eq!(reasons["invalid type: string, expected u64"], 2);
+        assert_eq!(reasons["expected an ISO 8601 duration"], 2);
+    }
+
+    #[test]
+    fn skip_reasons_leave_out_values_and_positions() {
+        let e = serde_json::from_str::<u64>(r#""abc""#).unwrap_err();
+        assert_eq!(skip_reason(&e), "invalid type: string, expected u64");
+        let e = serde_json::from_str::<u64>("-1").unwrap_err();
+        assert_eq!(skip_reason(&e), "invalid value: integer, expected u64");
+        let e = serde_json::from_str::<GCWhen>(r#""Sometime""#).unwrap_err();
+        assert_eq!(
+            skip_reason(&e),
+            "unknown variant, expected `Before GC` or `After GC`"
+        );
+        let e = serde_json::from_value::<GCPhasePause>(serde_json::json!({})).unwrap_err();
+        assert_eq!(skip_reason(&e), "missing field `startTime`");
+    }
+
+    #[test]
+    fn durations_in_ms() {
+        assert_eq!(dur_ms(&Duration::from_micros(4_875)), 4.875);
+        let custom = CustomEvent::from_value(
+            "com.acme.Request",
+            &serde_json::json!({
+                "values": {"startTime": "2026-10-18T18:20:07.118Z", "latency": "PT0.25S"},
+            }),
+        );
+        assert_eq!(custom.and_then(|evt| evt.field("latency")), Some(250f64));
+    }
+}
@@ -0,0 +1,278 @@
+use std::fmt;
+
+use plotly::{
+    common::{DashType, Line, Mode},
+    layout::Axis,
+    Layout, Plot, Scatter,
+};
+use serde::Serialize;
+
+use crate::{
+    jfr::{ms_since, JfrEvent, JfrMain},
+    mixed::mixed_report,
+    stats::{linear_fit, LinearFit},
+    MB,
+};
+
+/// Significance of the upward slope to call it a leak.
+const ALPHA: f64 = 0.05;
+
+/// Where a live set estimate comes from, in order of how little garbage it includes.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveSetSource {
+    /// Heap used after a full GC, which leaves only live objects.
+    FullGc,
+    /// Old bytes after the last mixed GC of a marking.
+    Mixed,
+    /// Old bytes after a marking with no mixed GCs, G1 found too little garbage to collect.
+    Marking,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LiveSetSample {
+    pub gc_id: u64,
+    /// End of the GC, in seconds since the recording started.
+    pub at_s: f64,
+    pub live: u64,
+    pub source: LiveSetSource,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct LeakReport {
+    pub samples: Vec<LiveSetSample>,
+    /// Live bytes over seconds.
+    pub trend: Option<LinearFit>,
+    /// Reserved heap, from jdk.GCHeapSummary.
+    pub max_heap: Option<u64>,
+    /// The live set grows with a p-value under 0.05.
+    pub leak: bool,
+    /// When the trend reaches `max_heap`, from the last sample.
+    pub exhaustion_in_s: Option<f64>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Leak {
+    pub plot: Plot,
+    pub report: LeakReport,
+}
+
+fn max_heap(jfr: &JfrMain) -> Option<u64> {
+    jfr.recording
+        .events
+        .iter()
+        .filter_map(|evt| match evt {
+            JfrEvent::GCHeapSummary { values } => Some(values.heap_space.as_ref()?.reserved_size),
+            _ => None,
+        })
+        .max()
+}
+
+/// The live set after every full GC and marking cycle.
+pub fn live_set(jfr: &JfrMain) -> Vec<LiveSetSample> {
+    let Some(origin) = jfr.start_time() else {
+        return Vec::new();
+    };
+    let cycles = jfr.gc_cycles();
+    let end_s = |gc_id: u64| {
+        let cycle = cycles.iter().find(|cycle| cycle.gc_id == gc_id)?;
+        Some((ms_since(origin, cycle.start_time?) + cycle.duration_ms) / 1000f64)
+    };
+    let mut samples = cycles
+        .iter()
+        .filter(|cycle| cycle.name == "G1Full")
+        .filter_map(|cycle| {
+            Some(LiveSetSample {
+                gc_id: cycle.gc_id,
+                at_s: end_s(cycle.gc_id)?,
+                live: cycle.after?.used,
+                source: LiveSetSource::FullGc,
+            })
+        })
+        .collect::<Vec<_>>();
+    for marking in mixed_report(jfr).markings {
+        let (gc_id, source) = match marking.mixed.last() {
+            Some(mixed) => (mixed.gc_id, LiveSetSource::Mixed),
+            None => (marking.gc_id, LiveSetSource::Marking),
+        };
+        let Some(cycle) = cycles.iter().find(|cycle| cycle.gc_id == gc_id) else {
+            continue;
+        };
+        if let (Some(at_s), Some(after)) = (end_s(gc_id), cycle.after) {
+            samples.push(LiveSetSample {
+                gc_id,
+                at_s,
+                live: after.old,
+                source,
+            });
+        }
+    }
+    samples.sort_by(|a, b| a.at_s.total_cmp(&b.at_s));
+    samples
+}
+
+pub fn leak_report(jfr: &JfrMain) -> LeakReport {
+    let samples = live_set(jfr);
+    let trend = linear_fit(
+        &samples
+            .iter()
+            .map(|s| (s.at_s, s.live as f64))
+            .collect::<Vec<_>>(),
+    );
+    let max_heap = max_heap(jfr);
+    let leak = trend.is_some_and(|t| t.slope > 0f64 && t.p_value < ALPHA);
+    let exhaustion_in_s = match (trend, max_heap, samples.last()) {
+        (Some(trend), Some(max), Some(last)) if leak => {
+            Some(((max as f64 - trend.at(last.at_s)) / trend.slope).max(0f64))
+        }
+        _ => None,
+    };
+    LeakReport {
+        samples,
+        trend,
+        max_heap,
+        leak,
+        exhaustion_in_s,
+    }
+}
+
+/// Live set samples by source, with the fitted trend and the heap limit.
+pub fn leak(jfr: &JfrMain) -> Leak {
+    let report = leak_report(jfr);
+    let mut plot = Plot::new();
+    for (name, source) in [
+        ("after full GC", LiveSetSource::FullGc),
+        ("after mixed GCs", LiveSetSource::Mixed),
+        ("after marking", LiveSetSource::Marking),
+    ] {
+        let samples = report
+            .samples
+            .iter()
+            .filter(|s| s.source == source)
+            .collect::<Vec<_>>();
+        plot.add_trace(
+            Scatter::new(
+                samples.iter().map(|s| s.at_s).collect(),
+                samples.iter().map(|s| s.live as f64 / MB).collect(),
+            )
+            .name(name)
+            .mode(Mode::Markers),
+        );
+    }
+    let xs = match (report.samples.first(), report.samples.last()) {
+        (Some(first), Some(last)) => vec![first.at_s, last.at_s],
+        _ => Vec::new(),
+    };
+    if let Some(trend) = report.trend {
+        plot.add_trace(
+            Scatter::new(xs.clone(), xs.iter().map(|x| trend.at(*x) / MB).collect())
+                .name(format!("trend, p={:.3}", trend.p_value))
+                .mode(Mode::Lines),
+        );
+    }
+    if let Some(max_heap) = report.max_heap {
+        plot.add_trace(
+            Scatter::new(xs, vec![max_heap as f64 / MB; 2])
+                .name("max heap")
+                .mode(Mode::Lines)
+                .line(Line::new().dash(DashType::Dash)),
+        );
+    }
+    plot.set_layout(
+        Layout::new()
+            .x_axis(Axis::new().title("seconds since recording start"))
+            .y_axis(Axis::new().title("live set (MB)")),
+    );
+    Leak { plot, report }
+}
+
+impl fmt::Display for LeakReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>8} {:>10} {:>12}  source",
+            "gc", "at (s)", "live (MB)"
+        )?;
+        for s in &self.samples {
+            writeln!(
+                f,
+                "{:>8} {:>10.3} {:>12.1}  {:?}",
+                s.gc_id,
+                s.at_s,
+                s.live as f64 / MB,
+                s.source
+            )?;
+        }
+        let Some(trend) = &self.trend else {
+            return writeln!(f, "too few samples for a trend");
+        };
+        writeln!(
+            f,
+            "trend {:+.3}MB/s over {} samples, r2 {:.2}, p {:.4}",
+            trend.slope / MB,
+            trend.n,
+            trend.r_squared,
+            trend.p_value
+        )?;
+        if !self.leak {
+            return writeln!(f, "no significant growth of the live set");
+        }
+        match self.exhaustion_in_s {
+            Some(seconds) => writeln!(f, "probable leak, the heap is exhausted in {:.0}s", seconds),
+            None => writeln!(f, "probable leak"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gclog;
+
+    /// Full GCs every 10s leaving `after` MB of a 128M heap.
+    fn recording(after: &[u64]) -> JfrMain {
+        let mut log = "[0.003s][info ][gc,init       ] Heap Max Capacity: 128M\n".to_string();
+        for (gc_id, after) in after.iter().enumerate() {
+            let at = (gc_id + 1) * 10;
+            log += &format!(
+                "[{at}.000s][info ][gc,start       ] GC({gc_id}) Pause Full (System.gc())
+[{at}.000s][info ][gc,heap        ] GC({gc_id}) Eden regions: 10->0(52)
+[{at}.000s][info ][gc,heap        ] GC({gc_id}) Survivor regions: 1->0(7)
+[{at}.000s][info ][gc] GC({gc_id}) Pause Full (System.gc()) 100M->{after}M(128M) 100.000ms\n"
+            );
+        }
+        gclog::parse(log.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn growing_live_set_is_a_leak() {
+        let report = leak_report(&recording(&[10, 21, 30, 41, 50, 61]));
+        assert_eq!(report.samples.len(), 6);
+        assert!(report
+            .samples
+            .iter()
+            .all(|s| s.source == LiveSetSource::FullGc));
+        assert_eq!(report.samples[1].live, 21 << 20);
+        assert_eq!(report.max_heap, Some(128 << 20));
+        assert!(report.leak, "{:?}", report.trend);
+        // about 1MB/s from 61MB at 60s to 128MB
+        let exhaustion = report.exhaustion_in_s.unwrap();
+        assert!((60f64..75f64).contains(&exhaustion), "{exhaustion}");
+    }
+
+    #[test]
+    fn flat_live_set_is_not_a_leak() {
+        let report = leak_report(&recording(&[30, 32, 29, 31, 30, 32]));
+        assert!(report.trend.is_some());
+        assert!(!report.leak, "{:?}", report.trend);
+        assert_eq!(report.exhaustion_in_s, None);
+    }
+
+    #[test]
+    fn no_trend_from_one_sample() {
+        let report = leak_report(&recording(&[30]));
+        assert_eq!(report.samples.len(), 1);
+        assert!(report.trend.is_none());
+        assert!(!report.leak);
+    }
+}
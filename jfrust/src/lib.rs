@@ -0,0 +1,39 @@
+//! Reads JFR recordings and GC logs of G1 and aggregates them per GC cycle.
+//!
+//! [`load`] reads a recording in any supported format into a [`JfrMain`],
+//! [`JfrMain::gc_cycles`] groups its events into one [`GcCycle`] per GC, and
+//! [`JfrMain::to_graphs`] and [`overlay::overlays`] turn them into plotly traces.
+
+pub mod alert;
+pub mod baseline;
+pub mod compare;
+pub mod cycle;
+pub mod error;
+pub mod fullgc;
+pub mod gclog;
+pub mod graphs;
+pub mod health;
+pub mod input;
+pub mod jdk;
+pub mod jfr;
+pub mod leak;
+pub mod mixed;
+pub mod mmu;
+pub mod overlay;
+pub mod plab;
+pub mod promotion;
+pub mod rates;
+pub mod segment;
+pub mod stats;
+pub mod storm;
+pub mod tenuring;
+pub mod tuning;
+
+pub use cycle::GcCycle;
+pub use error::{Diagnostics, Error, Result};
+pub use graphs::{Graphs, TimeAxis};
+pub use input::load;
+pub use jfr::JfrMain;
+
+/// Sizes and rates are reported in MB, and MB/s.
+pub(crate) const MB: f64 = 1024f64 * 1024f64;
@@ -1,276 +1,644 @@
-pub mod jfr;
-use crate::jfr::{CollectionType, JfrEvent, JfrMain};
-use std::{
-    collections::{BTreeMap, HashSet},
-    io::Write,
-    process::Command,
-};
+use jfrust::alert::{self, AlertReport, AlertRule, Severity};
+use jfrust::baseline::{self, Baseline, BaselineList, Trend};
+use jfrust::compare::{self, CompareThresholds, Comparison};
+use jfrust::fullgc::{self, FullGcReport};
+use jfrust::health::{self, HealthReport, RecordingHealth};
+use jfrust::jfr::{CollectionType, JfrMain};
+use jfrust::leak::{self, Leak};
+use jfrust::mixed::{self, Mixed};
+use jfrust::mmu::{self, Mmu};
+use jfrust::overlay::{self, OverlaySpec, Overlays};
+use jfrust::plab::{self, Plab};
+use jfrust::promotion::{self, Promotion};
+use jfrust::rates::{self, Rates};
+use jfrust::segment::{self, SegmentSpec, Segments};
+use jfrust::stats::{self, PauseReport, Pauses};
+use jfrust::storm::{self, StormThresholds, Storms};
+use jfrust::tenuring::{self, Tenuring};
+use jfrust::tuning::{self, TuningReport};
+use jfrust::{graphs, Diagnostics, GcCycle, TimeAxis};
+use std::{collections::HashSet, fmt::Display, path::PathBuf, process::ExitCode, sync::Arc};
 
 use axum::{
-    extract::{Query, State},
-    http::header,
+    async_trait,
+    extract::{FromRef, FromRequestParts, Query, State},
+    http::{header, request::Parts, StatusCode},
     response::{Html, IntoResponse},
     routing::get,
     Json, Router,
 };
-use clap::Parser;
-use itertools::Itertools;
-use plotly::{common, layout::Axis, plot::Plot, Bar, Layout, Scatter};
+use clap::{Parser, Subcommand};
+use plotly::{
+    color::NamedColor,
+    common,
+    layout::{Axis, AxisType, Shape, ShapeLayer, ShapeLine, ShapeType},
+    plot::Plot,
+    Layout,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Name of the person to greet
+    /// JFR recording, `jfr print --json`/`--xml` output or a unified GC log
+    /// written with -Xlog:gc*, optionally gzip or zstd compressed. `-` reads stdin.
     #[arg(short, long)]
-    jfr_file: String,
-    #[arg(short, long, default_value = "localhost:3000")]
+    jfr_file: Option<String>,
+    /// The jfr tool, by default looked up in $JAVA_HOME/bin, the java.home of java, and PATH
+    #[arg(long, global = true)]
+    jfr_bin: Option<PathBuf>,
+    #[arg(short, long, global = true, default_value = "localhost:3000")]
     addr: String,
-    #[arg(short, long, default_value = "false")]
+    #[arg(short, long, global = true, default_value = "false")]
     webbrowser: bool,
+    /// Application event to overlay on the GC timeline, as <event type>[:<field>],
+    /// e.g. com.acme.RequestCompleted:latency
+    #[arg(short, long)]
+    overlay: Vec<OverlaySpec>,
+    /// Overlays from a TOML file of [[overlay]] tables with an event_type and an
+    /// optional field, added to the --overlay ones
+    #[arg(long)]
+    overlay_file: Option<PathBuf>,
+    /// Alert rules, a TOML file of [[rule]] tables with a name, a condition such as
+    /// "pause_p99 > 200ms" and a severity of info, warning or critical
+    #[arg(long, global = true)]
+    rules: Option<PathBuf>,
+    /// Print reports as JSON instead of tables
+    #[arg(long, global = true)]
+    json: bool,
+    /// Only look at a segment of the recording, by index or as startup, warmup, steady
+    /// or shutdown
+    #[arg(long, global = true)]
+    segment: Option<SegmentSpec>,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Default, Clone, Debug)]
-pub struct Graphs {
-    ages: Vec<Scatter<u64, u64>>,
-    gcs: Vec<Bar<f64, u64>>,
-    gcs_labels: Vec<String>,
-    gcs_ticks: Vec<f64>,
-}
-
-#[derive(Default, Clone)]
-struct Candle {
-    gc_id: u64,
-    before_gc: u64,
-    young_before: u64,
-    young_after: u64,
-    survivors_before: u64,
-    survivors_after: u64,
-    after_gc: u64,
-    tenured: u64,
-    gc_name: String,
-    gc_pause_name: String,
-    tenuring_threshold: u64,
-    collection_type: CollectionType,
-}
-impl Candle {
-    fn title(&self) -> String {
-        if let CollectionType::Unknown = self.collection_type {
-            self.gc_pause_name.clone()
-        } else {
-            format!("{:?}", self.collection_type,)
-        }
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Serve the dashboard, the default
+    Serve,
+    /// Print pause time percentiles, overall and by GC name, cause and type
+    Pauses,
+    /// Print the minimum mutator utilization for windows of 1ms to 10s
+    Mmu {
+        /// Window of the GC overhead, in ms
+        #[arg(long, default_value = "1000")]
+        window: f64,
+    },
+    /// Print allocation and promotion rates between consecutive GCs
+    Rates,
+    /// Print survival rates by object age and recommend a MaxTenuringThreshold
+    Tenuring,
+    /// Print young GCs that promoted objects before the tenuring threshold
+    Promotion,
+    /// Print PLAB use per GC destination and worker, to size YoungPLABSize and OldPLABSize
+    Plab,
+    /// Print the mixed GCs of every concurrent marking and the old bytes they reclaimed
+    Mixed,
+    /// Print the live set after full GCs and markings and whether it grows
+    Leak,
+    /// Print the full GCs and what probably caused them
+    FullGc,
+    /// Print the alert rules the recording violates, fails when a critical one does
+    Alerts,
+    /// Print G1 flag recommendations and the GCs behind them
+    Tune,
+    /// Print a 0-100 score of pauses, GC overhead, full GCs, evacuation failures and heap
+    /// trend, and what each cost
+    Health {
+        /// Recordings to score and rank, the lowest score first, instead of --jfr-file
+        recordings: Vec<String>,
+    },
+    /// Print the startup, warmup, steady state and shutdown segments of the recording
+    Segments,
+    /// Print the differences of a candidate recording from a baseline, fails when it regressed
+    Compare {
+        /// Recording before the change
+        baseline: String,
+        /// Recording after the change
+        candidate: String,
+        /// Change from the baseline that counts as a regression or an improvement, in %
+        #[arg(long, default_value = "10")]
+        tolerance: f64,
+        /// Confidence of the bootstrap interval a change of a pause or rate metric must
+        /// leave 0 out of, in %
+        #[arg(long, default_value = "95")]
+        confidence: f64,
+        /// Serve the dashboard of the candidate with both recordings' GCs overlaid
+        #[arg(long)]
+        serve: bool,
+    },
+    /// Save, list and compare with summaries of earlier recordings
+    Baseline {
+        /// Where baselines are kept, a directory for every name
+        #[arg(long, default_value = ".jfrust/baselines")]
+        dir: PathBuf,
+        #[command(subcommand)]
+        action: BaselineAction,
+    },
+    /// Print periods with too many GCs or too much time in pauses
+    Storms {
+        /// Sliding window, in ms
+        #[arg(long, default_value = "1000")]
+        window: f64,
+        /// GCs per second over the window
+        #[arg(long, default_value = "5")]
+        max_rate: f64,
+        /// % of the window spent in pauses
+        #[arg(long, default_value = "20")]
+        max_overhead: f64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum BaselineAction {
+    /// Save a summary of the recording: pause percentiles, GC counts, rates and flags
+    Save {
+        /// Workload the recording is of
+        #[arg(long)]
+        name: String,
+        /// Commit the recording is of, by default baselines are kept by when they were saved
+        #[arg(long)]
+        commit: Option<String>,
+    },
+    /// Print the saved baselines, the oldest first
+    List {
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Print the differences of the recording from a saved baseline and the trend of the
+    /// last ones, fails when it regressed
+    Compare {
+        #[arg(long)]
+        name: String,
+        /// Baseline to compare with, by default the latest
+        #[arg(long)]
+        commit: Option<String>,
+        /// Baselines in the trend
+        #[arg(long, default_value = "10")]
+        last: usize,
+        /// Change from the baseline that counts as a regression or an improvement, in %
+        #[arg(long, default_value = "10")]
+        tolerance: f64,
+        /// Serve the dashboard of the recording with the trend
+        #[arg(long)]
+        serve: bool,
+    },
+}
+
+#[derive(Clone)]
+struct AppState {
+    jfr: Arc<JfrMain>,
+    overlays: Vec<OverlaySpec>,
+    rules: Vec<AlertRule>,
+    /// Recording to compare with.
+    baseline: Option<(Arc<JfrMain>, CompareThresholds)>,
+    /// Saved baselines of the trend, and the one to compare with.
+    history: Option<(Vec<Baseline>, Baseline, CompareThresholds)>,
+}
+
+impl FromRef<AppState> for Arc<JfrMain> {
+    fn from_ref(state: &AppState) -> Self {
+        Arc::clone(&state.jfr)
     }
 }
 
-impl JfrMain {
-    pub fn to_graphs(&self, collection_type_filter: HashSet<CollectionType>) -> Graphs {
-        let mut graphs: Graphs = Default::default();
-        let mut gc_id_to_candle: BTreeMap<u64, Candle> = BTreeMap::new();
-        for evt in &self.recording.events {
-            let gc_id = evt.gc_id();
-            let candle: &mut Candle = gc_id_to_candle
-                .entry(gc_id.unwrap_or(u64::MAX))
-                .or_insert(Default::default());
-            candle.gc_id = gc_id.unwrap_or(u64::MAX);
-            match &evt {
-                JfrEvent::G1GarbageCollection { values } => {
-                    candle.collection_type = values.type_;
-                }
-                JfrEvent::PromoteObjectOutsidePLAB { values } => {
-                    if values.tenured {
-                        candle.tenured += values.object_size;
-                    }
-                }
-                JfrEvent::PromoteObjectInNewPLAB { values } => {
-                    if values.tenured {
-                        candle.tenured += values.plab_size;
-                    }
-                }
-                JfrEvent::G1HeapSummary { values } => match values.when {
-                    jfr::GCWhen::Before => {
-                        candle.young_before = values.eden_used;
-                        candle.survivors_before = values.survivor_used;
-                    }
-                    jfr::GCWhen::After => {
-                        candle.young_after = values.eden_used;
-                        candle.survivors_after = values.survivor_used;
-                    }
-                },
-                JfrEvent::GCHeapSummary { values } => match values.when {
-                    jfr::GCWhen::Before => candle.before_gc = values.heap_used,
-                    jfr::GCWhen::After => {
-                        candle.gc_id = values.gc_id;
-                        candle.after_gc = values.heap_used;
-                    }
-                },
-                JfrEvent::GarbageCollection { values } => candle.gc_name = values.name.clone(),
-                JfrEvent::GCPhasePause { values } => candle.gc_pause_name = values.name.clone(),
-                JfrEvent::YoungGarbageCollection { values } => {
-                    candle.tenuring_threshold = values.tenuring_threshold
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    if let Some(Command::Baseline {
+        dir,
+        action: BaselineAction::List { name },
+    }) = &args.command
+    {
+        return match baseline::list(dir, name.as_deref()) {
+            Ok(baselines) => report(&BaselineList { baselines }, args.json),
+            Err(e) => {
+                eprintln!("cannot read {}: {}", dir.display(), e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if let Some(Command::Health { recordings }) = &args.command {
+        if !recordings.is_empty() {
+            let mut scored = Vec::new();
+            for recording in recordings {
+                // an unreadable recording is left out of the ranking
+                if let Some(jfr_evt) = load(recording, &args) {
+                    scored.push(RecordingHealth {
+                        recording: recording.clone(),
+                        health: health::health(&jfr_evt),
+                    });
                 }
-                _ => {}
             }
+            let complete = scored.len() == recordings.len();
+            report(&health::rank(scored), args.json);
+            return if complete {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            };
         }
-        let mut x_axis: Vec<f64> = Vec::new();
-        let mut heap: Vec<u64> = Vec::new();
-        let mut young: Vec<u64> = Vec::new();
-        let mut tenured: Vec<u64> = Vec::new();
-        let mut survivors: Vec<u64> = Vec::new();
-        let mut text_array = Vec::<String>::new();
-        let mut ix = 0;
-        for (gc_id, candle) in gc_id_to_candle {
-            if gc_id == u64::MAX {
-                continue;
-            }
-            if collection_type_filter.contains(&candle.collection_type) {
-                continue;
+    }
+    // compare reads its own recordings, the candidate stands for --jfr-file
+    let (jfr_file, baseline) = match &args.command {
+        Some(Command::Compare {
+            baseline,
+            candidate,
+            tolerance,
+            confidence,
+            ..
+        }) => {
+            let thresholds = CompareThresholds {
+                tolerance: tolerance / 100f64,
+                confidence: confidence / 100f64,
+            };
+            (candidate.clone(), Some((baseline.clone(), thresholds)))
+        }
+        _ => match &args.jfr_file {
+            Some(jfr_file) => (jfr_file.clone(), None),
+            None => {
+                eprintln!("--jfr-file is required");
+                return ExitCode::FAILURE;
             }
-            const FACTOR: f64 = 2.3f64;
-            let tenured_bytes = candle.tenured;
-            let gc_id_x_axis = ix as f64 * FACTOR;
-            x_axis.push(gc_id_x_axis);
-            heap.push(candle.before_gc - candle.young_before);
-            if tenured_bytes > candle.young_before + candle.survivors_before {
-                println!(
-                    "weird, we see {} tenure candidates but >{} tenured in gc {}",
-                    candle.young_before + candle.survivors_before,
-                    tenured_bytes,
-                    candle.gc_id
-                );
-                young.push(candle.young_before);
-                tenured.push(0);
-                survivors.push(candle.survivors_before);
-            } else {
-                let young_before = candle.young_before.saturating_sub(tenured_bytes);
-                let survivors_before =
-                    candle.survivors_before - tenured_bytes.saturating_sub(candle.young_before);
-
-                young.push(young_before);
-                tenured.push(tenured_bytes);
-                survivors.push(survivors_before);
+        },
+    };
+    let Some(jfr_evt) = load(&jfr_file, &args) else {
+        return ExitCode::FAILURE;
+    };
+    let baseline = match baseline {
+        Some((baseline_file, thresholds)) => match load(&baseline_file, &args) {
+            Some(baseline) => Some((baseline, thresholds)),
+            None => return ExitCode::FAILURE,
+        },
+        None => None,
+    };
+    let rules = match &args.rules {
+        Some(path) => match alert::load_rules(path) {
+            Ok(rules) => rules,
+            Err(e) => {
+                eprintln!("cannot read {}: {}", path.display(), e);
+                return ExitCode::FAILURE;
             }
-            text_array.push(format!("[{}] before gc", gc_id));
-            graphs.gcs_labels.push(candle.title());
-            graphs.gcs_ticks.push(gc_id_x_axis as f64);
-
-            let gc_id_x_axis = ix as f64 * FACTOR + 1f64;
-            x_axis.push(gc_id_x_axis);
-            if candle.after_gc < tenured_bytes {
-                heap.push(candle.after_gc - candle.young_after);
-                young.push(candle.young_after);
-                tenured.push(0);
-                survivors.push(candle.survivors_after);
-            } else {
-                heap.push(candle.after_gc - candle.young_after - tenured_bytes);
-                young.push(candle.young_after);
-                tenured.push(tenured_bytes);
-                survivors.push(candle.survivors_after);
+        },
+        None => Vec::new(),
+    };
+    let mut overlay_specs = args.overlay.clone();
+    if let Some(path) = &args.overlay_file {
+        match overlay::load_overlays(path) {
+            Ok(from_file) => overlay_specs.extend(from_file),
+            Err(e) => {
+                eprintln!("cannot read {}: {}", path.display(), e);
+                return ExitCode::FAILURE;
             }
-            text_array.push(format!("[{}] after gc", gc_id));
-            graphs.gcs_labels.push(candle.title());
-            ix += 1;
         }
-        graphs.gcs.push(
-            *Bar::new(x_axis.clone(), heap)
-                .name("heap")
-                .text_array(text_array.clone()),
-        );
-        graphs.gcs.push(
-            *Bar::new(x_axis.clone(), tenured)
-                .name("tenured")
-                .text_array(text_array.clone()),
-        );
-        graphs.gcs.push(
-            *Bar::new(x_axis.clone(), young)
-                .name("young")
-                .text_array(text_array.clone()),
-        );
-        graphs.gcs.push(
-            *Bar::new(x_axis.clone(), survivors)
-                .name("survivors")
-                .text_array(text_array),
-        );
-        let tenure_vec = &self
-            .recording
-            .events
-            .iter()
-            .filter_map(|e| {
-                if let JfrEvent::TenuringDistribution { values } = e {
-                    Some(values)
-                } else {
-                    None
+    }
+    let history = match &args.command {
+        Some(Command::Baseline {
+            dir,
+            action:
+                BaselineAction::Compare {
+                    name,
+                    commit,
+                    last,
+                    tolerance,
+                    ..
+                },
+        }) => {
+            let baselines = match baseline::list(dir, Some(name)) {
+                Ok(baselines) => baselines,
+                Err(e) => {
+                    eprintln!("cannot read {}: {}", dir.display(), e);
+                    return ExitCode::FAILURE;
                 }
-            })
-            .collect_vec();
-
-        let by_gc_id = tenure_vec.iter().chunk_by(|e| e.gc_id);
-        for (_gc_id, tenures) in by_gc_id.into_iter() {
-            let mut ages = Vec::<u64>::new();
-            let mut sizes = Vec::<u64>::new();
-            for tenure in tenures {
-                ages.push(tenure.age);
-                sizes.push(tenure.size);
+            };
+            let Some(against) = baseline::find(&baselines, commit.as_deref()).cloned() else {
+                eprintln!(
+                    "no baseline {}{} in {}",
+                    name,
+                    commit
+                        .as_deref()
+                        .map(|c| format!(" of {}", c))
+                        .unwrap_or_default(),
+                    dir.display()
+                );
+                return ExitCode::FAILURE;
+            };
+            let thresholds = CompareThresholds {
+                tolerance: tolerance / 100f64,
+                ..Default::default()
+            };
+            let history = baselines[baselines.len().saturating_sub(*last)..].to_vec();
+            Some((history, against, thresholds))
+        }
+        _ => None,
+    };
+    match args.command {
+        Some(Command::Pauses) => return report(&PauseReport::new(&jfr_evt.gc_cycles()), args.json),
+        Some(Command::Mmu { window }) => {
+            return report(&mmu::mmu(&jfr_evt, window).stats, args.json)
+        }
+        Some(Command::Rates) => return report(&rates::rates(&jfr_evt).stats, args.json),
+        Some(Command::Tenuring) => return report(&tenuring::advise(&jfr_evt), args.json),
+        Some(Command::Promotion) => {
+            return report(&promotion::premature_promotions(&jfr_evt), args.json)
+        }
+        Some(Command::Plab) => return report(&plab::plab_report(&jfr_evt), args.json),
+        Some(Command::Mixed) => return report(&mixed::mixed_report(&jfr_evt), args.json),
+        Some(Command::Leak) => return report(&leak::leak_report(&jfr_evt), args.json),
+        Some(Command::FullGc) => return report(&fullgc::full_gcs(&jfr_evt), args.json),
+        Some(Command::Alerts) => {
+            if args.rules.is_none() {
+                eprintln!("alerts needs --rules");
+                return ExitCode::FAILURE;
             }
-            let trace = Scatter::new(ages, sizes);
-            graphs.ages.push(*trace);
+            let alerts = alert::evaluate(&jfr_evt, &rules);
+            report(&alerts, args.json);
+            return match alerts.worst() {
+                Some(Severity::Critical) => ExitCode::FAILURE,
+                _ => ExitCode::SUCCESS,
+            };
         }
-        graphs
-    }
-}
-
-#[tokio::main]
-async fn main() {
-    let args = Args::parse();
-    let mut cmd = Command::new("jfr");
-    cmd.args(["print", "--json", &args.jfr_file]);
-    let jfr_out = cmd.output().expect("cannnot run jfr");
-    if !jfr_out.status.success() {
-        std::io::stderr().write_all(&jfr_out.stderr).unwrap();
-        return;
+        Some(Command::Tune) => return report(&tuning::tuning(&jfr_evt), args.json),
+        Some(Command::Health { .. }) => return report(&health::health(&jfr_evt), args.json),
+        Some(Command::Segments) => return report(&segment::segment_report(&jfr_evt), args.json),
+        Some(Command::Storms {
+            window,
+            max_rate,
+            max_overhead,
+        }) => {
+            let thresholds = StormThresholds {
+                window_ms: window,
+                max_gcs_per_s: max_rate,
+                max_overhead: max_overhead / 100f64,
+            };
+            return report(&storm::storm_report(&jfr_evt, thresholds), args.json);
+        }
+        Some(Command::Compare { serve: false, .. }) => {
+            let Some((baseline, thresholds)) = &baseline else {
+                return ExitCode::FAILURE;
+            };
+            let comparison = compare::compare_report(baseline, &jfr_evt, *thresholds);
+            report(&comparison, args.json);
+            return match comparison.regressions().next() {
+                Some(_) => ExitCode::FAILURE,
+                None => ExitCode::SUCCESS,
+            };
+        }
+        Some(Command::Baseline { dir, action }) => match action {
+            BaselineAction::Save { name, commit } => {
+                let summary = Baseline::of(&jfr_evt, &name, commit.as_deref(), &jfr_file);
+                return match baseline::save(&dir, &summary) {
+                    Ok(path) => {
+                        println!("saved {}", path.display());
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => {
+                        eprintln!("cannot save to {}: {}", dir.display(), e);
+                        ExitCode::FAILURE
+                    }
+                };
+            }
+            BaselineAction::Compare { serve: false, .. } => {
+                let Some((history, against, thresholds)) = &history else {
+                    return ExitCode::FAILURE;
+                };
+                let trend = baseline::trend_report(&jfr_evt, history, against, *thresholds);
+                report(&trend, args.json);
+                return match trend.comparison.regressions().next() {
+                    Some(_) => ExitCode::FAILURE,
+                    None => ExitCode::SUCCESS,
+                };
+            }
+            // listed before reading the recording
+            BaselineAction::List { .. } | BaselineAction::Compare { .. } => {}
+        },
+        Some(Command::Serve) | Some(Command::Compare { .. }) | None => {}
     }
-    let jfr_evt: JfrMain = serde_json::from_slice(&jfr_out.stdout).expect("cannot parse jfr JSON");
     let app = Router::new()
         .route("/ages", get(ages))
+        .route("/overlays", get(overlays))
+        .route("/diagnostics", get(diagnostics))
+        .route("/cycles", get(cycles))
+        .route("/pauses", get(pauses))
+        .route("/mmu", get(mmu_handler))
+        .route("/rates", get(rates_handler))
+        .route("/tenuring", get(tenuring_handler))
+        .route("/promotion", get(promotion_handler))
+        .route("/plab", get(plab_handler))
+        .route("/mixed", get(mixed_handler))
+        .route("/leak", get(leak_handler))
+        .route("/full_gcs", get(full_gcs))
+        .route("/storms", get(storms_handler))
+        .route("/segments", get(segments_handler))
+        .route("/tuning", get(tuning_handler))
+        .route("/health", get(health_handler))
+        .route("/alerts", get(alerts))
+        .route("/compare", get(compare_handler))
+        .route("/trend", get(trend_handler))
         .route("/", get(index))
         .route("/plotly-2.32.0.min.js", get(plotlyjs))
         .route("/tex-svg.js", get(tex))
         .route("/favicon.ico", get(favicon))
-        .with_state(jfr_evt);
-    let listener = tokio::net::TcpListener::bind(format!("{}", args.addr))
-        .await
-        .unwrap();
+        .with_state(AppState {
+            jfr: Arc::new(jfr_evt),
+            overlays: overlay_specs,
+            rules,
+            baseline: baseline.map(|(jfr, thresholds)| (Arc::new(jfr), thresholds)),
+            history,
+        });
+    let listener = tokio::net::TcpListener::bind(&args.addr).await.unwrap();
     println!("listening on {}", args.addr);
     // we don't care if it fails
     if args.webbrowser {
         let _ = webbrowser::open(format!("http://{}", args.addr).as_str());
     }
     axum::serve(listener, app).await.unwrap();
-    println!("Hello, world!");
+    ExitCode::SUCCESS
+}
+
+/// Reads a recording and the segment of it --segment picks, printing what went wrong.
+fn load(jfr_file: &str, args: &Args) -> Option<JfrMain> {
+    let jfr_evt = match jfrust::load(jfr_file, args.jfr_bin.as_deref()) {
+        Ok(jfr_evt) => jfr_evt,
+        Err(e) => {
+            eprintln!("cannot read {}: {}", jfr_file, e);
+            return None;
+        }
+    };
+    if !jfr_evt.recording.diagnostics.is_empty() {
+        eprint!("{}: {}", jfr_file, jfr_evt.recording.diagnostics);
+    }
+    match args.segment {
+        Some(spec) => {
+            let scoped = segment::scope(&jfr_evt, spec);
+            if scoped.is_none() {
+                eprintln!("{}: no segment {}", jfr_file, spec);
+            }
+            scoped
+        }
+        None => Some(jfr_evt),
+    }
+}
+
+/// The recording, or the segment of it the `segment` query parameter picks. Only a
+/// segment is copied out of the recording, the whole one is shared with the state.
+struct Scoped(Arc<JfrMain>);
+
+#[derive(Deserialize)]
+struct SegmentParams {
+    segment: Option<String>,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Scoped {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<SegmentParams>::try_from_uri(&parts.uri)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        scope(&state.jfr, params.segment).map(Scoped)
+    }
+}
+
+fn scope(
+    jfr: &Arc<JfrMain>,
+    segment: Option<String>,
+) -> Result<Arc<JfrMain>, (StatusCode, String)> {
+    let Some(spec) = segment.filter(|spec| !spec.is_empty()) else {
+        return Ok(Arc::clone(jfr));
+    };
+    let spec = spec
+        .parse::<SegmentSpec>()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    segment::scope(jfr, spec)
+        .map(Arc::new)
+        .ok_or((StatusCode::NOT_FOUND, format!("no segment {}", spec)))
+}
+
+fn report<T: Serialize + Display>(report: &T, json: bool) -> ExitCode {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(report).unwrap_or_default()
+        );
+    } else {
+        print!("{}", report);
+    }
+    ExitCode::SUCCESS
+}
+
+async fn diagnostics(Scoped(jfr_main): Scoped) -> Json<Diagnostics> {
+    Json(jfr_main.recording.diagnostics.clone())
+}
+
+async fn cycles(Scoped(jfr_main): Scoped) -> Json<Vec<GcCycle>> {
+    Json(jfr_main.gc_cycles())
+}
+
+async fn pauses(Scoped(jfr_main): Scoped) -> Json<Pauses> {
+    Json(stats::pauses(&jfr_main.gc_cycles()))
+}
+
+#[derive(Deserialize)]
+struct MmuParams {
+    window_ms: Option<f64>,
+}
+
+async fn mmu_handler(Scoped(jfr_main): Scoped, Query(params): Query<MmuParams>) -> Json<Mmu> {
+    Json(mmu::mmu(&jfr_main, params.window_ms.unwrap_or(1000f64)))
+}
+
+async fn rates_handler(Scoped(jfr_main): Scoped) -> Json<Rates> {
+    Json(rates::rates(&jfr_main))
+}
+
+async fn tenuring_handler(Scoped(jfr_main): Scoped) -> Json<Tenuring> {
+    Json(tenuring::tenuring(&jfr_main))
+}
+
+async fn promotion_handler(Scoped(jfr_main): Scoped) -> Json<Promotion> {
+    Json(promotion::promotion(&jfr_main))
+}
+
+async fn plab_handler(Scoped(jfr_main): Scoped) -> Json<Plab> {
+    Json(plab::plab(&jfr_main))
+}
+
+async fn mixed_handler(Scoped(jfr_main): Scoped) -> Json<Mixed> {
+    Json(mixed::mixed(&jfr_main))
+}
+
+async fn leak_handler(Scoped(jfr_main): Scoped) -> Json<Leak> {
+    Json(leak::leak(&jfr_main))
+}
+
+async fn full_gcs(Scoped(jfr_main): Scoped) -> Json<FullGcReport> {
+    Json(fullgc::full_gcs(&jfr_main))
+}
+
+#[derive(Deserialize)]
+struct StormParams {
+    window_ms: Option<f64>,
+    max_rate: Option<f64>,
+    /// In %.
+    max_overhead: Option<f64>,
+}
+
+async fn storms_handler(
+    Scoped(jfr_main): Scoped,
+    Query(params): Query<StormParams>,
+) -> Json<Storms> {
+    let defaults = StormThresholds::default();
+    let thresholds = StormThresholds {
+        window_ms: params.window_ms.unwrap_or(defaults.window_ms),
+        max_gcs_per_s: params.max_rate.unwrap_or(defaults.max_gcs_per_s),
+        max_overhead: params
+            .max_overhead
+            .map(|pct| pct / 100f64)
+            .unwrap_or(defaults.max_overhead),
+    };
+    Json(storm::storms(&jfr_main, thresholds))
+}
+
+async fn tuning_handler(Scoped(jfr_main): Scoped) -> Json<TuningReport> {
+    Json(tuning::tuning(&jfr_main))
+}
+
+async fn health_handler(Scoped(jfr_main): Scoped) -> Json<HealthReport> {
+    Json(health::health(&jfr_main))
+}
+
+async fn segments_handler(State(jfr_main): State<Arc<JfrMain>>) -> Json<Segments> {
+    Json(segment::segments(&jfr_main))
 }
 
 async fn ages(
-    State(jfr_main): State<JfrMain>,
+    Scoped(jfr_main): Scoped,
     Query(params): Query<Vec<(String, String)>>,
-) -> Json<Vec<Plot>> {
+) -> Json<Vec<Value>> {
     let mut ages = Plot::new();
     let filter: HashSet<CollectionType> = params
-        .into_iter()
+        .iter()
         .filter_map(|(key, val)| -> Option<CollectionType> {
             if key == "collection_type_filter" {
-                let x: Option<CollectionType> =
-                    serde_json::from_str(format!(r#""{}""#, val).as_str()).ok();
-                println!("filter {}<>{} = {:?}", format!(r#""{}""#, val), val, x);
                 serde_json::from_str(format!(r#""{}""#, val).as_str()).ok()
             } else {
                 None
             }
         })
         .collect();
-    /*let filter: HashSet<CollectionType> = params
-    .get_vec("collection_type_filter")
-    .map(|e| serde_json::from_str::<CollectionType>(e).unwrap())
-    .collect();*/
-    let graphs = jfr_main.to_graphs(filter);
+    let time_axis: TimeAxis = params
+        .iter()
+        .find(|(key, _)| key == "time_axis")
+        .and_then(|(_, val)| serde_json::from_str(format!(r#""{}""#, val).as_str()).ok())
+        .unwrap_or_default();
+    let graphs = jfr_main.to_graphs(filter, time_axis);
     for trace in graphs.ages {
         ages.add_trace(Box::new(trace));
     }
@@ -278,29 +646,92 @@ async fn ages(
     for trace in graphs.gcs {
         gc.add_trace(Box::new(trace));
     }
-    gc.set_layout(
-        Layout::new()
-            .x_axis(
-                Axis::new()
-                    .tick_values(graphs.gcs_ticks)
-                    .tick_text(graphs.gcs_labels)
-                    .tick_mode(common::TickMode::Array),
-            )
-            .bar_mode(plotly::layout::BarMode::Stack),
-    );
-    Json(Vec::from([ages, gc]))
-}
-
-async fn index(State(_state): State<JfrMain>) -> Html<&'static str> {
+    let x_axis = match graphs.time_axis {
+        TimeAxis::Index => Axis::new()
+            .tick_values(graphs.gcs_ticks)
+            .tick_text(graphs.gcs_labels)
+            .tick_mode(common::TickMode::Array),
+        TimeAxis::Uptime => Axis::new().title("seconds since JVM start"),
+        TimeAxis::WallClock => Axis::new().type_(AxisType::Date),
+    };
+    let mut layout = Layout::new()
+        .x_axis(x_axis)
+        .bar_mode(plotly::layout::BarMode::Stack);
+    for (x0, x1) in graphs.gcs_highlights {
+        layout.add_shape(
+            Shape::new()
+                .shape_type(ShapeType::Rect)
+                .layer(ShapeLayer::Below)
+                .x_ref("x")
+                .y_ref("paper")
+                .x0(x0)
+                .x1(x1)
+                .y0(0)
+                .y1(1)
+                .fill_color(NamedColor::Red)
+                .opacity(0.2)
+                .line(ShapeLine::new().width(0f64)),
+        );
+    }
+    gc.set_layout(layout);
+    let gc = if graphs.gcs_widths.is_empty() {
+        serde_json::to_value(&gc).unwrap_or(Value::Null)
+    } else {
+        graphs::with_bar_widths(&gc, &graphs.gcs_widths)
+    };
+    Json(Vec::from([
+        serde_json::to_value(&ages).unwrap_or(Value::Null),
+        gc,
+    ]))
+}
+
+async fn alerts(Scoped(jfr_main): Scoped, State(state): State<AppState>) -> Json<AlertReport> {
+    Json(alert::evaluate(&jfr_main, &state.rules))
+}
+
+/// The same segment of both recordings, 404 unless serving a comparison.
+async fn compare_handler(
+    Scoped(jfr_main): Scoped,
+    State(state): State<AppState>,
+    Query(params): Query<SegmentParams>,
+) -> Result<Json<Comparison>, (StatusCode, String)> {
+    let Some((baseline, thresholds)) = &state.baseline else {
+        return Err((StatusCode::NOT_FOUND, "no baseline recording".to_string()));
+    };
+    let baseline = scope(baseline, params.segment)?;
+    Ok(Json(compare::compare(&baseline, &jfr_main, *thresholds)))
+}
+
+/// 404 unless serving the trend of saved baselines.
+async fn trend_handler(
+    Scoped(jfr_main): Scoped,
+    State(state): State<AppState>,
+) -> Result<Json<Trend>, (StatusCode, String)> {
+    let Some((history, against, thresholds)) = &state.history else {
+        return Err((StatusCode::NOT_FOUND, "no saved baselines".to_string()));
+    };
+    Ok(Json(baseline::trend(
+        &jfr_main,
+        history,
+        against,
+        *thresholds,
+    )))
+}
+
+async fn overlays(Scoped(jfr_main): Scoped, State(state): State<AppState>) -> Json<Overlays> {
+    Json(overlay::overlays(&jfr_main, &state.overlays))
+}
+
+async fn index(State(_state): State<Arc<JfrMain>>) -> Html<&'static str> {
     Html(include_str!("../assets/index.html"))
 }
-async fn plotlyjs(State(_state): State<JfrMain>) -> Html<&'static str> {
+async fn plotlyjs(State(_state): State<Arc<JfrMain>>) -> Html<&'static str> {
     Html(include_str!("../assets/plotly-2.32.0.min.js"))
 }
-async fn tex(State(_state): State<JfrMain>) -> Html<&'static str> {
+async fn tex(State(_state): State<Arc<JfrMain>>) -> Html<&'static str> {
     Html(include_str!("../assets/tex-svg.js"))
 }
-async fn favicon(State(_state): State<JfrMain>) -> impl IntoResponse {
+async fn favicon(State(_state): State<Arc<JfrMain>>) -> impl IntoResponse {
     (
         [(header::CONTENT_TYPE, "image/png")],
         include_bytes!("../assets/favicon.ico"),
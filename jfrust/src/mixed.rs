@@ -0,0 +1,341 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use chrono::{DateTime, Utc};
+use plotly::{
+    layout::{Axis, AxisType, BarMode},
+    Bar, Layout, Plot,
+};
+use serde::Serialize;
+
+use crate::{
+    cycle::GcCycle,
+    jfr::{CollectionType, Flag, GCWhen, JfrEvent, JfrMain},
+    MB,
+};
+
+/// A mixed GC, which evacuates old regions chosen by the preceding marking along with
+/// the young ones.
+#[derive(Serialize, Debug, Clone)]
+pub struct MixedGc {
+    pub gc_id: u64,
+    pub start_time: Option<DateTime<Utc>>,
+    pub pause_ms: f64,
+    /// Old bytes before the GC less old bytes after it, net of what it promoted.
+    pub old_reclaimed: u64,
+    pub young_reclaimed: u64,
+    /// Regions in the collection set, young and old, from jdk.EvacuationInformation.
+    pub cset_regions: Option<u64>,
+    /// The collection set less the eden and survivor regions before the GC.
+    pub old_cset_regions: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkingEnd {
+    NextMarking,
+    FullGc,
+    RecordingEnd,
+}
+
+/// A concurrent marking, the G1Old GC, and the mixed GCs that used its results.
+#[derive(Serialize, Debug, Clone)]
+pub struct MarkingCycle {
+    pub gc_id: u64,
+    pub start_time: Option<DateTime<Utc>>,
+    pub marking_ms: f64,
+    pub mixed: Vec<MixedGc>,
+    pub old_reclaimed: u64,
+    pub pause_ms: f64,
+    /// Old bytes reclaimed per ms of mixed GC pause.
+    pub old_reclaimed_per_ms: f64,
+    pub end: MarkingEnd,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct MixedReport {
+    pub markings: Vec<MarkingCycle>,
+    pub mixed_gcs: usize,
+    pub old_reclaimed: u64,
+    pub young_reclaimed: u64,
+    pub pause_ms: f64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Mixed {
+    pub plot: Plot,
+    pub report: MixedReport,
+}
+
+/// G1HeapRegionSize, from the flag events or a GC log init line, else committed heap
+/// over committed regions in the summaries before the first GC that has both.
+fn region_size(jfr: &JfrMain) -> Option<u64> {
+    let flag = jfr.flags().get("G1HeapRegionSize").and_then(Flag::number);
+    if let Some(size) = flag.filter(|size| *size > 0f64) {
+        return Some(size as u64);
+    }
+    let mut committed = BTreeMap::new();
+    let mut regions = BTreeMap::new();
+    for evt in &jfr.recording.events {
+        match evt {
+            JfrEvent::GCHeapSummary { values } if values.when == GCWhen::Before => {
+                if let Some(space) = &values.heap_space {
+                    committed.insert(values.gc_id, space.committed_size);
+                }
+            }
+            JfrEvent::G1HeapSummary { values } if values.when == GCWhen::Before => {
+                if let Some(number) = values.number_of_regions {
+                    regions.insert(values.gc_id, number);
+                }
+            }
+            _ => {}
+        }
+    }
+    regions
+        .iter()
+        .filter(|(_, number)| **number > 0)
+        .find_map(|(key, number)| Some(committed.get(key)? / number))
+}
+
+fn mixed_gc(cycle: &GcCycle, cset: &HashMap<u64, u64>, region_size: Option<u64>) -> MixedGc {
+    let (old_reclaimed, young_reclaimed) = match (cycle.before, cycle.after) {
+        (Some(before), Some(after)) => (
+            before.old.saturating_sub(after.old),
+            before.young().saturating_sub(after.young()),
+        ),
+        _ => (0, 0),
+    };
+    let cset_regions = cset.get(&cycle.gc_id).copied();
+    let old_cset_regions = match (cset_regions, region_size, cycle.before) {
+        (Some(cset), Some(size), Some(before)) => {
+            Some(cset.saturating_sub(before.eden.div_ceil(size) + before.survivor.div_ceil(size)))
+        }
+        _ => None,
+    };
+    MixedGc {
+        gc_id: cycle.gc_id,
+        start_time: cycle.start_time,
+        pause_ms: cycle.sum_of_pauses_ms,
+        old_reclaimed,
+        young_reclaimed,
+        cset_regions,
+        old_cset_regions,
+    }
+}
+
+pub fn mixed_report(jfr: &JfrMain) -> MixedReport {
+    let cset = jfr
+        .recording
+        .events
+        .iter()
+        .filter_map(|evt| match evt {
+            JfrEvent::EvacuationInformation { values } => Some((values.gc_id, values.cset_regions)),
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+    let region_size = region_size(jfr);
+    let mut cycles = jfr.gc_cycles();
+    cycles.sort_by_key(|cycle| (cycle.start_time, cycle.gc_id));
+
+    let mut markings: Vec<MarkingCycle> = Vec::new();
+    let mut open = false;
+    let mut report = MixedReport::default();
+    for cycle in &cycles {
+        match cycle.name.as_str() {
+            "G1Old" => {
+                if let Some(last) = markings.last_mut().filter(|_| open) {
+                    last.end = MarkingEnd::NextMarking;
+                }
+                open = true;
+                markings.push(MarkingCycle {
+                    gc_id: cycle.gc_id,
+                    start_time: cycle.start_time,
+                    marking_ms: cycle.duration_ms,
+                    mixed: Vec::new(),
+                    old_reclaimed: 0,
+                    pause_ms: 0f64,
+                    old_reclaimed_per_ms: 0f64,
+                    end: MarkingEnd::RecordingEnd,
+                });
+            }
+            "G1Full" if open => {
+                if let Some(last) = markings.last_mut() {
+                    last.end = MarkingEnd::FullGc;
+                }
+                open = false;
+            }
+            _ if matches!(cycle.collection_type, CollectionType::Mixed) => {
+                let mixed = mixed_gc(cycle, &cset, region_size);
+                report.mixed_gcs += 1;
+                report.old_reclaimed += mixed.old_reclaimed;
+                report.young_reclaimed += mixed.young_reclaimed;
+                report.pause_ms += mixed.pause_ms;
+                // mixed GCs before the first marking of the recording have no cycle
+                if let Some(last) = markings.last_mut().filter(|_| open) {
+                    last.old_reclaimed += mixed.old_reclaimed;
+                    last.pause_ms += mixed.pause_ms;
+                    last.mixed.push(mixed);
+                }
+            }
+            _ => {}
+        }
+    }
+    for marking in &mut markings {
+        if marking.pause_ms > 0f64 {
+            marking.old_reclaimed_per_ms = marking.old_reclaimed as f64 / marking.pause_ms;
+        }
+    }
+    report.markings = markings;
+    report
+}
+
+/// Old and young bytes reclaimed by every mixed GC, stacked.
+pub fn mixed(jfr: &JfrMain) -> Mixed {
+    let report = mixed_report(jfr);
+    let gcs = report
+        .markings
+        .iter()
+        .flat_map(|marking| &marking.mixed)
+        .collect::<Vec<_>>();
+    let gc_ids = gcs
+        .iter()
+        .map(|gc| gc.gc_id.to_string())
+        .collect::<Vec<_>>();
+    let mut plot = Plot::new();
+    plot.add_trace(
+        Bar::new(
+            gc_ids.clone(),
+            gcs.iter().map(|gc| gc.old_reclaimed as f64 / MB).collect(),
+        )
+        .name("old reclaimed (MB)"),
+    );
+    plot.add_trace(
+        Bar::new(
+            gc_ids,
+            gcs.iter()
+                .map(|gc| gc.young_reclaimed as f64 / MB)
+                .collect(),
+        )
+        .name("young reclaimed (MB)"),
+    );
+    plot.set_layout(
+        Layout::new()
+            .x_axis(Axis::new().title("mixed GC").type_(AxisType::Category))
+            .y_axis(Axis::new().title("MB reclaimed"))
+            .bar_mode(BarMode::Stack),
+    );
+    Mixed { plot, report }
+}
+
+impl fmt::Display for MixedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} markings, {} mixed GCs reclaimed {:.1}MB old and {:.1}MB young in {:.1}ms",
+            self.markings.len(),
+            self.mixed_gcs,
+            self.old_reclaimed as f64 / MB,
+            self.young_reclaimed as f64 / MB,
+            self.pause_ms
+        )?;
+        for marking in &self.markings {
+            writeln!(
+                f,
+                "marking gc {} ({:.1}ms): {} mixed GCs, {:.1}MB old in {:.1}ms, {:.2}MB/ms, ended by {:?}",
+                marking.gc_id,
+                marking.marking_ms,
+                marking.mixed.len(),
+                marking.old_reclaimed as f64 / MB,
+                marking.pause_ms,
+                marking.old_reclaimed_per_ms / MB,
+                marking.end
+            )?;
+            for gc in &marking.mixed {
+                writeln!(
+                    f,
+                    "  gc {}: {:.1}MB old, {:.1}MB young, {} of {} regions old, {:.1}ms",
+                    gc.gc_id,
+                    gc.old_reclaimed as f64 / MB,
+                    gc.young_reclaimed as f64 / MB,
+                    gc.old_cset_regions
+                        .map(|n| n.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                    gc.cset_regions
+                        .map(|n| n.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                    gc.pause_ms
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cycle::HeapUsage, gclog};
+
+    const MIB: u64 = 1 << 20;
+
+    fn usage(eden: u64, survivor: u64, old: u64) -> HeapUsage {
+        HeapUsage {
+            used: (eden + survivor + old) * MIB,
+            eden: eden * MIB,
+            survivor: survivor * MIB,
+            humongous: None,
+            old: old * MIB,
+        }
+    }
+
+    fn cycle(before: Option<HeapUsage>, after: Option<HeapUsage>) -> GcCycle {
+        GcCycle {
+            gc_id: 7,
+            name: "G1New".to_string(),
+            collection_type: CollectionType::Mixed,
+            sum_of_pauses_ms: 12.5,
+            before,
+            after,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn mixed_gc_reclaims_old_net_of_promotions() {
+        // 40M old in, 4M promoted and 24M of old regions evacuated
+        let gc = cycle(Some(usage(20, 3, 40)), Some(usage(0, 2, 20)));
+        let cset = HashMap::from([(7, 35)]);
+        let mixed = mixed_gc(&gc, &cset, Some(MIB));
+        assert_eq!(mixed.old_reclaimed, 20 * MIB);
+        assert_eq!(mixed.young_reclaimed, 21 * MIB);
+        assert_eq!(mixed.pause_ms, 12.5);
+        assert_eq!(mixed.cset_regions, Some(35));
+        assert_eq!(mixed.old_cset_regions, Some(12));
+    }
+
+    #[test]
+    fn mixed_gc_that_grew_old_reclaims_nothing() {
+        let gc = cycle(Some(usage(20, 3, 40)), Some(usage(0, 2, 45)));
+        let mixed = mixed_gc(&gc, &HashMap::new(), Some(MIB));
+        assert_eq!(mixed.old_reclaimed, 0);
+        assert_eq!(mixed.cset_regions, None);
+        assert_eq!(mixed.old_cset_regions, None);
+    }
+
+    #[test]
+    fn mixed_gc_without_heap_summaries() {
+        let cset = HashMap::from([(7, 35)]);
+        let mixed = mixed_gc(&cycle(None, None), &cset, Some(MIB));
+        assert_eq!((mixed.old_reclaimed, mixed.young_reclaimed), (0, 0));
+        assert_eq!(mixed.old_cset_regions, None);
+    }
+
+    #[test]
+    fn region_size_from_the_gc_log() {
+        let log = "[0.003s][info ][gc,init       ] Heap Region Size: 4M\n";
+        let jfr = gclog::parse(log.as_bytes()).unwrap();
+        assert_eq!(region_size(&jfr), Some(4 * MIB));
+    }
+}
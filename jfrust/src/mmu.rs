@@ -0,0 +1,322 @@
+use std::fmt;
+
+use plotly::{
+    common::{DashType, Line, Mode},
+    layout::{Axis, AxisType},
+    Layout, Plot, Scatter,
+};
+use serde::Serialize;
+
+use crate::jfr::{dur_ms, ms_since, JfrEvent, JfrMain};
+
+/// Pauses that overlap are merged, a young pause and its phases are reported separately.
+fn merge(pauses: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    let mut merged: Vec<(f64, f64)> = Vec::with_capacity(pauses.len());
+    for (start, end) in pauses {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Sorted, disjoint pause intervals in ms, with a prefix sum of their durations.
+pub(crate) struct Timeline {
+    pauses: Vec<(f64, f64)>,
+    prefix: Vec<f64>,
+    start: f64,
+    end: f64,
+}
+
+impl Timeline {
+    pub(crate) fn new(pauses: Vec<(f64, f64)>, start: f64, end: f64) -> Timeline {
+        let pauses = merge(pauses);
+        let mut prefix = vec![0f64];
+        for (pause_start, pause_end) in &pauses {
+            prefix.push(prefix[prefix.len() - 1] + (pause_end - pause_start));
+        }
+        Timeline {
+            pauses,
+            prefix,
+            start,
+            end,
+        }
+    }
+
+    /// Time spent in pauses between `from` and `to`.
+    pub(crate) fn paused(&self, from: f64, to: f64) -> f64 {
+        let first = self.pauses.partition_point(|(_, end)| *end <= from);
+        let last = self.pauses.partition_point(|(start, _)| *start < to);
+        if first >= last {
+            return 0f64;
+        }
+        let mut paused = self.prefix[last] - self.prefix[first];
+        paused -= (from - self.pauses[first].0).max(0f64);
+        paused -= (self.pauses[last - 1].1 - to).max(0f64);
+        paused
+    }
+
+    /// The worst window either starts at the start of a pause or ends at the end of one.
+    fn mmu(&self, window: f64) -> Option<f64> {
+        if window > self.end - self.start {
+            return None;
+        }
+        let worst = self
+            .pauses
+            .iter()
+            .flat_map(|(start, end)| [*start, end - window])
+            .map(|from| from.clamp(self.start, self.end - window))
+            .map(|from| self.paused(from, from + window))
+            .fold(0f64, f64::max);
+        let mmu = 1f64 - worst / window;
+        Some(if mmu > 0f64 { mmu } else { 0f64 })
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MmuPoint {
+    pub window_ms: f64,
+    /// Smallest fraction of any window of this size left to the application.
+    pub mmu: f64,
+}
+
+/// What G1 reported in jdk.G1MMU, next to the MMU computed for its time slice.
+#[derive(Serialize, Debug, Clone)]
+pub struct G1MmuComparison {
+    pub events: usize,
+    pub time_slice_ms: f64,
+    pub pause_target_ms: f64,
+    /// MMU G1 aims for, 1 - pause target / time slice.
+    pub goal: f64,
+    /// Lowest MMU G1 reported, 1 - GC time / time slice.
+    pub reported: f64,
+    /// MMU computed from the pauses for a window of `time_slice_ms`.
+    pub computed: Option<f64>,
+    /// GCs after which G1 saw more GC time in the slice than the pause target.
+    pub over_target: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct MmuStats {
+    pub curve: Vec<MmuPoint>,
+    pub g1: Option<G1MmuComparison>,
+    pub overhead_window_ms: f64,
+    /// Highest share of time spent in pauses over a sliding `overhead_window_ms`.
+    pub max_overhead: f64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Mmu {
+    pub plot: Plot,
+    pub overhead: Plot,
+    pub stats: MmuStats,
+}
+
+/// 1ms to 10s, ten windows a decade.
+fn windows() -> Vec<f64> {
+    (0..=40).map(|i| 10f64.powf(i as f64 / 10f64)).collect()
+}
+
+fn g1_mmu(jfr: &JfrMain, timeline: &Timeline) -> Option<G1MmuComparison> {
+    let mut comparison: Option<G1MmuComparison> = None;
+    for evt in &jfr.recording.events {
+        let JfrEvent::G1MMU { values } = evt else {
+            continue;
+        };
+        let time_slice_ms = dur_ms(&values.time_slice);
+        let gc_time_ms = dur_ms(&values.gc_time);
+        let pause_target_ms = dur_ms(&values.pause_target);
+        let reported = 1f64 - gc_time_ms / time_slice_ms;
+        let g1 = comparison.get_or_insert_with(|| G1MmuComparison {
+            events: 0,
+            time_slice_ms,
+            pause_target_ms,
+            goal: 1f64 - pause_target_ms / time_slice_ms,
+            reported,
+            computed: timeline.mmu(time_slice_ms),
+            over_target: 0,
+        });
+        g1.events += 1;
+        g1.reported = g1.reported.min(reported);
+        if gc_time_ms > pause_target_ms {
+            g1.over_target += 1;
+        }
+    }
+    comparison
+}
+
+/// Minimum mutator utilization for windows of 1ms to 10s, and the GC overhead
+/// over a sliding window of `overhead_window_ms`.
+pub fn mmu(jfr: &JfrMain, overhead_window_ms: f64) -> Mmu {
+    let mut plot = Plot::new();
+    let mut overhead = Plot::new();
+    let Some(origin) = jfr.start_time() else {
+        return Mmu {
+            plot,
+            overhead,
+            stats: MmuStats::default(),
+        };
+    };
+    let end = jfr
+        .recording
+        .events
+        .iter()
+        .filter_map(JfrEvent::start_time)
+        .max()
+        .map(|end| ms_since(origin, end))
+        .unwrap_or(0f64);
+    let pauses = jfr.pause_intervals(origin);
+    let end = pauses.iter().map(|(_, end)| *end).fold(end, f64::max);
+    let timeline = Timeline::new(pauses, 0f64, end);
+
+    let curve = windows()
+        .into_iter()
+        .filter_map(|window_ms| {
+            timeline
+                .mmu(window_ms)
+                .map(|mmu| MmuPoint { window_ms, mmu })
+        })
+        .collect::<Vec<_>>();
+    plot.add_trace(
+        Scatter::new(
+            curve.iter().map(|p| p.window_ms).collect(),
+            curve.iter().map(|p| p.mmu).collect(),
+        )
+        .name("MMU")
+        .mode(Mode::LinesMarkers),
+    );
+    let g1 = g1_mmu(jfr, &timeline);
+    if let Some(g1) = &g1 {
+        let xs = vec![curve.first().map(|p| p.window_ms).unwrap_or(1f64), end];
+        plot.add_trace(
+            Scatter::new(xs, vec![g1.goal; 2])
+                .name(format!(
+                    "G1 goal, {}ms in {}ms",
+                    g1.pause_target_ms, g1.time_slice_ms
+                ))
+                .mode(Mode::Lines)
+                .line(Line::new().dash(DashType::Dash)),
+        );
+        plot.add_trace(
+            Scatter::new(vec![g1.time_slice_ms], vec![g1.reported])
+                .name("lowest jdk.G1MMU")
+                .mode(Mode::Markers),
+        );
+    }
+    plot.set_layout(
+        Layout::new()
+            .x_axis(Axis::new().title("window (ms)").type_(AxisType::Log))
+            .y_axis(Axis::new().title("MMU").range(vec![0f64, 1f64])),
+    );
+
+    // sample the sliding window at most 2000 times
+    let step = (overhead_window_ms / 10f64).max(end / 2000f64);
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    let mut max_overhead = 0f64;
+    let mut to = overhead_window_ms.min(end);
+    while to <= end && to > 0f64 {
+        let from = (to - overhead_window_ms).max(0f64);
+        let share = timeline.paused(from, to) / (to - from);
+        max_overhead = max_overhead.max(share);
+        xs.push(to / 1000f64);
+        ys.push(share * 100f64);
+        to += step;
+    }
+    overhead.add_trace(
+        Scatter::new(xs, ys)
+            .name(format!("GC overhead, {}ms window", overhead_window_ms))
+            .mode(Mode::Lines),
+    );
+    overhead.set_layout(
+        Layout::new()
+            .x_axis(Axis::new().title("seconds since recording start"))
+            .y_axis(Axis::new().title("% of time in GC pauses")),
+    );
+    Mmu {
+        plot,
+        overhead,
+        stats: MmuStats {
+            curve,
+            g1,
+            overhead_window_ms,
+            max_overhead,
+        },
+    }
+}
+
+impl fmt::Display for MmuStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>12} {:>8}", "window (ms)", "MMU")?;
+        for point in &self.curve {
+            writeln!(f, "{:>12.1} {:>7.1}%", point.window_ms, point.mmu * 100f64)?;
+        }
+        writeln!(
+            f,
+            "max GC overhead over {}ms: {:.1}%",
+            self.overhead_window_ms,
+            self.max_overhead * 100f64
+        )?;
+        if let Some(g1) = &self.g1 {
+            writeln!(
+                f,
+                "G1 goal {}ms of GC in {}ms, MMU {:.1}%: lowest reported {:.1}%, computed {}, {} of {} GCs over target",
+                g1.pause_target_ms,
+                g1.time_slice_ms,
+                g1.goal * 100f64,
+                g1.reported * 100f64,
+                g1.computed
+                    .map(|mmu| format!("{:.1}%", mmu * 100f64))
+                    .unwrap_or_else(|| "-".to_string()),
+                g1.over_target,
+                g1.events
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pauses at 10-30ms, the first two overlapping, and 40-50ms, in 100ms.
+    fn timeline() -> Timeline {
+        Timeline::new(
+            vec![(10f64, 20f64), (15f64, 30f64), (40f64, 50f64)],
+            0f64,
+            100f64,
+        )
+    }
+
+    #[test]
+    fn overlapping_pauses_are_counted_once() {
+        let timeline = timeline();
+        assert_eq!(timeline.pauses, vec![(10f64, 30f64), (40f64, 50f64)]);
+        assert_eq!(timeline.paused(0f64, 100f64), 30f64);
+    }
+
+    #[test]
+    fn windows_cutting_a_pause_count_their_part() {
+        let timeline = timeline();
+        assert_eq!(timeline.paused(0f64, 5f64), 0f64);
+        assert_eq!(timeline.paused(30f64, 40f64), 0f64);
+        assert_eq!(timeline.paused(25f64, 45f64), 10f64);
+        assert_eq!(timeline.paused(12f64, 45f64), 23f64);
+        assert_eq!(timeline.paused(42f64, 48f64), 6f64);
+    }
+
+    #[test]
+    fn mmu_of_windows() {
+        let timeline = timeline();
+        assert_eq!(timeline.mmu(10f64), Some(0f64));
+        assert_eq!(timeline.mmu(25f64), Some(1f64 - 20f64 / 25f64));
+        assert_eq!(timeline.mmu(100f64), Some(0.7));
+    }
+
+    #[test]
+    fn no_mmu_for_a_window_longer_than_the_recording() {
+        assert_eq!(timeline().mmu(100.5), None);
+    }
+}
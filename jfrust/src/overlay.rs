@@ -0,0 +1,304 @@
+use std::{fs, path::Path, str::FromStr};
+
+use plotly::{
+    common::{AxisSide, Mode},
+    layout::Axis,
+    Bar, Layout, Plot, Scatter,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    jfr::{dur_ms, ms_since, JfrEvent, JfrMain},
+};
+
+/*
+[[overlay]]
+event_type = "com.acme.RequestCompleted"
+field = "latency"
+
+[[overlay]]
+event_type = "com.acme.CacheEviction"
+*/
+#[derive(Deserialize, Debug, Clone)]
+struct OverlaysFile {
+    #[serde(default)]
+    overlay: Vec<OverlaySpec>,
+}
+
+/// An application event type to draw on top of the GC timeline,
+/// given as `com.acme.RequestCompleted[:latency]`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "OverlayTable")]
+pub struct OverlaySpec {
+    pub event_type: String,
+    pub field: Option<String>,
+}
+
+/// An `[[overlay]]` table before it is checked.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OverlayTable {
+    event_type: String,
+    field: Option<String>,
+}
+
+impl TryFrom<OverlayTable> for OverlaySpec {
+    type Error = String;
+
+    fn try_from(table: OverlayTable) -> std::result::Result<Self, Self::Error> {
+        if table.event_type.is_empty() || table.field.as_deref() == Some("") {
+            return Err("event_type and field cannot be empty".to_string());
+        }
+        Ok(OverlaySpec {
+            event_type: table.event_type,
+            field: table.field,
+        })
+    }
+}
+
+impl FromStr for OverlaySpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (event_type, field) = match s.split_once(':') {
+            Some((event_type, field)) => (event_type, Some(field.to_string())),
+            None => (s, None),
+        };
+        OverlaySpec::try_from(OverlayTable {
+            event_type: event_type.to_string(),
+            field,
+        })
+        .map_err(|_| format!("expected <event type>[:<field>], got {:?}", s))
+    }
+}
+
+/// Overlays from a TOML file of `[[overlay]]` tables.
+pub fn load_overlays(path: &Path) -> Result<Vec<OverlaySpec>> {
+    let overlays: OverlaysFile =
+        toml::from_str(&fs::read_to_string(path)?).map_err(Error::Overlays)?;
+    Ok(overlays.overlay)
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct OverlayStats {
+    pub event_type: String,
+    pub events: usize,
+    pub during_pause: usize,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Overlays {
+    pub plot: Plot,
+    pub stats: Vec<OverlayStats>,
+}
+
+fn overlaps_pause(pauses: &[(f64, f64)], start: f64, end: f64) -> bool {
+    // first pause that ends after the event starts
+    let ix = pauses.partition_point(|(_, pause_end)| *pause_end < start);
+    pauses
+        .get(ix)
+        .is_some_and(|(pause_start, _)| *pause_start <= end)
+}
+
+pub fn overlays(jfr: &JfrMain, specs: &[OverlaySpec]) -> Overlays {
+    let mut plot = Plot::new();
+    let mut stats = Vec::new();
+    let Some(origin) = jfr.start_time() else {
+        return Overlays { plot, stats };
+    };
+    let pauses = jfr.pause_intervals(origin);
+    plot.add_trace(
+        Bar::new(
+            pauses.iter().map(|(start, _)| *start).collect(),
+            pauses.iter().map(|(start, end)| end - start).collect(),
+        )
+        .name("gc pause (ms)"),
+    );
+    for spec in specs {
+        let mut xs = Vec::<f64>::new();
+        let mut ys = Vec::<f64>::new();
+        let mut events = 0;
+        let mut during_pause = 0;
+        for evt in &jfr.recording.events {
+            let JfrEvent::Custom(custom) = evt else {
+                continue;
+            };
+            if custom.event_type != spec.event_type {
+                continue;
+            }
+            let Some(start_time) = custom.start_time else {
+                continue;
+            };
+            events += 1;
+            let start = ms_since(origin, start_time);
+            let duration = custom.duration.as_ref().map(dur_ms).unwrap_or(0f64);
+            if overlaps_pause(&pauses, start, start + duration) {
+                during_pause += 1;
+            }
+            let y = match &spec.field {
+                Some(field) => custom.field(field),
+                None => Some(duration),
+            };
+            if let Some(y) = y {
+                xs.push(start);
+                ys.push(y);
+            }
+        }
+        let name = match &spec.field {
+            Some(field) => format!("{}.{}", spec.event_type, field),
+            None => format!("{} (ms)", spec.event_type),
+        };
+        plot.add_trace(
+            Scatter::new(xs, ys)
+                .name(name)
+                .mode(Mode::Markers)
+                .y_axis("y2"),
+        );
+        stats.push(OverlayStats {
+            event_type: spec.event_type.clone(),
+            events,
+            during_pause,
+        });
+    }
+    plot.set_layout(
+        Layout::new()
+            .x_axis(Axis::new().title("ms since recording start"))
+            .y_axis(Axis::new().title("pause (ms)"))
+            .y_axis2(
+                Axis::new()
+                    .overlaying("y")
+                    .side(AxisSide::Right)
+                    .title("application events"),
+            ),
+    );
+    Overlays { plot, stats }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::gclog;
+
+    const LOG: &str = "\
+[1.000s][info ][gc,start       ] GC(0) Pause Young (Normal) (G1 Evacuation Pause)
+[1.010s][info ][gc             ] GC(0) Pause Young (Normal) (G1 Evacuation Pause) 10M->5M(64M) 10.000ms
+[2.000s][info ][gc,start       ] GC(1) Pause Young (Normal) (G1 Evacuation Pause)
+[2.020s][info ][gc             ] GC(1) Pause Young (Normal) (G1 Evacuation Pause) 12M->5M(64M) 20.000ms
+";
+
+    fn recording() -> JfrMain {
+        let mut jfr = gclog::parse(LOG.as_bytes()).unwrap();
+        for values in [
+            // during the first pause
+            json!({"startTime": "1970-01-01T00:00:01.005Z", "duration": "PT0.001S", "latency": 42}),
+            json!({"startTime": "1970-01-01T00:00:01.500Z", "duration": "PT0.002S", "latency": 7}),
+            // runs into the second pause
+            json!({"startTime": "1970-01-01T00:00:01.995Z", "duration": "PT0.01S"}),
+            json!({"duration": "PT0.003S", "latency": 1}),
+        ] {
+            jfr.recording
+                .push_value(json!({"type": "com.acme.Request", "values": values}));
+        }
+        jfr.recording.push_value(json!({
+            "type": "com.acme.Other",
+            "values": {"startTime": "1970-01-01T00:00:01.005Z"},
+        }));
+        jfr
+    }
+
+    fn ys(overlays: &Overlays, trace: usize) -> Value {
+        serde_json::to_value(&overlays.plot).unwrap()["data"][trace]["y"].clone()
+    }
+
+    #[test]
+    fn specs_from_the_command_line() {
+        assert_eq!(
+            "com.acme.Request:latency".parse::<OverlaySpec>(),
+            Ok(OverlaySpec {
+                event_type: "com.acme.Request".to_string(),
+                field: Some("latency".to_string()),
+            })
+        );
+        assert_eq!(
+            "com.acme.Request".parse::<OverlaySpec>(),
+            Ok(OverlaySpec {
+                event_type: "com.acme.Request".to_string(),
+                field: None,
+            })
+        );
+        assert!("".parse::<OverlaySpec>().is_err());
+        assert!(":latency".parse::<OverlaySpec>().is_err());
+        assert!("com.acme.Request:".parse::<OverlaySpec>().is_err());
+    }
+
+    #[test]
+    fn specs_from_a_file() {
+        let file: OverlaysFile = toml::from_str(
+            r#"
+[[overlay]]
+event_type = "com.acme.Request"
+field = "latency"
+
+[[overlay]]
+event_type = "com.acme.Other"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            file.overlay,
+            [
+                "com.acme.Request:latency".parse().unwrap(),
+                "com.acme.Other".parse().unwrap()
+            ]
+        );
+        assert!(toml::from_str::<OverlaysFile>("")
+            .unwrap()
+            .overlay
+            .is_empty());
+        assert!(toml::from_str::<OverlaysFile>("[[overlay]]\nevent_type = \"\"").is_err());
+        assert!(toml::from_str::<OverlaysFile>("[[overlay]]\nfield = \"latency\"").is_err());
+        assert!(
+            toml::from_str::<OverlaysFile>("[[overlay]]\nevent_type = \"a\"\nfeild = \"b\"")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn events_during_pauses() {
+        let overlays = overlays(&recording(), &["com.acme.Request".parse().unwrap()]);
+        assert_eq!(overlays.stats.len(), 1);
+        assert_eq!(overlays.stats[0].event_type, "com.acme.Request");
+        // the one without a start time is left out
+        assert_eq!(overlays.stats[0].events, 3);
+        assert_eq!(overlays.stats[0].during_pause, 2);
+        assert_eq!(ys(&overlays, 0), json!([10.0, 20.0]));
+        // durations by default
+        assert_eq!(ys(&overlays, 1), json!([1.0, 2.0, 10.0]));
+    }
+
+    #[test]
+    fn field_values() {
+        let overlays = overlays(
+            &recording(),
+            &[
+                "com.acme.Request:latency".parse().unwrap(),
+                "com.acme.Missing".parse().unwrap(),
+            ],
+        );
+        assert_eq!(ys(&overlays, 1), json!([42.0, 7.0]));
+        assert_eq!(overlays.stats[1].events, 0);
+        assert_eq!(ys(&overlays, 2), json!([]));
+    }
+
+    #[test]
+    fn empty_recording() {
+        let overlays = overlays(
+            &gclog::parse(&b""[..]).unwrap(),
+            &["com.acme.Request".parse().unwrap()],
+        );
+        assert!(overlays.stats.is_empty());
+    }
+}
@@ -0,0 +1,417 @@
+use std::{collections::BTreeMap, fmt};
+
+use plotly::{
+    layout::{Axis, BarMode},
+    Bar, Layout, Plot,
+};
+use serde::Serialize;
+
+use crate::{
+    jfr::{G1EvacuationStatistics, JfrEvent, JfrMain},
+    stats::percentile,
+};
+
+/// -XX:ParallelGCBufferWastePct default. A PLAB is retired once less than this share
+/// of it is left, and objects larger than this share are copied outside PLABs.
+const BUFFER_WASTE_PCT: u64 = 10;
+/// Share of copied bytes allocated outside PLABs worth a larger PLAB.
+const DIRECT_SHARE: f64 = 0.2;
+/// Share of PLAB bytes wasted worth a smaller PLAB.
+const WASTE_SHARE: f64 = 0.1;
+const HEAP_WORD: u64 = 8;
+
+/// Copies into survivor or old regions seen in jdk.PromoteObjectInNewPLAB and
+/// jdk.PromoteObjectOutsidePLAB. Both events are sampled, so these are lower bounds.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PlabStats {
+    pub new_plabs: usize,
+    pub plab_bytes: u64,
+    pub avg_plab_size: f64,
+    /// Objects that started a new PLAB.
+    pub in_plab_bytes: u64,
+    pub outside_objects: usize,
+    pub outside_bytes: u64,
+    /// At most ParallelGCBufferWastePct of every new PLAB is left unused when it is retired.
+    pub max_waste: u64,
+}
+
+impl PlabStats {
+    fn add_plab(&mut self, plab_size: u64, object_size: u64) {
+        self.new_plabs += 1;
+        self.plab_bytes += plab_size;
+        self.in_plab_bytes += object_size;
+        self.update();
+    }
+
+    fn add_outside(&mut self, object_size: u64) {
+        self.outside_objects += 1;
+        self.outside_bytes += object_size;
+    }
+
+    fn update(&mut self) {
+        self.avg_plab_size = self.plab_bytes as f64 / self.new_plabs.max(1) as f64;
+        self.max_waste = self.plab_bytes * BUFFER_WASTE_PCT / 100;
+    }
+
+    fn merge(&mut self, other: &PlabStats) {
+        self.new_plabs += other.new_plabs;
+        self.plab_bytes += other.plab_bytes;
+        self.in_plab_bytes += other.in_plab_bytes;
+        self.outside_objects += other.outside_objects;
+        self.outside_bytes += other.outside_bytes;
+        self.update();
+    }
+}
+
+/// PLABs into survivor regions, sized by YoungPLABSize, and into old regions, sized by OldPLABSize.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PlabPair {
+    pub survivor: PlabStats,
+    pub old: PlabStats,
+}
+
+impl PlabPair {
+    fn add(&mut self, tenured: bool) -> &mut PlabStats {
+        if tenured {
+            &mut self.old
+        } else {
+            &mut self.survivor
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PlabGc {
+    pub gc_id: u64,
+    pub plabs: PlabPair,
+    /// From jdk.G1EvacuationYoungStatistics, exact unlike `plabs`.
+    pub survivor_statistics: Option<G1EvacuationStatistics>,
+    /// From jdk.G1EvacuationOldStatistics.
+    pub old_statistics: Option<G1EvacuationStatistics>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PlabWorker {
+    pub thread: String,
+    pub plabs: PlabPair,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PlabReport {
+    pub plabs: PlabPair,
+    pub survivor_statistics: Option<G1EvacuationStatistics>,
+    pub old_statistics: Option<G1EvacuationStatistics>,
+    pub gcs: Vec<PlabGc>,
+    pub workers: Vec<PlabWorker>,
+    pub advice: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Plab {
+    pub plot: Plot,
+    pub report: PlabReport,
+}
+
+fn add_statistics(total: &mut Option<G1EvacuationStatistics>, stats: &G1EvacuationStatistics) {
+    let total = total.get_or_insert_with(Default::default);
+    total.allocated += stats.allocated;
+    total.wasted += stats.wasted;
+    total.used += stats.used;
+    total.undo_waste += stats.undo_waste;
+    total.region_end_waste += stats.region_end_waste;
+    total.regions_refilled += stats.regions_refilled;
+    total.num_plabs_filled += stats.num_plabs_filled;
+    total.direct_allocated += stats.direct_allocated;
+    total.num_direct_allocated += stats.num_direct_allocated;
+    total.failure_used += stats.failure_used;
+    total.failure_waste += stats.failure_waste;
+}
+
+/// Whether PLABs of one destination look too small or too large, preferring the
+/// exact G1 statistics over the sampled events.
+fn advise(
+    flag: &str,
+    plabs: &PlabStats,
+    statistics: Option<&G1EvacuationStatistics>,
+    outside_sizes: &mut [f64],
+) -> Option<String> {
+    let (direct, copied, wasted, allocated) = match statistics {
+        Some(s) if s.allocated + s.direct_allocated > 0 => (
+            s.direct_allocated,
+            s.used + s.direct_allocated,
+            s.wasted + s.undo_waste,
+            s.allocated,
+        ),
+        _ => (
+            plabs.outside_bytes,
+            plabs.in_plab_bytes + plabs.outside_bytes,
+            0,
+            plabs.plab_bytes,
+        ),
+    };
+    if copied == 0 {
+        return None;
+    }
+    let direct_share = direct as f64 / copied as f64;
+    if direct_share > DIRECT_SHARE && !outside_sizes.is_empty() {
+        outside_sizes.sort_by(f64::total_cmp);
+        let size = percentile(outside_sizes, 50f64) as u64 * 100 / BUFFER_WASTE_PCT;
+        return Some(format!(
+            "{:.0}% of the bytes copied with {} bypassed PLABs, -XX:{}={} words fits half of those objects",
+            direct_share * 100f64,
+            flag,
+            flag,
+            size.div_ceil(HEAP_WORD)
+        ));
+    }
+    if allocated > 0 && wasted as f64 / allocated as f64 > WASTE_SHARE {
+        return Some(format!(
+            "{:.0}% of the PLAB bytes sized by {} were wasted, lower -XX:{} or keep -XX:+ResizePLAB",
+            wasted as f64 / allocated as f64 * 100f64,
+            flag,
+            flag
+        ));
+    }
+    None
+}
+
+pub fn plab_report(jfr: &JfrMain) -> PlabReport {
+    let mut gcs: BTreeMap<u64, PlabGc> = BTreeMap::new();
+    let mut workers: BTreeMap<String, PlabPair> = BTreeMap::new();
+    let mut survivor_sizes = Vec::new();
+    let mut old_sizes = Vec::new();
+    for evt in &jfr.recording.events {
+        match evt {
+            JfrEvent::PromoteObjectInNewPLAB { values } => {
+                let thread = values.event_thread.as_ref().map(|t| t.os_name.clone());
+                let gc = gcs.entry(values.gc_id).or_default();
+                gc.plabs
+                    .add(values.tenured)
+                    .add_plab(values.plab_size, values.object_size);
+                workers
+                    .entry(thread.unwrap_or_default())
+                    .or_default()
+                    .add(values.tenured)
+                    .add_plab(values.plab_size, values.object_size);
+            }
+            JfrEvent::PromoteObjectOutsidePLAB { values } => {
+                let thread = values.event_thread.as_ref().map(|t| t.os_name.clone());
+                let gc = gcs.entry(values.gc_id).or_default();
+                gc.plabs.add(values.tenured).add_outside(values.object_size);
+                workers
+                    .entry(thread.unwrap_or_default())
+                    .or_default()
+                    .add(values.tenured)
+                    .add_outside(values.object_size);
+                if values.tenured {
+                    old_sizes.push(values.object_size as f64);
+                } else {
+                    survivor_sizes.push(values.object_size as f64);
+                }
+            }
+            JfrEvent::G1EvacuationYoungStatistics { values } => {
+                let gc = gcs.entry(values.statistics.gc_id).or_default();
+                gc.survivor_statistics = Some(values.statistics.clone());
+            }
+            JfrEvent::G1EvacuationOldStatistics { values } => {
+                let gc = gcs.entry(values.statistics.gc_id).or_default();
+                gc.old_statistics = Some(values.statistics.clone());
+            }
+            _ => {}
+        }
+    }
+
+    let mut report = PlabReport::default();
+    for (gc_id, mut gc) in gcs {
+        gc.gc_id = gc_id;
+        report.plabs.survivor.merge(&gc.plabs.survivor);
+        report.plabs.old.merge(&gc.plabs.old);
+        if let Some(stats) = &gc.survivor_statistics {
+            add_statistics(&mut report.survivor_statistics, stats);
+        }
+        if let Some(stats) = &gc.old_statistics {
+            add_statistics(&mut report.old_statistics, stats);
+        }
+        report.gcs.push(gc);
+    }
+    report.workers = workers
+        .into_iter()
+        .map(|(thread, plabs)| PlabWorker { thread, plabs })
+        .collect();
+    report.advice = [
+        advise(
+            "YoungPLABSize",
+            &report.plabs.survivor,
+            report.survivor_statistics.as_ref(),
+            &mut survivor_sizes,
+        ),
+        advise(
+            "OldPLABSize",
+            &report.plabs.old,
+            report.old_statistics.as_ref(),
+            &mut old_sizes,
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    report
+}
+
+/// Bytes copied by each GC, in and outside PLABs, stacked.
+pub fn plab(jfr: &JfrMain) -> Plab {
+    let report = plab_report(jfr);
+    let gc_ids = report.gcs.iter().map(|gc| gc.gc_id).collect::<Vec<_>>();
+    let mut plot = Plot::new();
+    for (name, tenured, outside) in [
+        ("survivor, new PLAB", false, false),
+        ("survivor, outside PLAB", false, true),
+        ("old, new PLAB", true, false),
+        ("old, outside PLAB", true, true),
+    ] {
+        let bytes = report
+            .gcs
+            .iter()
+            .map(|gc| {
+                let stats = if tenured {
+                    &gc.plabs.old
+                } else {
+                    &gc.plabs.survivor
+                };
+                if outside {
+                    stats.outside_bytes
+                } else {
+                    stats.in_plab_bytes
+                }
+            })
+            .collect();
+        plot.add_trace(Bar::new(gc_ids.clone(), bytes).name(name));
+    }
+    plot.set_layout(
+        Layout::new()
+            .x_axis(Axis::new().title("gcId"))
+            .y_axis(Axis::new().title("bytes copied"))
+            .bar_mode(BarMode::Stack),
+    );
+    Plab { plot, report }
+}
+
+impl fmt::Display for PlabReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<40} {:>9} {:>12} {:>14} {:>9} {:>14} {:>12}",
+            "PLABs", "new", "avg size", "in new PLAB", "outside", "outside bytes", "max waste"
+        )?;
+        let row = |f: &mut fmt::Formatter<'_>, label: &str, stats: &PlabStats| {
+            writeln!(
+                f,
+                "{:<40} {:>9} {:>12.0} {:>14} {:>9} {:>14} {:>12}",
+                label,
+                stats.new_plabs,
+                stats.avg_plab_size,
+                stats.in_plab_bytes,
+                stats.outside_objects,
+                stats.outside_bytes,
+                stats.max_waste
+            )
+        };
+        row(f, "survivor", &self.plabs.survivor)?;
+        row(f, "old", &self.plabs.old)?;
+        writeln!(f, "by worker")?;
+        for worker in &self.workers {
+            row(
+                f,
+                &format!("  {} survivor", worker.thread),
+                &worker.plabs.survivor,
+            )?;
+            row(f, &format!("  {} old", worker.thread), &worker.plabs.old)?;
+        }
+        for (label, stats) in [
+            ("survivor", &self.survivor_statistics),
+            ("old", &self.old_statistics),
+        ] {
+            if let Some(s) = stats {
+                writeln!(
+                    f,
+                    "G1 {}: {} PLAB bytes, {} used, {} wasted, {} undone, {} at region ends, {} direct",
+                    label,
+                    s.allocated,
+                    s.used,
+                    s.wasted,
+                    s.undo_waste,
+                    s.region_end_waste,
+                    s.direct_allocated
+                )?;
+            }
+        }
+        for advice in &self.advice {
+            writeln!(f, "{}", advice)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statistics(allocated: u64, wasted: u64, direct_allocated: u64) -> G1EvacuationStatistics {
+        G1EvacuationStatistics {
+            allocated,
+            wasted,
+            used: allocated - wasted,
+            direct_allocated,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn wasted_plabs_are_too_large() {
+        let stats = statistics(1000, 150, 0);
+        let advice = advise("OldPLABSize", &PlabStats::default(), Some(&stats), &mut []);
+        assert_eq!(
+            advice.as_deref(),
+            Some("15% of the PLAB bytes sized by OldPLABSize were wasted, lower -XX:OldPLABSize or keep -XX:+ResizePLAB")
+        );
+    }
+
+    #[test]
+    fn little_waste_and_few_direct_copies_need_no_advice() {
+        let stats = statistics(1000, 50, 100);
+        let advice = advise(
+            "YoungPLABSize",
+            &PlabStats::default(),
+            Some(&stats),
+            &mut [4096f64],
+        );
+        assert_eq!(advice, None);
+    }
+
+    #[test]
+    fn direct_copies_are_fit_by_a_larger_plab() {
+        let mut sizes = [8192f64, 2048f64, 4096f64];
+        let stats = statistics(1000, 0, 500);
+        let advice = advise(
+            "YoungPLABSize",
+            &PlabStats::default(),
+            Some(&stats),
+            &mut sizes,
+        );
+        assert_eq!(
+            advice.as_deref(),
+            Some("33% of the bytes copied with YoungPLABSize bypassed PLABs, -XX:YoungPLABSize=5120 words fits half of those objects")
+        );
+    }
+
+    #[test]
+    fn sampled_events_without_statistics() {
+        let mut plabs = PlabStats::default();
+        plabs.add_plab(1 << 20, 1024);
+        assert_eq!(advise("OldPLABSize", &plabs, None, &mut []), None);
+        assert_eq!(
+            advise("OldPLABSize", &PlabStats::default(), None, &mut []),
+            None
+        );
+    }
+}
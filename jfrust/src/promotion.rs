@@ -0,0 +1,295 @@
+use std::{collections::BTreeMap, fmt};
+
+use chrono::{DateTime, Utc};
+use plotly::{
+    layout::{Axis, BarMode},
+    Bar, Layout, Plot,
+};
+use serde::Serialize;
+
+use crate::jfr::{JfrEvent, JfrMain};
+
+/// Why objects of a GC were promoted before reaching the highest tenuring threshold.
+/// Both come down to survivor regions that are too small for the survivors.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PromotionCause {
+    /// Tenured below the threshold of the GC, survivor regions ran out during the copy.
+    SurvivorFull { objects: usize, bytes: u64 },
+    /// Tenured at the threshold, which G1 lowered from `from` as the survivors of
+    /// the previous GC took more than TargetSurvivorRatio of survivor space.
+    ThresholdLowered {
+        from: u64,
+        to: u64,
+        objects: usize,
+        bytes: u64,
+    },
+}
+
+impl PromotionCause {
+    pub fn bytes(&self) -> u64 {
+        match self {
+            PromotionCause::SurvivorFull { bytes, .. } => *bytes,
+            PromotionCause::ThresholdLowered { bytes, .. } => *bytes,
+        }
+    }
+}
+
+impl fmt::Display for PromotionCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PromotionCause::SurvivorFull { objects, bytes } => write!(
+                f,
+                "{} objects, {} bytes tenured below the threshold: survivor full",
+                objects, bytes
+            ),
+            PromotionCause::ThresholdLowered {
+                from,
+                to,
+                objects,
+                bytes,
+            } => write!(
+                f,
+                "{} objects, {} bytes tenured at threshold {} lowered from {}: survivor full",
+                objects, bytes, to, from
+            ),
+        }
+    }
+}
+
+/// A young GC that promoted objects early.
+#[derive(Serialize, Debug, Clone)]
+pub struct PrematurePromotion {
+    pub gc_id: u64,
+    pub start_time: Option<DateTime<Utc>>,
+    pub tenuring_threshold: u64,
+    /// Sampled by jdk.PromoteObjectInNewPLAB and jdk.PromoteObjectOutsidePLAB.
+    pub bytes: u64,
+    pub causes: Vec<PromotionCause>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PromotionReport {
+    /// Highest tenuring threshold G1 used, the MaxTenuringThreshold it lowers from.
+    pub max_threshold: Option<u64>,
+    pub young_gcs: usize,
+    pub bytes: u64,
+    pub gcs: Vec<PrematurePromotion>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Promotion {
+    pub plot: Plot,
+    pub report: PromotionReport,
+}
+
+/// Objects and bytes promoted by a GC, below its threshold and at or above it.
+#[derive(Default)]
+struct Promoted {
+    below: (usize, u64),
+    at: (usize, u64),
+}
+
+pub fn premature_promotions(jfr: &JfrMain) -> PromotionReport {
+    let mut promoted: BTreeMap<u64, Promoted> = BTreeMap::new();
+    let cycles = jfr.gc_cycles();
+    let thresholds = cycles
+        .iter()
+        .filter_map(|cycle| Some((cycle.gc_id, cycle.tenuring_threshold?)))
+        .collect::<BTreeMap<_, _>>();
+    for evt in &jfr.recording.events {
+        let (gc_id, size, age) = match evt {
+            JfrEvent::PromoteObjectInNewPLAB { values } if values.tenured => {
+                (values.gc_id, values.object_size, values.tenuring_age)
+            }
+            JfrEvent::PromoteObjectOutsidePLAB { values } if values.tenured => {
+                (values.gc_id, values.object_size, values.tenuring_age)
+            }
+            _ => continue,
+        };
+        let Some(threshold) = thresholds.get(&gc_id) else {
+            continue;
+        };
+        let gc = promoted.entry(gc_id).or_default();
+        let count = if age < *threshold {
+            &mut gc.below
+        } else {
+            &mut gc.at
+        };
+        count.0 += 1;
+        count.1 += size;
+    }
+
+    let max_threshold = thresholds.values().max().copied();
+    let gcs = cycles
+        .iter()
+        .filter_map(|cycle| {
+            let tenuring_threshold = cycle.tenuring_threshold?;
+            let promoted = promoted.get(&cycle.gc_id)?;
+            let mut causes = Vec::new();
+            if promoted.below.0 > 0 {
+                causes.push(PromotionCause::SurvivorFull {
+                    objects: promoted.below.0,
+                    bytes: promoted.below.1,
+                });
+            }
+            match max_threshold {
+                Some(from) if tenuring_threshold < from && promoted.at.0 > 0 => {
+                    causes.push(PromotionCause::ThresholdLowered {
+                        from,
+                        to: tenuring_threshold,
+                        objects: promoted.at.0,
+                        bytes: promoted.at.1,
+                    })
+                }
+                _ => {}
+            }
+            if causes.is_empty() {
+                return None;
+            }
+            Some(PrematurePromotion {
+                gc_id: cycle.gc_id,
+                start_time: cycle.start_time,
+                tenuring_threshold,
+                bytes: causes.iter().map(PromotionCause::bytes).sum(),
+                causes,
+            })
+        })
+        .collect::<Vec<_>>();
+    PromotionReport {
+        max_threshold,
+        young_gcs: thresholds.len(),
+        bytes: gcs.iter().map(|gc| gc.bytes).sum(),
+        gcs,
+    }
+}
+
+/// Bytes promoted early by each GC, stacked by cause.
+pub fn promotion(jfr: &JfrMain) -> Promotion {
+    let report = premature_promotions(jfr);
+    let gc_ids = report.gcs.iter().map(|gc| gc.gc_id).collect::<Vec<_>>();
+    let bytes_of = |full: bool| {
+        report
+            .gcs
+            .iter()
+            .map(|gc| {
+                gc.causes
+                    .iter()
+                    .filter(|cause| matches!(cause, PromotionCause::SurvivorFull { .. }) == full)
+                    .map(PromotionCause::bytes)
+                    .sum::<u64>()
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut plot = Plot::new();
+    plot.add_trace(Bar::new(gc_ids.clone(), bytes_of(true)).name("below the threshold"));
+    plot.add_trace(Bar::new(gc_ids, bytes_of(false)).name("at a lowered threshold"));
+    plot.set_layout(
+        Layout::new()
+            .x_axis(Axis::new().title("gcId"))
+            .y_axis(Axis::new().title("bytes promoted early"))
+            .bar_mode(BarMode::Stack),
+    );
+    Promotion { plot, report }
+}
+
+impl fmt::Display for PromotionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} young GCs promoted {} bytes early, highest threshold {}",
+            self.gcs.len(),
+            self.young_gcs,
+            self.bytes,
+            self.max_threshold
+                .map(|t| t.to_string())
+                .unwrap_or_else(|| "unknown".to_string())
+        )?;
+        for gc in &self.gcs {
+            writeln!(
+                f,
+                "gc {} threshold {}: {} bytes",
+                gc.gc_id, gc.tenuring_threshold, gc.bytes
+            )?;
+            for cause in &gc.causes {
+                writeln!(f, "  {}", cause)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::gclog;
+
+    /// GC(1) tenures at the highest threshold, GC(2) at a lowered one.
+    const LOG: &str = "\
+[1.000s][info ][gc,start       ] GC(1) Pause Young (Normal) (G1 Evacuation Pause)
+[1.000s][debug][gc,age         ] GC(1) Desired survivor size 3670016 bytes, new threshold 15 (max threshold 15)
+[1.010s][info ][gc             ] GC(1) Pause Young (Normal) (G1 Evacuation Pause) 50M->10M(96M) 10.000ms
+[2.000s][info ][gc,start       ] GC(2) Pause Young (Normal) (G1 Evacuation Pause)
+[2.000s][debug][gc,age         ] GC(2) Desired survivor size 3670016 bytes, new threshold 3 (max threshold 15)
+[2.010s][info ][gc             ] GC(2) Pause Young (Normal) (G1 Evacuation Pause) 50M->10M(96M) 10.000ms
+";
+
+    fn recording(promotions: &[(u64, u64, u64)]) -> JfrMain {
+        let mut jfr = gclog::parse(LOG.as_bytes()).unwrap();
+        for (gc_id, tenuring_age, object_size) in promotions {
+            jfr.recording.push_value(json!({
+                "type": "jdk.PromoteObjectOutsidePLAB",
+                "values": {
+                    "startTime": "1970-01-01T00:00:02.005Z",
+                    "gcId": gc_id,
+                    "objectSize": object_size,
+                    "tenuringAge": tenuring_age,
+                    "tenured": true,
+                },
+            }));
+        }
+        jfr
+    }
+
+    #[test]
+    fn tenured_at_the_highest_threshold_is_not_premature() {
+        let report = premature_promotions(&recording(&[(1, 15, 1024)]));
+        assert_eq!(report.max_threshold, Some(15));
+        assert_eq!(report.young_gcs, 2);
+        assert!(report.gcs.is_empty(), "{:?}", report.gcs);
+        assert_eq!(report.bytes, 0);
+    }
+
+    #[test]
+    fn survivor_full_and_threshold_lowered() {
+        let report = premature_promotions(&recording(&[
+            (2, 1, 100),
+            (2, 2, 200),
+            (2, 3, 400),
+            (2, 5, 800),
+        ]));
+        let [gc] = &report.gcs[..] else {
+            panic!("{:?}", report.gcs);
+        };
+        assert_eq!(gc.gc_id, 2);
+        assert_eq!(gc.tenuring_threshold, 3);
+        assert_eq!(
+            gc.causes,
+            vec![
+                PromotionCause::SurvivorFull {
+                    objects: 2,
+                    bytes: 300
+                },
+                PromotionCause::ThresholdLowered {
+                    from: 15,
+                    to: 3,
+                    objects: 2,
+                    bytes: 1200
+                },
+            ]
+        );
+        assert_eq!(report.bytes, 1500);
+    }
+}
@@ -0,0 +1,236 @@
+use std::fmt;
+
+use plotly::{
+    common::{AxisSide, Mode},
+    layout::Axis,
+    Layout, Plot, Scatter,
+};
+use serde::Serialize;
+
+use crate::{
+    cycle::GcCycle,
+    jfr::{ms_since, JfrMain},
+    stats::percentile,
+    MB,
+};
+
+/// GC log timestamps are in ms, shorter intervals between GCs are rounding.
+const MIN_INTERVAL_S: f64 = 0.001;
+
+/// The mutator time between the end of one GC and the start of the next.
+#[derive(Serialize, Debug, Clone)]
+pub struct RateInterval {
+    /// The GC that ends the interval.
+    pub gc_id: u64,
+    /// Start of that GC, in seconds since the recording started.
+    pub at_s: f64,
+    pub seconds: f64,
+    /// Eden before the GC, less eden after the previous one.
+    pub allocated: u64,
+    pub allocation_mb_s: f64,
+    /// Promoted by the GC, from jdk.PromoteObjectInNewPLAB and jdk.PromoteObjectOutsidePLAB.
+    pub promoted: u64,
+    pub promotion_mb_s: f64,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct RateSummary {
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub max: f64,
+}
+
+impl RateSummary {
+    fn of(mut rates: Vec<f64>) -> RateSummary {
+        if rates.is_empty() {
+            return RateSummary::default();
+        }
+        rates.sort_by(f64::total_cmp);
+        RateSummary {
+            mean: rates.iter().sum::<f64>() / rates.len() as f64,
+            p50: percentile(&rates, 50f64),
+            p90: percentile(&rates, 90f64),
+            max: rates[rates.len() - 1],
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct RateStats {
+    pub intervals: Vec<RateInterval>,
+    pub allocation_mb_s: RateSummary,
+    pub promotion_mb_s: RateSummary,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Rates {
+    pub plot: Plot,
+    pub stats: RateStats,
+}
+
+/// Young and full GCs empty eden, concurrent cycles overlap them and are skipped.
+fn empties_eden(cycle: &GcCycle) -> bool {
+    cycle.name != "G1Old" && cycle.before.is_some() && cycle.after.is_some()
+}
+
+pub fn rate_intervals(jfr: &JfrMain) -> Vec<RateInterval> {
+    let Some(origin) = jfr.start_time() else {
+        return Vec::new();
+    };
+    let mut cycles = jfr
+        .gc_cycles()
+        .into_iter()
+        .filter(empties_eden)
+        .filter_map(|cycle| Some((ms_since(origin, cycle.start_time?), cycle)))
+        .collect::<Vec<_>>();
+    cycles.sort_by(|a, b| a.0.total_cmp(&b.0));
+    cycles
+        .windows(2)
+        .filter_map(|pair| {
+            let [(prev_start, prev), (start, cycle)] = pair else {
+                return None;
+            };
+            let seconds = (start - (prev_start + prev.duration_ms)) / 1000f64;
+            if seconds < MIN_INTERVAL_S {
+                return None;
+            }
+            let eden_before = cycle.before?.eden;
+            let eden_after = prev.after?.eden;
+            let allocated = eden_before.saturating_sub(eden_after);
+            Some(RateInterval {
+                gc_id: cycle.gc_id,
+                at_s: start / 1000f64,
+                seconds,
+                allocated,
+                allocation_mb_s: allocated as f64 / MB / seconds,
+                promoted: cycle.promoted,
+                promotion_mb_s: cycle.promoted as f64 / MB / seconds,
+            })
+        })
+        .collect()
+}
+
+pub fn rates(jfr: &JfrMain) -> Rates {
+    let intervals = rate_intervals(jfr);
+    let xs = intervals.iter().map(|i| i.at_s).collect::<Vec<_>>();
+    let mut plot = Plot::new();
+    plot.add_trace(
+        Scatter::new(
+            xs.clone(),
+            intervals.iter().map(|i| i.allocation_mb_s).collect(),
+        )
+        .name("allocation (MB/s)")
+        .mode(Mode::LinesMarkers),
+    );
+    plot.add_trace(
+        Scatter::new(xs, intervals.iter().map(|i| i.promotion_mb_s).collect())
+            .name("promotion (MB/s)")
+            .mode(Mode::LinesMarkers)
+            .y_axis("y2"),
+    );
+    plot.set_layout(
+        Layout::new()
+            .x_axis(Axis::new().title("seconds since recording start"))
+            .y_axis(Axis::new().title("allocation (MB/s)"))
+            .y_axis2(
+                Axis::new()
+                    .overlaying("y")
+                    .side(AxisSide::Right)
+                    .title("promotion (MB/s)"),
+            ),
+    );
+    let stats = RateStats {
+        allocation_mb_s: RateSummary::of(intervals.iter().map(|i| i.allocation_mb_s).collect()),
+        promotion_mb_s: RateSummary::of(intervals.iter().map(|i| i.promotion_mb_s).collect()),
+        intervals,
+    };
+    Rates { plot, stats }
+}
+
+impl fmt::Display for RateStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:>10} {:>10} {:>10} {:>10}",
+            format!("{} intervals", self.intervals.len()),
+            "mean",
+            "p50",
+            "p90",
+            "max"
+        )?;
+        for (label, summary) in [
+            ("allocation (MB/s)", &self.allocation_mb_s),
+            ("promotion (MB/s)", &self.promotion_mb_s),
+        ] {
+            writeln!(
+                f,
+                "{:<20} {:>10.2} {:>10.2} {:>10.2} {:>10.2}",
+                label, summary.mean, summary.p50, summary.p90, summary.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gclog;
+
+    /// GC(1) leaves 2M in eden, GC(3) is a concurrent cycle, GC(4) starts when GC(2) ends, but for rounding.
+    const LOG: &str = "\
+[0.003s][info ][gc,init       ] Heap Region Size: 1M
+[1.000s][info ][gc,start       ] GC(1) Pause Young (Normal) (G1 Evacuation Pause)
+[1.010s][info ][gc,heap        ] GC(1) Eden regions: 50->2(52)
+[1.010s][info ][gc,heap        ] GC(1) Survivor regions: 0->0(7)
+[1.010s][info ][gc             ] GC(1) Pause Young (Normal) (G1 Evacuation Pause) 60M->12M(96M) 10.000ms
+[1.500s][info ][gc             ] GC(3) Concurrent Mark Cycle
+[1.600s][info ][gc             ] GC(3) Pause Remark 40M->40M(96M) 1.000ms
+[1.700s][info ][gc             ] GC(3) Concurrent Mark Cycle 200.000ms
+[2.010s][info ][gc,start       ] GC(2) Pause Young (Normal) (G1 Evacuation Pause)
+[2.020s][info ][gc,heap        ] GC(2) Eden regions: 42->0(52)
+[2.020s][info ][gc,heap        ] GC(2) Survivor regions: 0->0(7)
+[2.020s][info ][gc             ] GC(2) Pause Young (Normal) (G1 Evacuation Pause) 52M->10M(96M) 10.000ms
+[2.020s][info ][gc,start       ] GC(4) Pause Young (Normal) (G1 Evacuation Pause)
+[2.030s][info ][gc,heap        ] GC(4) Eden regions: 1->0(52)
+[2.030s][info ][gc,heap        ] GC(4) Survivor regions: 0->0(7)
+[2.030s][info ][gc             ] GC(4) Pause Young (Normal) (G1 Evacuation Pause) 11M->10M(96M) 10.000ms
+";
+
+    #[test]
+    fn allocated_is_eden_before_less_eden_after_the_previous_gc() {
+        let jfr = gclog::parse(LOG.as_bytes()).unwrap();
+        let intervals = rate_intervals(&jfr);
+        let [interval] = &intervals[..] else {
+            panic!("{:?}", intervals);
+        };
+        assert_eq!(interval.gc_id, 2);
+        assert!(
+            (interval.seconds - 1f64).abs() < 1e-5,
+            "{}",
+            interval.seconds
+        );
+        assert_eq!(interval.allocated, 40 << 20);
+        assert!((interval.allocation_mb_s - 40f64).abs() < 1e-3);
+    }
+
+    #[test]
+    fn concurrent_cycles_do_not_empty_eden() {
+        let jfr = gclog::parse(LOG.as_bytes()).unwrap();
+        let names = jfr
+            .gc_cycles()
+            .iter()
+            .filter(|cycle| empties_eden(cycle))
+            .map(|cycle| cycle.gc_id)
+            .collect::<Vec<_>>();
+        assert_eq!(names, [1, 2, 4]);
+    }
+
+    #[test]
+    fn no_intervals_without_gcs() {
+        let jfr = gclog::parse(&b""[..]).unwrap();
+        assert!(rate_intervals(&jfr).is_empty());
+        assert_eq!(rates(&jfr).stats.allocation_mb_s.max, 0f64);
+    }
+}
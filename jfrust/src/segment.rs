@@ -0,0 +1,499 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use plotly::{
+    color::NamedColor,
+    common::{AxisSide, Mode},
+    layout::{Annotation, Axis, Shape, ShapeLayer, ShapeLine, ShapeType},
+    Layout, Plot, Scatter,
+};
+use serde::Serialize;
+
+use crate::{
+    jfr::{JfrEvent, JfrMain},
+    rates::rate_intervals,
+    MB,
+};
+
+/// Fewest GCs in a segment, a few slow GCs are not a phase.
+const MIN_GCS: usize = 5;
+/// Cost of a change point, times ln(GCs) per metric, in units of the metric's variance.
+const PENALTY: f64 = 3.0;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Startup,
+    Warmup,
+    SteadyState,
+    Shutdown,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::Startup => "startup",
+            Phase::Warmup => "warmup",
+            Phase::SteadyState => "steady",
+            Phase::Shutdown => "shutdown",
+        })
+    }
+}
+
+/// A part of the recording whose GCs allocate, pause and occupy the heap alike.
+#[derive(Serialize, Debug, Clone)]
+pub struct Segment {
+    pub index: usize,
+    pub phase: Phase,
+    /// From the end of the GC before it, in seconds since the recording started.
+    pub start_s: f64,
+    pub end_s: f64,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub gc_ids: Vec<u64>,
+    pub allocation_mb_s: f64,
+    pub pause_ms: f64,
+    pub used_after: f64,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SegmentReport {
+    pub segments: Vec<Segment>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Segments {
+    pub plot: Plot,
+    pub report: SegmentReport,
+}
+
+/// A segment by index or every segment of a phase: startup, warmup, steady or shutdown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SegmentSpec {
+    Index(usize),
+    Phase(Phase),
+}
+
+impl FromStr for SegmentSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "startup" => Ok(SegmentSpec::Phase(Phase::Startup)),
+            "warmup" => Ok(SegmentSpec::Phase(Phase::Warmup)),
+            "steady" | "steady_state" => Ok(SegmentSpec::Phase(Phase::SteadyState)),
+            "shutdown" => Ok(SegmentSpec::Phase(Phase::Shutdown)),
+            _ => s.parse().map(SegmentSpec::Index).map_err(|_| {
+                format!(
+                    "expected a segment index, startup, warmup, steady or shutdown, got {:?}",
+                    s
+                )
+            }),
+        }
+    }
+}
+
+impl fmt::Display for SegmentSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SegmentSpec::Index(index) => write!(f, "{}", index),
+            SegmentSpec::Phase(phase) => write!(f, "{}", phase),
+        }
+    }
+}
+
+/// Standard deviation of a whole series, the unit its changes are measured in. Unlike
+/// the noise between consecutive GCs, it is large enough that the sawtooth of heap
+/// occupancy between marking cycles is not a change of phase.
+fn std_dev(xs: &[f64]) -> f64 {
+    let n = xs.len() as f64;
+    let mean = xs.iter().sum::<f64>() / n;
+    (xs.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n).sqrt()
+}
+
+/// Prefix sums of the metrics and their squares, for the squared error of any range.
+struct Costs {
+    sums: Vec<Vec<f64>>,
+    squares: Vec<Vec<f64>>,
+}
+
+impl Costs {
+    fn new(metrics: &[Vec<f64>]) -> Costs {
+        let prefix = |f: &dyn Fn(f64) -> f64| {
+            metrics
+                .iter()
+                .map(|xs| {
+                    let mut sums = vec![0f64];
+                    for x in xs {
+                        sums.push(sums[sums.len() - 1] + f(*x));
+                    }
+                    sums
+                })
+                .collect()
+        };
+        Costs {
+            sums: prefix(&|x| x),
+            squares: prefix(&|x| x * x),
+        }
+    }
+
+    /// Squared error of `from..to` around its mean, over all metrics.
+    fn cost(&self, from: usize, to: usize) -> f64 {
+        let n = (to - from) as f64;
+        self.sums
+            .iter()
+            .zip(&self.squares)
+            .map(|(sums, squares)| {
+                let sum = sums[to] - sums[from];
+                squares[to] - squares[from] - sum * sum / n
+            })
+            .sum()
+    }
+}
+
+/// Binary segmentation: split at the point that lowers the error most while that pays
+/// for the penalty.
+fn split(costs: &Costs, from: usize, to: usize, penalty: f64, points: &mut Vec<usize>) {
+    if to - from < 2 * MIN_GCS {
+        return;
+    }
+    let whole = costs.cost(from, to);
+    let best = (from + MIN_GCS..=to - MIN_GCS)
+        .map(|at| (at, costs.cost(from, at) + costs.cost(at, to)))
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+    if let Some((at, cost)) = best {
+        if whole - cost > penalty {
+            split(costs, from, at, penalty, points);
+            points.push(at);
+            split(costs, at, to, penalty, points);
+        }
+    }
+}
+
+/// The longest segment after the first is the steady state, the first is startup, those
+/// in between warmup and those after shutdown.
+fn phases(durations: &[f64]) -> Vec<Phase> {
+    if durations.len() == 1 {
+        return vec![Phase::SteadyState];
+    }
+    let steady = (1..durations.len())
+        .max_by(|a, b| durations[*a].total_cmp(&durations[*b]))
+        .unwrap_or(0);
+    (0..durations.len())
+        .map(|ix| match ix {
+            0 => Phase::Startup,
+            _ if ix < steady => Phase::Warmup,
+            _ if ix == steady => Phase::SteadyState,
+            _ => Phase::Shutdown,
+        })
+        .collect()
+}
+
+/// Change points of the allocation rate, pause time and heap used after every young or
+/// full GC. Rates and pauses are skewed and compared on a log scale.
+pub fn segment_report(jfr: &JfrMain) -> SegmentReport {
+    let Some(origin) = jfr.start_time() else {
+        return SegmentReport::default();
+    };
+    let end = jfr
+        .recording
+        .events
+        .iter()
+        .filter_map(JfrEvent::start_time)
+        .max()
+        .unwrap_or(origin);
+    let cycles = jfr.gc_cycles();
+    let intervals = rate_intervals(jfr)
+        .into_iter()
+        .filter_map(|interval| {
+            let cycle = cycles.iter().find(|cycle| cycle.gc_id == interval.gc_id)?;
+            Some((interval, cycle.sum_of_pauses_ms, cycle.after?.used))
+        })
+        .collect::<Vec<_>>();
+    if intervals.is_empty() {
+        return SegmentReport::default();
+    }
+    let metrics = [
+        intervals
+            .iter()
+            .map(|(i, _, _)| i.allocation_mb_s.ln_1p())
+            .collect::<Vec<_>>(),
+        intervals
+            .iter()
+            .map(|(_, pause, _)| pause.ln_1p())
+            .collect(),
+        intervals
+            .iter()
+            .map(|(_, _, used)| *used as f64 / MB)
+            .collect(),
+    ]
+    .into_iter()
+    .filter_map(|xs| {
+        let std_dev = std_dev(&xs);
+        (std_dev > 0f64).then(|| xs.iter().map(|x| x / std_dev).collect::<Vec<_>>())
+    })
+    .collect::<Vec<_>>();
+    let n = intervals.len();
+    let mut points = vec![0];
+    split(
+        &Costs::new(&metrics),
+        0,
+        n,
+        PENALTY * metrics.len() as f64 * (n as f64).ln(),
+        &mut points,
+    );
+    points.push(n);
+
+    let total_s = (end - origin).num_microseconds().unwrap_or(0) as f64 / 1e6;
+    // a segment starts where the mutator time before its first GC starts
+    let starts = points
+        .iter()
+        .map(|ix| match intervals.get(*ix) {
+            Some((interval, _, _)) if *ix > 0 => interval.at_s - interval.seconds,
+            Some(_) => 0f64,
+            None => total_s,
+        })
+        .collect::<Vec<_>>();
+    let phases = phases(&starts.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>());
+    let at = |s: f64| origin + TimeDelta::microseconds((s * 1e6) as i64);
+    let segments = points
+        .windows(2)
+        .enumerate()
+        .map(|(index, range)| {
+            let gcs = &intervals[range[0]..range[1]];
+            let mean = |f: &dyn Fn(&(_, f64, u64)) -> f64| {
+                gcs.iter().map(f).sum::<f64>() / gcs.len() as f64
+            };
+            Segment {
+                index,
+                phase: phases[index],
+                start_s: starts[index],
+                end_s: starts[index + 1],
+                start_time: at(starts[index]),
+                end_time: at(starts[index + 1]),
+                gc_ids: gcs.iter().map(|(i, _, _)| i.gc_id).collect(),
+                allocation_mb_s: mean(&|(i, _, _)| i.allocation_mb_s),
+                pause_ms: mean(&|(_, pause, _)| *pause),
+                used_after: mean(&|(_, _, used)| *used as f64),
+            }
+        })
+        .collect();
+    SegmentReport { segments }
+}
+
+/// The recording from the start of the first segment `spec` picks to the end of the last.
+pub fn scope(jfr: &JfrMain, spec: SegmentSpec) -> Option<JfrMain> {
+    let report = segment_report(jfr);
+    let picked = report
+        .segments
+        .iter()
+        .filter(|segment| match spec {
+            SegmentSpec::Index(index) => segment.index == index,
+            SegmentSpec::Phase(phase) => segment.phase == phase,
+        })
+        .collect::<Vec<_>>();
+    let (first, last) = (picked.first()?, picked.last()?);
+    // the last segment ends with the last event, which must stay in
+    let end = if last.index + 1 == report.segments.len() {
+        last.end_time + TimeDelta::microseconds(1)
+    } else {
+        last.end_time
+    };
+    Some(jfr.between(first.start_time, end))
+}
+
+/// Allocation rate, pause time and heap used after every GC, segments shaded.
+pub fn segments(jfr: &JfrMain) -> Segments {
+    let report = segment_report(jfr);
+    let intervals = rate_intervals(jfr);
+    let cycles = jfr.gc_cycles();
+    let xs = intervals.iter().map(|i| i.at_s).collect::<Vec<_>>();
+    let mut plot = Plot::new();
+    plot.add_trace(
+        Scatter::new(
+            xs.clone(),
+            intervals.iter().map(|i| i.allocation_mb_s).collect(),
+        )
+        .name("allocation (MB/s)")
+        .mode(Mode::Markers),
+    );
+    let after = |gc_id: u64| cycles.iter().find(|cycle| cycle.gc_id == gc_id);
+    plot.add_trace(
+        Scatter::new(
+            xs.clone(),
+            intervals
+                .iter()
+                .map(|i| after(i.gc_id).map(|c| c.sum_of_pauses_ms).unwrap_or(0f64))
+                .collect(),
+        )
+        .name("pause (ms)")
+        .mode(Mode::Markers)
+        .y_axis("y2"),
+    );
+    plot.add_trace(
+        Scatter::new(
+            xs,
+            intervals
+                .iter()
+                .map(|i| {
+                    after(i.gc_id)
+                        .and_then(|c| c.after)
+                        .map(|heap| heap.used as f64 / MB)
+                        .unwrap_or(0f64)
+                })
+                .collect(),
+        )
+        .name("used after GC (MB)")
+        .mode(Mode::Lines),
+    );
+    let mut layout = Layout::new()
+        .x_axis(Axis::new().title("seconds since recording start"))
+        .y_axis(Axis::new().title("MB/s, MB"))
+        .y_axis2(
+            Axis::new()
+                .title("pause (ms)")
+                .overlaying("y")
+                .side(AxisSide::Right),
+        );
+    let colors = [
+        NamedColor::LightBlue,
+        NamedColor::LightGreen,
+        NamedColor::LightYellow,
+        NamedColor::LightPink,
+    ];
+    for segment in &report.segments {
+        layout.add_shape(
+            Shape::new()
+                .shape_type(ShapeType::Rect)
+                .layer(ShapeLayer::Below)
+                .x_ref("x")
+                .y_ref("paper")
+                .x0(segment.start_s)
+                .x1(segment.end_s)
+                .y0(0)
+                .y1(1)
+                .fill_color(colors[segment.index % colors.len()])
+                .opacity(0.4)
+                .line(ShapeLine::new().width(0f64)),
+        );
+        layout.add_annotation(
+            Annotation::new()
+                .x_ref("x")
+                .y_ref("paper")
+                .x((segment.start_s + segment.end_s) / 2f64)
+                .y(1)
+                .text(format!("{} {}", segment.index, segment.phase))
+                .show_arrow(false),
+        );
+    }
+    plot.set_layout(layout);
+    Segments { plot, report }
+}
+
+impl fmt::Display for SegmentReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>3} {:<9} {:>10} {:>10} {:>6} {:>10} {:>10} {:>10}",
+            "#", "phase", "from (s)", "to (s)", "GCs", "MB/s", "pause ms", "used MB"
+        )?;
+        for s in &self.segments {
+            writeln!(
+                f,
+                "{:>3} {:<9} {:>10.3} {:>10.3} {:>6} {:>10.1} {:>10.2} {:>10.1}",
+                s.index,
+                s.phase.to_string(),
+                s.start_s,
+                s.end_s,
+                s.gc_ids.len(),
+                s.allocation_mb_s,
+                s.pause_ms,
+                s.used_after / MB
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change_points(xs: &[f64], penalty: f64) -> Vec<usize> {
+        let costs = Costs::new(&[xs.to_vec()]);
+        let mut points = Vec::new();
+        split(&costs, 0, xs.len(), penalty, &mut points);
+        points
+    }
+
+    fn steps(levels: &[(f64, usize)]) -> Vec<f64> {
+        levels
+            .iter()
+            .flat_map(|(level, n)| {
+                (0..*n).map(move |ix| level + if ix % 2 == 0 { 0.1 } else { -0.1 })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_at_a_step() {
+        assert_eq!(change_points(&steps(&[(1f64, 8), (5f64, 12)]), 3f64), [8]);
+    }
+
+    #[test]
+    fn splits_at_every_step_in_order() {
+        let xs = steps(&[(1f64, 6), (5f64, 10), (2f64, 7)]);
+        assert_eq!(change_points(&xs, 3f64), [6, 16]);
+    }
+
+    #[test]
+    fn no_split_without_a_change() {
+        assert!(change_points(&steps(&[(3f64, 30)]), 3f64).is_empty());
+    }
+
+    #[test]
+    fn no_split_below_the_penalty() {
+        assert!(change_points(&steps(&[(1f64, 10), (1.5, 10)]), 3f64).is_empty());
+        assert_eq!(change_points(&steps(&[(1f64, 10), (1.5, 10)]), 0.5), [10]);
+    }
+
+    #[test]
+    fn segments_have_min_gcs() {
+        // too short to split at all
+        assert!(change_points(&steps(&[(1f64, 4), (9f64, 5)]), 3f64).is_empty());
+        // a step closer to the start than MIN_GCS moves to MIN_GCS
+        assert_eq!(
+            change_points(&steps(&[(1f64, 2), (9f64, 18)]), 3f64),
+            [MIN_GCS]
+        );
+    }
+
+    #[test]
+    fn longest_later_segment_is_steady() {
+        use Phase::*;
+        assert_eq!(phases(&[5f64]), [SteadyState]);
+        assert_eq!(phases(&[50f64, 10f64]), [Startup, SteadyState]);
+        assert_eq!(
+            phases(&[10f64, 50f64, 20f64]),
+            [Startup, SteadyState, Shutdown]
+        );
+        assert_eq!(
+            phases(&[10f64, 5f64, 20f64, 60f64, 5f64, 1f64]),
+            [Startup, Warmup, Warmup, SteadyState, Shutdown, Shutdown]
+        );
+        assert!(phases(&[]).is_empty());
+    }
+
+    #[test]
+    fn segment_specs() {
+        assert_eq!("2".parse(), Ok(SegmentSpec::Index(2)));
+        assert_eq!("steady".parse(), Ok(SegmentSpec::Phase(Phase::SteadyState)));
+        assert_eq!(
+            "steady_state".parse(),
+            Ok(SegmentSpec::Phase(Phase::SteadyState))
+        );
+        assert_eq!("warmup".parse(), Ok(SegmentSpec::Phase(Phase::Warmup)));
+        assert!("-1".parse::<SegmentSpec>().is_err());
+        assert!("idle".parse::<SegmentSpec>().is_err());
+    }
+}
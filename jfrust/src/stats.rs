@@ -0,0 +1,604 @@
+use std::{collections::BTreeMap, fmt};
+
+use plotly::{
+    common::{AxisSide, Mode},
+    layout::Axis,
+    Histogram, Layout, Plot, Scatter,
+};
+use serde::Serialize;
+
+use crate::cycle::GcCycle;
+
+/// Nearest rank percentile of sorted values, `p` in 0..=100.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0f64;
+    }
+    let rank = (p / 100f64 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Pause times of a group of GCs. The percentiles are of the longest pause of each GC,
+/// the total is the sum of all their pauses.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct PauseStats {
+    pub count: usize,
+    pub total_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub p999_ms: f64,
+    pub max_ms: f64,
+}
+
+impl PauseStats {
+    pub fn of<'a>(cycles: impl IntoIterator<Item = &'a GcCycle>) -> PauseStats {
+        let mut longest = Vec::new();
+        let mut total_ms = 0f64;
+        for cycle in cycles {
+            longest.push(cycle.longest_pause_ms);
+            total_ms += cycle.sum_of_pauses_ms;
+        }
+        longest.sort_by(f64::total_cmp);
+        PauseStats {
+            count: longest.len(),
+            total_ms,
+            p50_ms: percentile(&longest, 50f64),
+            p90_ms: percentile(&longest, 90f64),
+            p99_ms: percentile(&longest, 99f64),
+            p999_ms: percentile(&longest, 99.9),
+            max_ms: longest.last().copied().unwrap_or(0f64),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PauseReport {
+    pub overall: PauseStats,
+    /// By `GarbageCollection.name`, G1New, G1Old or G1Full.
+    pub by_name: BTreeMap<String, PauseStats>,
+    pub by_cause: BTreeMap<String, PauseStats>,
+    /// By the type of young GCs, Normal, Concurrent Start, Mixed...
+    pub by_type: BTreeMap<String, PauseStats>,
+}
+
+/*
+{
+  "overall": {"count": 116, "total_ms": 612.0, "p50_ms": 4.0, ...},
+  "by_name": {"G1New": {...}, "G1Full": {...}},
+  "by_cause": {"G1 Evacuation Pause": {...}, "System.gc()": {...}},
+  "by_type": {"Normal": {...}, "ConcurrentStart": {...}}
+}
+*/
+impl PauseReport {
+    /// GCs without a pause, such as those the recording started in the middle of, are left out.
+    pub fn new(cycles: &[GcCycle]) -> PauseReport {
+        let paused = cycles
+            .iter()
+            .filter(|cycle| cycle.sum_of_pauses_ms > 0f64)
+            .collect::<Vec<_>>();
+        let group = |key: fn(&GcCycle) -> Option<String>| {
+            let mut groups: BTreeMap<String, Vec<&GcCycle>> = BTreeMap::new();
+            for cycle in &paused {
+                if let Some(key) = key(cycle) {
+                    groups.entry(key).or_default().push(cycle);
+                }
+            }
+            groups
+                .into_iter()
+                .map(|(key, cycles)| (key, PauseStats::of(cycles)))
+                .collect()
+        };
+        PauseReport {
+            overall: PauseStats::of(paused.iter().copied()),
+            by_name: group(|cycle| Some(cycle.name.clone())),
+            by_cause: group(|cycle| Some(cycle.cause.clone())),
+            by_type: group(|cycle| {
+                // only young GCs have a G1GarbageCollection event
+                (cycle.name == "G1New").then(|| format!("{:?}", cycle.collection_type))
+            }),
+        }
+    }
+}
+
+impl fmt::Display for PauseReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<32} {:>6} {:>10} {:>8} {:>8} {:>8} {:>8} {:>8}",
+            "pauses (ms)", "count", "total", "p50", "p90", "p99", "p99.9", "max"
+        )?;
+        let row = |f: &mut fmt::Formatter<'_>, label: &str, stats: &PauseStats| {
+            writeln!(
+                f,
+                "{:<32} {:>6} {:>10.1} {:>8.1} {:>8.1} {:>8.1} {:>8.1} {:>8.1}",
+                label,
+                stats.count,
+                stats.total_ms,
+                stats.p50_ms,
+                stats.p90_ms,
+                stats.p99_ms,
+                stats.p999_ms,
+                stats.max_ms
+            )
+        };
+        row(f, "all", &self.overall)?;
+        for (title, groups) in [
+            ("by name", &self.by_name),
+            ("by cause", &self.by_cause),
+            ("by type", &self.by_type),
+        ] {
+            writeln!(f, "{}", title)?;
+            for (key, stats) in groups {
+                row(f, &format!("  {}", key), stats)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct Pauses {
+    pub plot: Plot,
+    pub stats: PauseReport,
+}
+
+/// Histogram of the longest pause of each GC, with its CDF on a second y axis.
+pub fn pauses(cycles: &[GcCycle]) -> Pauses {
+    let stats = PauseReport::new(cycles);
+    let mut longest = cycles
+        .iter()
+        .filter(|cycle| cycle.sum_of_pauses_ms > 0f64)
+        .map(|cycle| cycle.longest_pause_ms)
+        .collect::<Vec<_>>();
+    longest.sort_by(f64::total_cmp);
+    let n = longest.len() as f64;
+    let cdf = (1..=longest.len())
+        .map(|rank| rank as f64 / n)
+        .collect::<Vec<_>>();
+    let mut plot = Plot::new();
+    plot.add_trace(Histogram::new(longest.clone()).name("GCs"));
+    plot.add_trace(
+        Scatter::new(longest, cdf)
+            .name("CDF")
+            .mode(Mode::Lines)
+            .y_axis("y2"),
+    );
+    plot.set_layout(
+        Layout::new()
+            .x_axis(Axis::new().title("longest pause of a GC (ms)"))
+            .y_axis(Axis::new().title("GCs"))
+            .y_axis2(
+                Axis::new()
+                    .overlaying("y")
+                    .side(AxisSide::Right)
+                    .range(vec![0f64, 1f64])
+                    .title("fraction of GCs"),
+            ),
+    );
+    Pauses { plot, stats }
+}
+
+/// Least squares fit of `y = intercept + slope * x`, with a t-test of the slope.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct LinearFit {
+    pub n: usize,
+    pub slope: f64,
+    pub intercept: f64,
+    pub r_squared: f64,
+    pub slope_stderr: f64,
+    pub t: f64,
+    /// One sided, the chance of a slope at least this high if the true slope were 0.
+    pub p_value: f64,
+}
+
+impl LinearFit {
+    pub fn at(&self, x: f64) -> f64 {
+        self.intercept + self.slope * x
+    }
+}
+
+/// None for fewer than 3 points, or when all share the same x.
+pub fn linear_fit(points: &[(f64, f64)]) -> Option<LinearFit> {
+    let n = points.len();
+    if n < 3 {
+        return None;
+    }
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n as f64;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n as f64;
+    let sxx = points
+        .iter()
+        .map(|(x, _)| (x - mean_x).powi(2))
+        .sum::<f64>();
+    let sxy = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum::<f64>();
+    let syy = points
+        .iter()
+        .map(|(_, y)| (y - mean_y).powi(2))
+        .sum::<f64>();
+    if sxx == 0f64 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    let residuals = points
+        .iter()
+        .map(|(x, y)| (y - intercept - slope * x).powi(2))
+        .sum::<f64>();
+    let df = (n - 2) as f64;
+    let slope_stderr = (residuals / df / sxx).sqrt();
+    let t = if slope_stderr > 0f64 {
+        slope / slope_stderr
+    } else if slope == 0f64 {
+        0f64
+    } else {
+        slope.signum() * f64::INFINITY
+    };
+    Some(LinearFit {
+        n,
+        slope,
+        intercept,
+        r_squared: if syy > 0f64 {
+            sxy * sxy / (sxx * syy)
+        } else {
+            1f64
+        },
+        slope_stderr,
+        t,
+        p_value: student_t_sf(t, df),
+    })
+}
+
+/// P(T > t) for Student's t distribution with `df` degrees of freedom.
+pub fn student_t_sf(t: f64, df: f64) -> f64 {
+    if t.is_infinite() {
+        return if t > 0f64 { 0f64 } else { 1f64 };
+    }
+    let tail = 0.5 * inc_beta(df / 2f64, 0.5, df / (df + t * t));
+    if t > 0f64 {
+        tail
+    } else {
+        1f64 - tail
+    }
+}
+
+/// Nearest rank percentile like [`percentile`], partially sorting `values` in place.
+pub fn percentile_of(values: &mut [f64], p: f64) -> f64 {
+    if values.is_empty() {
+        return 0f64;
+    }
+    let rank = (p / 100f64 * values.len() as f64).ceil() as usize;
+    let (_, value, _) =
+        values.select_nth_unstable_by(rank.clamp(1, values.len()) - 1, f64::total_cmp);
+    *value
+}
+
+/// xorshift64*, enough to resample with and the same on every run.
+pub struct XorShift(u64);
+
+impl XorShift {
+    pub fn new(seed: u64) -> XorShift {
+        XorShift(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// In 0..n, with a bias too small to matter for n far below 2^64.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Resamples a bootstrap draws.
+const RESAMPLES: usize = 1000;
+
+/// Percentile bootstrap of the difference of a statistic of two samples, `candidate`
+/// less `baseline`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Bootstrap {
+    pub confidence: f64,
+    pub low: f64,
+    pub high: f64,
+    /// Two sided, the share of resampled differences on the other side of 0, doubled.
+    pub p_value: f64,
+}
+
+impl Bootstrap {
+    /// Whether the interval leaves out 0.
+    pub fn significant(&self) -> bool {
+        self.low > 0f64 || self.high < 0f64
+    }
+}
+
+/// None when either sample is empty. `statistic` may reorder the values it is given.
+pub fn bootstrap<T: Copy>(
+    baseline: &[T],
+    candidate: &[T],
+    statistic: impl Fn(&mut [T]) -> f64,
+    confidence: f64,
+) -> Option<Bootstrap> {
+    if baseline.is_empty() || candidate.is_empty() {
+        return None;
+    }
+    let mut rng = XorShift::new(0x9e37_79b9_7f4a_7c15);
+    let mut resample = |values: &[T], into: &mut Vec<T>| {
+        into.clear();
+        into.extend((0..values.len()).map(|_| values[rng.below(values.len())]));
+    };
+    let (mut b, mut c) = (Vec::new(), Vec::new());
+    let mut differences = (0..RESAMPLES)
+        .map(|_| {
+            resample(baseline, &mut b);
+            resample(candidate, &mut c);
+            statistic(&mut c) - statistic(&mut b)
+        })
+        .collect::<Vec<_>>();
+    differences.sort_by(f64::total_cmp);
+    let tail = (1f64 - confidence) / 2f64 * 100f64;
+    let below = differences.partition_point(|d| *d < 0f64);
+    let above = differences.len() - differences.partition_point(|d| *d <= 0f64);
+    let other_side = below.min(above) + (differences.len() - below - above) / 2;
+    Some(Bootstrap {
+        confidence,
+        low: percentile(&differences, tail),
+        high: percentile(&differences, 100f64 - tail),
+        p_value: (2f64 * (other_side + 1) as f64 / (RESAMPLES + 1) as f64).min(1f64),
+    })
+}
+
+/// Lanczos approximation, g = 7.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1f64 - x);
+    }
+    let x = x - 1f64;
+    let mut sum = COEFFICIENTS[0];
+    for (i, c) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += c / (x + i as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2f64 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// Regularized incomplete beta function I_x(a, b).
+fn inc_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0f64 {
+        return 0f64;
+    }
+    if x >= 1f64 {
+        return 1f64;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1f64 - x).ln()).exp();
+    if x < (a + 1f64) / (a + b + 2f64) {
+        front * beta_cf(a, b, x) / a
+    } else {
+        1f64 - front * beta_cf(b, a, 1f64 - x) / b
+    }
+}
+
+/// Continued fraction of the incomplete beta function, by the modified Lentz method.
+fn beta_cf(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1f64;
+    let mut d = 1f64 - (a + b) * x / (a + 1f64);
+    d = if d.abs() < TINY { TINY } else { d }.recip();
+    let mut h = d;
+    for m in 1..300 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2f64 * m - 1f64) * (a + 2f64 * m));
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2f64 * m) * (a + 2f64 * m + 1f64));
+        for coefficient in [even, odd] {
+            d = 1f64 + coefficient * d;
+            d = if d.abs() < TINY { TINY } else { d }.recip();
+            c = 1f64 + coefficient / c;
+            c = if c.abs() < TINY { TINY } else { c };
+            h *= d * c;
+        }
+        if (d * c - 1f64).abs() < 1e-14 {
+            break;
+        }
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jfr::CollectionType;
+
+    fn gc(name: &str, cause: &str, longest_pause_ms: f64, sum_of_pauses_ms: f64) -> GcCycle {
+        GcCycle {
+            name: name.to_string(),
+            cause: cause.to_string(),
+            collection_type: CollectionType::Normal,
+            longest_pause_ms,
+            sum_of_pauses_ms,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn nearest_rank() {
+        let values = (1..=10).map(f64::from).collect::<Vec<_>>();
+        assert_eq!(percentile(&values, 50f64), 5f64);
+        assert_eq!(percentile(&values, 90f64), 9f64);
+        assert_eq!(percentile(&values, 91f64), 10f64);
+        assert_eq!(percentile(&values, 100f64), 10f64);
+        assert_eq!(percentile(&values, 0f64), 1f64);
+        assert_eq!(percentile(&[7f64], 99.9), 7f64);
+        assert_eq!(percentile(&[], 50f64), 0f64);
+    }
+
+    #[test]
+    fn high_percentiles_of_few_values() {
+        let values = (1..=100).map(f64::from).collect::<Vec<_>>();
+        assert_eq!(percentile(&values, 99f64), 99f64);
+        // fewer than 1000 values, p99.9 is the max
+        assert_eq!(percentile(&values, 99.9), 100f64);
+    }
+
+    #[test]
+    fn pause_stats() {
+        let cycles = [
+            gc("G1New", "G1 Evacuation Pause", 4f64, 4f64),
+            gc("G1Old", "G1 Humongous Allocation", 2f64, 3f64),
+            gc("G1New", "G1 Evacuation Pause", 1f64, 1f64),
+            gc("G1Full", "System.gc()", 100f64, 100f64),
+        ];
+        let stats = PauseStats::of(&cycles);
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.total_ms, 108f64);
+        assert_eq!(stats.p50_ms, 2f64);
+        assert_eq!(stats.p90_ms, 100f64);
+        assert_eq!(stats.max_ms, 100f64);
+        assert_eq!(PauseStats::of(&[]), PauseStats::default());
+    }
+
+    #[test]
+    fn pause_report_groups() {
+        let cycles = [
+            gc("G1New", "G1 Evacuation Pause", 4f64, 4f64),
+            gc("G1Old", "G1 Humongous Allocation", 2f64, 3f64),
+            gc("G1New", "G1 Evacuation Pause", 1f64, 1f64),
+            // the recording started during this one
+            gc("G1New", "G1 Evacuation Pause", 0f64, 0f64),
+        ];
+        let report = PauseReport::new(&cycles);
+        assert_eq!(report.overall.count, 3);
+        assert_eq!(report.by_name["G1New"].count, 2);
+        assert_eq!(report.by_name["G1Old"].total_ms, 3f64);
+        assert_eq!(report.by_cause["G1 Evacuation Pause"].max_ms, 4f64);
+        assert_eq!(
+            report.by_type.keys().collect::<Vec<_>>(),
+            [&"Normal".to_string()]
+        );
+        assert_eq!(report.by_type["Normal"].count, 2);
+    }
+
+    fn mean(values: &mut [f64]) -> f64 {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn percentile_of_unsorted_values() {
+        let mut rng = XorShift::new(42);
+        let values = (0..101).map(|_| rng.below(1000) as f64).collect::<Vec<_>>();
+        let mut sorted = values.clone();
+        sorted.sort_by(f64::total_cmp);
+        for p in [0f64, 1f64, 50f64, 90f64, 99f64, 99.9, 100f64] {
+            assert_eq!(
+                percentile_of(&mut values.clone(), p),
+                percentile(&sorted, p),
+                "p{}",
+                p
+            );
+        }
+        assert_eq!(percentile_of(&mut [], 50f64), 0f64);
+    }
+
+    #[test]
+    fn student_t_tails() {
+        // t tables
+        assert!(close(student_t_sf(2.228, 10f64), 0.025));
+        assert!(close(student_t_sf(1.812, 10f64), 0.05));
+        assert!(close(student_t_sf(2.576, 1e6), 0.005));
+        // Cauchy
+        assert!(close(student_t_sf(1f64, 1f64), 0.25));
+        assert_eq!(student_t_sf(0f64, 5f64), 0.5);
+        assert!(close(student_t_sf(-2.228, 10f64), 0.975));
+        assert_eq!(student_t_sf(f64::INFINITY, 3f64), 0f64);
+        assert_eq!(student_t_sf(f64::NEG_INFINITY, 3f64), 1f64);
+    }
+
+    #[test]
+    fn linear_fit_of_a_line() {
+        let fit = linear_fit(&[(0f64, 1f64), (1f64, 3f64), (2f64, 5f64), (3f64, 7f64)]).unwrap();
+        assert_eq!(
+            (fit.slope, fit.intercept, fit.r_squared),
+            (2f64, 1f64, 1f64)
+        );
+        assert_eq!(fit.p_value, 0f64);
+        assert_eq!(fit.at(10f64), 21f64);
+        assert!(linear_fit(&[(0f64, 1f64), (1f64, 3f64)]).is_none());
+        assert!(linear_fit(&[(1f64, 1f64), (1f64, 3f64), (1f64, 5f64)]).is_none());
+    }
+
+    #[test]
+    fn bootstrap_of_a_shift() {
+        let baseline = (0..50).map(|x| (x % 10) as f64).collect::<Vec<_>>();
+        let candidate = baseline.iter().map(|x| x + 5f64).collect::<Vec<_>>();
+        let b = bootstrap(&baseline, &candidate, mean, 0.95).unwrap();
+        assert!(b.significant());
+        // standard error of the difference of the means about 0.58
+        assert!(b.low > 3.5 && b.low < 5f64, "{:?}", b);
+        assert!(b.high > 5f64 && b.high < 6.5, "{:?}", b);
+        assert!(b.p_value < 0.01, "{:?}", b);
+        let lower = bootstrap(&candidate, &baseline, mean, 0.95).unwrap();
+        assert!(lower.significant() && lower.high < 0f64, "{:?}", lower);
+    }
+
+    #[test]
+    fn bootstrap_of_the_same_sample() {
+        let sample = (0..50).map(|x| (x * 7 % 13) as f64).collect::<Vec<_>>();
+        let b = bootstrap(&sample, &sample, mean, 0.95).unwrap();
+        assert!(!b.significant(), "{:?}", b);
+        assert!(b.low < 0f64 && b.high > 0f64, "{:?}", b);
+        assert!(b.p_value > 0.5, "{:?}", b);
+        // the same seed every run
+        assert_eq!(bootstrap(&sample, &sample, mean, 0.95), Some(b));
+        let wider = bootstrap(&sample, &sample, mean, 0.99).unwrap();
+        assert!(wider.low <= b.low && wider.high >= b.high);
+    }
+
+    #[test]
+    fn bootstrap_of_constants() {
+        let b = bootstrap(&[3f64; 20], &[3f64; 20], mean, 0.95).unwrap();
+        assert_eq!((b.low, b.high), (0f64, 0f64));
+        assert!(!b.significant());
+        assert_eq!(b.p_value, 1f64);
+    }
+
+    #[test]
+    fn bootstrap_needs_both_samples() {
+        assert_eq!(bootstrap(&[], &[1f64], mean, 0.95), None);
+        assert_eq!(bootstrap(&[1f64], &[], mean, 0.95), None);
+    }
+
+    #[test]
+    fn bootstrap_of_pairs() {
+        // overhead as total pause over total period, as compare samples it
+        let share = |pairs: &mut [(f64, f64)]| {
+            let (pause, period) = pairs
+                .iter()
+                .fold((0f64, 0f64), |(a, b), (p, q)| (a + p, b + q));
+            pause / period * 100f64
+        };
+        let baseline = vec![(10f64, 1000f64); 30];
+        let candidate = vec![(30f64, 1000f64); 30];
+        let b = bootstrap(&baseline, &candidate, share, 0.95).unwrap();
+        assert!(close(b.low, 2f64) && close(b.high, 2f64), "{:?}", b);
+    }
+}
@@ -0,0 +1,412 @@
+use std::{collections::BTreeMap, fmt};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use plotly::{
+    color::NamedColor,
+    common::{AxisSide, DashType, Line, Mode},
+    layout::{Axis, Shape, ShapeLayer, ShapeLine, ShapeType},
+    Layout, Plot, Scatter,
+};
+use serde::Serialize;
+
+use crate::{
+    cycle::GcCycle,
+    jfr::{ms_since, JfrEvent, JfrMain},
+    mmu::Timeline,
+    stats::linear_fit,
+    MB,
+};
+
+/// Beyond either threshold, a window of the recording is part of a storm.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct StormThresholds {
+    pub window_ms: f64,
+    /// GCs started per second over the window.
+    pub max_gcs_per_s: f64,
+    /// Share of the window spent in pauses.
+    pub max_overhead: f64,
+}
+
+impl Default for StormThresholds {
+    fn default() -> Self {
+        StormThresholds {
+            window_ms: 1000f64,
+            max_gcs_per_s: 5f64,
+            max_overhead: 0.2,
+        }
+    }
+}
+
+/// Which threshold a window went over.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum StormTrigger {
+    Rate,
+    Overhead,
+}
+
+/// Consecutive windows over a threshold, from the start of their first GC to the end of
+/// their last one.
+#[derive(Serialize, Debug, Clone)]
+pub struct StormIncident {
+    pub start_s: f64,
+    pub end_s: f64,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub gc_ids: Vec<u64>,
+    pub triggers: Vec<StormTrigger>,
+    /// Highest rate and overhead of the windows in the incident.
+    pub peak_gcs_per_s: f64,
+    pub peak_overhead: f64,
+    pub pause_ms: f64,
+    pub used_before: Option<u64>,
+    pub used_after: Option<u64>,
+    /// Heap used after its GCs, in bytes per second.
+    pub used_trend: Option<f64>,
+    /// Number of GCs by cause.
+    pub causes: BTreeMap<String, usize>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct StormReport {
+    pub thresholds: StormThresholds,
+    pub gcs: usize,
+    pub incidents: Vec<StormIncident>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Storms {
+    pub plot: Plot,
+    pub report: StormReport,
+}
+
+/// A GC with its start and end in ms since the recording started.
+struct Gc<'a> {
+    cycle: &'a GcCycle,
+    start: f64,
+    end: f64,
+}
+
+/// GC rate and pause overhead of the window ending with a GC.
+struct Window {
+    from: f64,
+    to: f64,
+    gcs_per_s: f64,
+    overhead: f64,
+}
+
+/// Sorted by end, concurrent GCs end after the young GCs that start during them.
+fn windows(gcs: &[Gc], timeline: &Timeline, window_ms: f64) -> Vec<Window> {
+    let mut windows = gcs
+        .iter()
+        .map(|gc| {
+            let to = gc.end;
+            let from = to - window_ms;
+            let started =
+                gcs.partition_point(|g| g.start < to) - gcs.partition_point(|g| g.start < from);
+            Window {
+                from,
+                to,
+                gcs_per_s: started as f64 * 1000f64 / window_ms,
+                overhead: timeline.paused(from, to) / window_ms,
+            }
+        })
+        .collect::<Vec<_>>();
+    windows.sort_by(|a, b| a.to.total_cmp(&b.to));
+    windows
+}
+
+fn incident(
+    gcs: &[Gc],
+    windows: &[&Window],
+    thresholds: &StormThresholds,
+    origin: DateTime<Utc>,
+) -> StormIncident {
+    let at = |ms: f64| origin + TimeDelta::microseconds((ms * 1000f64) as i64);
+    let mut triggers = Vec::new();
+    if windows
+        .iter()
+        .any(|w| w.gcs_per_s > thresholds.max_gcs_per_s)
+    {
+        triggers.push(StormTrigger::Rate);
+    }
+    if windows.iter().any(|w| w.overhead > thresholds.max_overhead) {
+        triggers.push(StormTrigger::Overhead);
+    }
+    let mut causes = BTreeMap::new();
+    for gc in gcs {
+        *causes.entry(gc.cycle.cause.clone()).or_insert(0) += 1;
+    }
+    let used = gcs
+        .iter()
+        .filter_map(|gc| Some((gc.end / 1000f64, gc.cycle.after?.used as f64)))
+        .collect::<Vec<_>>();
+    let start = gcs.first().map(|gc| gc.start).unwrap_or(0f64);
+    let end = gcs.iter().map(|gc| gc.end).fold(start, f64::max);
+    StormIncident {
+        start_s: start / 1000f64,
+        end_s: end / 1000f64,
+        start_time: Some(at(start)),
+        end_time: Some(at(end)),
+        gc_ids: gcs.iter().map(|gc| gc.cycle.gc_id).collect(),
+        triggers,
+        peak_gcs_per_s: windows.iter().map(|w| w.gcs_per_s).fold(0f64, f64::max),
+        peak_overhead: windows.iter().map(|w| w.overhead).fold(0f64, f64::max),
+        pause_ms: gcs.iter().map(|gc| gc.cycle.sum_of_pauses_ms).sum(),
+        used_before: gcs
+            .first()
+            .and_then(|gc| gc.cycle.before)
+            .map(|heap| heap.used),
+        used_after: gcs
+            .last()
+            .and_then(|gc| gc.cycle.after)
+            .map(|heap| heap.used),
+        used_trend: linear_fit(&used).map(|fit| fit.slope),
+        causes,
+    }
+}
+
+fn detect(jfr: &JfrMain, thresholds: StormThresholds) -> (StormReport, Vec<Window>) {
+    let mut report = StormReport {
+        thresholds,
+        ..Default::default()
+    };
+    let Some(origin) = jfr.start_time() else {
+        return (report, Vec::new());
+    };
+    let cycles = jfr.gc_cycles();
+    report.gcs = cycles.len();
+    let mut gcs = cycles
+        .iter()
+        .filter_map(|cycle| {
+            let start = ms_since(origin, cycle.start_time?);
+            Some(Gc {
+                cycle,
+                start,
+                end: start + cycle.duration_ms,
+            })
+        })
+        .collect::<Vec<_>>();
+    gcs.sort_by(|a, b| a.start.total_cmp(&b.start));
+    let pauses = jfr.pause_intervals(origin);
+    let end = jfr
+        .recording
+        .events
+        .iter()
+        .filter_map(JfrEvent::start_time)
+        .max()
+        .map(|end| ms_since(origin, end))
+        .unwrap_or(0f64);
+    let end = pauses.iter().map(|(_, end)| *end).fold(end, f64::max);
+    let timeline = Timeline::new(pauses, 0f64, end);
+    let windows = windows(&gcs, &timeline, thresholds.window_ms);
+
+    // overlapping windows over a threshold make one incident
+    let mut stormy: Vec<Vec<&Window>> = Vec::new();
+    for window in windows
+        .iter()
+        .filter(|w| w.gcs_per_s > thresholds.max_gcs_per_s || w.overhead > thresholds.max_overhead)
+    {
+        match stormy.last_mut() {
+            Some(last) if window.from <= last[last.len() - 1].to => last.push(window),
+            _ => stormy.push(vec![window]),
+        }
+    }
+    for windows in stormy {
+        let from = windows[0].from;
+        let to = windows[windows.len() - 1].to;
+        let first = gcs.partition_point(|gc| gc.start < from);
+        let last = gcs.partition_point(|gc| gc.start < to);
+        report
+            .incidents
+            .push(incident(&gcs[first..last], &windows, &thresholds, origin));
+    }
+    (report, windows)
+}
+
+pub fn storm_report(jfr: &JfrMain, thresholds: StormThresholds) -> StormReport {
+    detect(jfr, thresholds).0
+}
+
+/// GC rate and overhead of the window ending with every GC, incidents shaded.
+pub fn storms(jfr: &JfrMain, thresholds: StormThresholds) -> Storms {
+    let (report, windows) = detect(jfr, thresholds);
+    let xs = windows.iter().map(|w| w.to / 1000f64).collect::<Vec<_>>();
+    let mut plot = Plot::new();
+    plot.add_trace(
+        Scatter::new(xs.clone(), windows.iter().map(|w| w.gcs_per_s).collect())
+            .name("GCs per second")
+            .mode(Mode::LinesMarkers),
+    );
+    plot.add_trace(
+        Scatter::new(
+            xs.clone(),
+            windows.iter().map(|w| w.overhead * 100f64).collect(),
+        )
+        .name("% of time in pauses")
+        .mode(Mode::LinesMarkers)
+        .y_axis("y2"),
+    );
+    let ends = match (xs.first(), xs.last()) {
+        (Some(first), Some(last)) => vec![*first, *last],
+        _ => Vec::new(),
+    };
+    plot.add_trace(
+        Scatter::new(ends.clone(), vec![thresholds.max_gcs_per_s; ends.len()])
+            .name("max GCs per second")
+            .mode(Mode::Lines)
+            .line(Line::new().dash(DashType::Dash)),
+    );
+    plot.add_trace(
+        Scatter::new(
+            ends.clone(),
+            vec![thresholds.max_overhead * 100f64; ends.len()],
+        )
+        .name("max % in pauses")
+        .mode(Mode::Lines)
+        .line(Line::new().dash(DashType::Dot))
+        .y_axis("y2"),
+    );
+    let mut layout = Layout::new()
+        .x_axis(Axis::new().title("seconds since recording start"))
+        .y_axis(Axis::new().title(format!(
+            "GCs per {}ms window, per second",
+            thresholds.window_ms
+        )))
+        .y_axis2(
+            Axis::new()
+                .title("% of time in pauses")
+                .overlaying("y")
+                .side(AxisSide::Right),
+        );
+    for incident in &report.incidents {
+        layout.add_shape(
+            Shape::new()
+                .shape_type(ShapeType::Rect)
+                .layer(ShapeLayer::Below)
+                .x_ref("x")
+                .y_ref("paper")
+                .x0(incident.start_s)
+                .x1(incident.end_s)
+                .y0(0)
+                .y1(1)
+                .fill_color(NamedColor::Orange)
+                .opacity(0.3)
+                .line(ShapeLine::new().width(0f64)),
+        );
+    }
+    plot.set_layout(layout);
+    Storms { plot, report }
+}
+
+impl fmt::Display for StormReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} incidents in {} GCs, over {} GCs/s or {:.0}% in pauses over {}ms",
+            self.incidents.len(),
+            self.gcs,
+            self.thresholds.max_gcs_per_s,
+            self.thresholds.max_overhead * 100f64,
+            self.thresholds.window_ms
+        )?;
+        let mb = |bytes: Option<u64>| {
+            bytes
+                .map(|bytes| format!("{:.1}MB", bytes as f64 / MB))
+                .unwrap_or_else(|| "-".to_string())
+        };
+        for incident in &self.incidents {
+            writeln!(
+                f,
+                "{:.3}s-{:.3}s: {} GCs ({}-{}), peak {:.1} GCs/s and {:.1}% in pauses, {:.1}ms of pauses, {:?}",
+                incident.start_s,
+                incident.end_s,
+                incident.gc_ids.len(),
+                incident.gc_ids.first().copied().unwrap_or(0),
+                incident.gc_ids.last().copied().unwrap_or(0),
+                incident.peak_gcs_per_s,
+                incident.peak_overhead * 100f64,
+                incident.pause_ms,
+                incident.triggers
+            )?;
+            write!(
+                f,
+                "  heap used {}->{}",
+                mb(incident.used_before),
+                mb(incident.used_after)
+            )?;
+            match incident.used_trend {
+                Some(trend) => writeln!(f, ", {:+.1}MB/s after GCs", trend / MB)?,
+                None => writeln!(f)?,
+            }
+            for (cause, count) in &incident.causes {
+                writeln!(f, "  {:>5} {}", count, cause)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gclog;
+
+    /// Young GCs ending at `at` seconds with a pause of `ms`.
+    fn recording(gcs: &[(f64, f64)]) -> JfrMain {
+        let mut log = String::new();
+        for (gc_id, (at, ms)) in gcs.iter().enumerate() {
+            log += &format!(
+                "[{at:.3}s][info ][gc] GC({gc_id}) Pause Young (Normal) (G1 Evacuation Pause) 50M->10M(96M) {ms:.3}ms\n"
+            );
+        }
+        gclog::parse(log.as_bytes()).unwrap()
+    }
+
+    /// A GC every 2s, 100 to 118s and 124 to 130s.
+    fn calm() -> Vec<(f64, f64)> {
+        (50..60)
+            .chain(62..66)
+            .map(|s| (s as f64 * 2f64, 1f64))
+            .collect()
+    }
+
+    #[test]
+    fn calm_recording_has_no_storm() {
+        let report = storm_report(&recording(&calm()), StormThresholds::default());
+        assert_eq!(report.gcs, 14);
+        assert!(report.incidents.is_empty());
+    }
+
+    #[test]
+    fn bursts_in_overlapping_windows_are_one_incident() {
+        let mut gcs = calm();
+        // ten GCs 100ms apart from 120s, four more from 121.3s
+        gcs.extend((0..10).map(|i| (120f64 + i as f64 / 10f64, 1f64)));
+        gcs.extend((0..4).map(|i| (121.3 + i as f64 / 10f64, 1f64)));
+        gcs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let jfr = recording(&gcs);
+        let report = storm_report(&jfr, StormThresholds::default());
+        let [incident] = &report.incidents[..] else {
+            panic!("{:?}", report.incidents);
+        };
+        assert_eq!(incident.gc_ids, (10..24).collect::<Vec<_>>());
+        assert_eq!(incident.triggers, [StormTrigger::Rate]);
+        assert_eq!(incident.peak_gcs_per_s, 10f64);
+        assert_eq!(incident.causes["G1 Evacuation Pause"], 14);
+    }
+
+    #[test]
+    fn long_pauses_are_an_overhead_storm() {
+        let mut gcs = calm();
+        gcs.extend([(120.2, 150f64), (120.5, 150f64)]);
+        gcs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let report = storm_report(&recording(&gcs), StormThresholds::default());
+        let [incident] = &report.incidents[..] else {
+            panic!("{:?}", report.incidents);
+        };
+        assert_eq!(incident.gc_ids, [10, 11]);
+        assert_eq!(incident.triggers, [StormTrigger::Overhead]);
+        assert!((incident.peak_overhead - 0.3).abs() < 1e-6);
+    }
+}
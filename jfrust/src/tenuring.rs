@@ -0,0 +1,322 @@
+use std::{collections::BTreeMap, fmt};
+
+use plotly::{
+    common::{AxisSide, Mode},
+    layout::Axis,
+    Bar, Layout, Plot, Scatter,
+};
+use serde::Serialize;
+
+use crate::jfr::{JfrEvent, JfrMain};
+
+/// HotSpot never tenures later than this.
+pub const MAX_TENURING_THRESHOLD: u64 = 15;
+/// Survival above this means the cohort is long lived, and copying it again is wasted.
+const LONG_LIVED: f64 = 0.9;
+/// -XX:TargetSurvivorRatio default, G1 lowers the threshold when survivors pass it.
+const TARGET_SURVIVOR_RATIO: f64 = 0.5;
+
+/// The bytes of age `age` after a GC, and how many of them were at age `age + 1`
+/// after the following young GC.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct AgeSurvival {
+    pub age: u64,
+    pub bytes: u64,
+    pub survived: u64,
+    pub rate: f64,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct TenuringAdvice {
+    /// gcIds of the consecutive young GCs the survival rates are computed from.
+    pub gc_pairs: Vec<(u64, u64)>,
+    pub survival: Vec<AgeSurvival>,
+    /// Highest tenuring threshold G1 used, MaxTenuringThreshold unless survivors overflowed.
+    pub current_threshold: Option<u64>,
+    pub recommended_threshold: u64,
+    /// Most bytes of ages up to the recommended threshold seen after a single GC.
+    pub survivor_peak: u64,
+    /// Survivor space that keeps `survivor_peak` under TargetSurvivorRatio.
+    pub recommended_survivor_bytes: u64,
+    pub reason: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Tenuring {
+    pub plot: Plot,
+    pub advice: TenuringAdvice,
+}
+
+/// Bytes by age from jdk.TenuringDistribution, by gcId.
+fn age_tables(jfr: &JfrMain) -> BTreeMap<u64, BTreeMap<u64, u64>> {
+    let mut tables: BTreeMap<u64, BTreeMap<u64, u64>> = BTreeMap::new();
+    for evt in &jfr.recording.events {
+        if let JfrEvent::TenuringDistribution { values } = evt {
+            *tables
+                .entry(values.gc_id)
+                .or_default()
+                .entry(values.age)
+                .or_default() += values.size;
+        }
+    }
+    tables
+}
+
+pub fn advise(jfr: &JfrMain) -> TenuringAdvice {
+    let cycles = jfr.gc_cycles();
+    let current_threshold = cycles.iter().filter_map(|c| c.tenuring_threshold).max();
+    let tables = age_tables(jfr);
+
+    // follow the cohort of age k after GC n to age k + 1 after GC n + 1,
+    // a full GC in between resets the ages and a young GC without an age table
+    // hides them, concurrent cycles overlap the young GCs and are skipped
+    let mut bytes: BTreeMap<u64, (u64, u64)> = BTreeMap::new();
+    let mut gc_pairs = Vec::new();
+    let stopping = cycles
+        .iter()
+        .filter(|cycle| cycle.name != "G1Old")
+        .collect::<Vec<_>>();
+    for pair in stopping.windows(2) {
+        let [gc, next_gc] = pair else {
+            continue;
+        };
+        if gc.name != "G1New" || next_gc.name != "G1New" {
+            continue;
+        }
+        let (Some(table), Some(next)) = (tables.get(&gc.gc_id), tables.get(&next_gc.gc_id)) else {
+            continue;
+        };
+        gc_pairs.push((gc.gc_id, next_gc.gc_id));
+        for (age, size) in table.iter() {
+            let survived = next.get(&(age + 1)).copied().unwrap_or(0);
+            let entry = bytes.entry(*age).or_default();
+            entry.0 += size;
+            entry.1 += survived.min(*size);
+        }
+    }
+    let survival = bytes
+        .into_iter()
+        .filter(|(age, (size, _))| *size > 0 && *age < MAX_TENURING_THRESHOLD)
+        .map(|(age, (bytes, survived))| AgeSurvival {
+            age,
+            bytes,
+            survived,
+            rate: survived as f64 / bytes as f64,
+        })
+        .collect::<Vec<_>>();
+
+    // the youngest age from which every older cohort is long lived
+    let plateau = (0..survival.len())
+        .find(|ix| survival[*ix..].iter().all(|s| s.rate >= LONG_LIVED))
+        .map(|ix| &survival[ix]);
+    let (recommended_threshold, reason) = match plateau {
+        _ if gc_pairs.is_empty() => (
+            current_threshold.unwrap_or(MAX_TENURING_THRESHOLD),
+            "no consecutive young GCs with jdk.TenuringDistribution".to_string(),
+        ),
+        Some(s) => (
+            s.age.max(1),
+            format!(
+                "{:.0}% or more of the bytes at age {} and older survive every GC, \
+                 copying them between survivor regions again is wasted",
+                LONG_LIVED * 100f64,
+                s.age
+            ),
+        ),
+        None => (
+            MAX_TENURING_THRESHOLD,
+            format!(
+                "survivors keep dying as they age, and are gone by age {}, \
+                 keep them out of the old generation",
+                survival
+                    .iter()
+                    .rev()
+                    .find(|s| s.survived > 0)
+                    .map(|s| s.age + 2)
+                    .unwrap_or(1)
+            ),
+        ),
+    };
+    let survivor_peak = tables
+        .values()
+        .map(|table| table.range(..=recommended_threshold).map(|(_, s)| s).sum())
+        .max()
+        .unwrap_or(0);
+    TenuringAdvice {
+        gc_pairs,
+        survival,
+        current_threshold,
+        recommended_threshold,
+        survivor_peak,
+        recommended_survivor_bytes: (survivor_peak as f64 / TARGET_SURVIVOR_RATIO) as u64,
+        reason,
+    }
+}
+
+pub fn tenuring(jfr: &JfrMain) -> Tenuring {
+    let advice = advise(jfr);
+    let ages = advice.survival.iter().map(|s| s.age).collect::<Vec<_>>();
+    let mut plot = Plot::new();
+    plot.add_trace(
+        Bar::new(
+            ages.clone(),
+            advice.survival.iter().map(|s| s.rate).collect(),
+        )
+        .name("survival to the next age"),
+    );
+    plot.add_trace(
+        Scatter::new(
+            ages,
+            advice
+                .survival
+                .iter()
+                .map(|s| s.bytes as f64 / advice.gc_pairs.len().max(1) as f64)
+                .collect(),
+        )
+        .name("bytes per GC")
+        .mode(Mode::LinesMarkers)
+        .y_axis("y2"),
+    );
+    plot.set_layout(
+        Layout::new()
+            .x_axis(Axis::new().title("age"))
+            .y_axis(Axis::new().title("survival rate").range(vec![0f64, 1f64]))
+            .y_axis2(
+                Axis::new()
+                    .overlaying("y")
+                    .side(AxisSide::Right)
+                    .title("bytes"),
+            ),
+    );
+    Tenuring { plot, advice }
+}
+
+impl fmt::Display for TenuringAdvice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "survival by age over {} GC pairs", self.gc_pairs.len())?;
+        writeln!(
+            f,
+            "{:>4} {:>14} {:>14} {:>8}",
+            "age", "bytes", "survived", "rate"
+        )?;
+        for s in &self.survival {
+            writeln!(
+                f,
+                "{:>4} {:>14} {:>14} {:>7.1}%",
+                s.age,
+                s.bytes,
+                s.survived,
+                s.rate * 100f64
+            )?;
+        }
+        writeln!(
+            f,
+            "MaxTenuringThreshold: {} (now {}), {}",
+            self.recommended_threshold,
+            self.current_threshold
+                .map(|t| t.to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+            self.reason
+        )?;
+        writeln!(
+            f,
+            "survivor space: {} bytes, peak of {} bytes up to age {} at TargetSurvivorRatio {:.0}",
+            self.recommended_survivor_bytes,
+            self.survivor_peak,
+            self.recommended_threshold,
+            TARGET_SURVIVOR_RATIO * 100f64
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gclog;
+
+    fn young(gc_id: u64, ages: &[(u64, u64)]) -> String {
+        let mut log = format!(
+            "[{gc_id}.000s][info ][gc,start] GC({gc_id}) Pause Young (Normal) (G1 Evacuation Pause)\n"
+        );
+        for (age, size) in ages {
+            log += &format!(
+                "[{gc_id}.001s][trace][gc,age  ] GC({gc_id}) - age {age:>3}: {size:>10} bytes, {size:>10} total\n"
+            );
+        }
+        log + &format!(
+            "[{gc_id}.002s][info ][gc      ] GC({gc_id}) Pause Young (Normal) (G1 Evacuation Pause) 50M->10M(96M) 2.000ms\n"
+        )
+    }
+
+    fn full(gc_id: u64) -> String {
+        format!("[{gc_id}.002s][info ][gc      ] GC({gc_id}) Pause Full (System.gc()) 50M->10M(96M) 20.000ms\n")
+    }
+
+    fn concurrent(gc_id: u64) -> String {
+        format!(
+            "[{gc_id}.000s][info ][gc      ] GC({gc_id}) Concurrent Mark Cycle\n\
+             [{gc_id}.500s][info ][gc      ] GC({gc_id}) Concurrent Mark Cycle 500.000ms\n"
+        )
+    }
+
+    fn advise_log(log: &str) -> TenuringAdvice {
+        advise(&gclog::parse(log.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn follows_cohorts_between_young_gcs() {
+        let advice = advise_log(
+            &[
+                young(1, &[(1, 1000)]),
+                young(2, &[(1, 2000), (2, 800)]),
+                young(3, &[(1, 1000), (2, 1000), (3, 800)]),
+            ]
+            .concat(),
+        );
+        assert_eq!(advice.gc_pairs, [(1, 2), (2, 3)]);
+        let rates = advice
+            .survival
+            .iter()
+            .map(|s| (s.age, s.bytes, s.survived))
+            .collect::<Vec<_>>();
+        assert_eq!(rates, [(1, 3000, 1800), (2, 800, 800)]);
+    }
+
+    #[test]
+    fn full_gc_breaks_the_chain() {
+        let advice =
+            advise_log(&[young(1, &[(1, 1000)]), full(2), young(3, &[(2, 1000)])].concat());
+        assert!(advice.gc_pairs.is_empty());
+        assert!(advice.survival.is_empty());
+        assert_eq!(
+            advice.reason,
+            "no consecutive young GCs with jdk.TenuringDistribution"
+        );
+    }
+
+    #[test]
+    fn young_gc_without_ages_breaks_the_chain() {
+        let advice = advise_log(
+            &[
+                young(1, &[(1, 1000)]),
+                young(2, &[]),
+                young(3, &[(2, 1000)]),
+                young(4, &[(1, 500), (3, 900)]),
+            ]
+            .concat(),
+        );
+        assert_eq!(advice.gc_pairs, [(3, 4)]);
+        assert_eq!(advice.survival[0].age, 2);
+        assert_eq!(advice.survival[0].rate, 0.9);
+    }
+
+    #[test]
+    fn concurrent_cycles_are_skipped() {
+        let advice =
+            advise_log(&[young(1, &[(1, 1000)]), concurrent(2), young(3, &[(2, 950)])].concat());
+        assert_eq!(advice.gc_pairs, [(1, 3)]);
+        assert_eq!(advice.survival[0].rate, 0.95);
+        // long lived from age 1
+        assert_eq!(advice.recommended_threshold, 1);
+    }
+}
@@ -0,0 +1,563 @@
+use std::{collections::HashMap, fmt};
+
+use serde::Serialize;
+
+use crate::{
+    cycle::GcCycle,
+    fullgc::{full_gcs, FullGcCause},
+    jfr::{CollectionType, Flag, JfrMain},
+    rates::rate_intervals,
+    stats::percentile,
+    tenuring, MB,
+};
+
+/// GC ids cited per recommendation, the rest are counted.
+const MAX_CITED: usize = 10;
+/// Share of young pauses over MaxGCPauseMillis that G1 cannot be expected to avoid.
+const OVER_TARGET_SHARE: f64 = 0.1;
+/// Share of the recording in young pauses that calls for a larger young generation.
+const YOUNG_OVERHEAD: f64 = 0.05;
+/// Largest G1HeapRegionSize before JDK 18.
+const MAX_REGION_SIZE: u64 = 32 * 1024 * 1024;
+/// Largest G1HeapRegionSize from JDK 18.
+const MAX_REGION_SIZE_18: u64 = 512 * 1024 * 1024;
+
+/// A flag to change, why, and the GCs that show it.
+#[derive(Serialize, Debug, Clone)]
+pub struct Recommendation {
+    pub flag: String,
+    /// As recorded in the flag events, None when the recording has no value for it.
+    pub current: Option<String>,
+    pub origin: Option<String>,
+    pub recommended: String,
+    /// The command line option, e.g. -XX:G1HeapRegionSize=2m
+    pub option: String,
+    pub reason: String,
+    pub gc_ids: Vec<u64>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct TuningReport {
+    pub recommendations: Vec<Recommendation>,
+}
+
+/// Flags of the recording by name. Those it does not have are taken at their JDK 17
+/// defaults where a rule needs them.
+struct Flags(HashMap<String, Flag>);
+
+impl Flags {
+    fn number(&self, name: &str) -> Option<f64> {
+        self.0.get(name)?.number()
+    }
+
+    fn recommend(
+        &self,
+        flag: &str,
+        recommended: String,
+        reason: String,
+        gc_ids: &[u64],
+    ) -> Recommendation {
+        let current = self.0.get(flag);
+        let option = match recommended.as_str() {
+            "true" => format!("-XX:+{}", flag),
+            "false" => format!("-XX:-{}", flag),
+            _ => format!("-XX:{}={}", flag, recommended),
+        };
+        Recommendation {
+            flag: flag.to_string(),
+            current: current.map(|flag| match &flag.value {
+                serde_json::Value::String(s) => s.clone(),
+                value => value.to_string(),
+            }),
+            origin: current.map(|flag| flag.origin.clone()),
+            recommended,
+            option,
+            reason,
+            gc_ids: gc_ids.iter().take(MAX_CITED).copied().collect(),
+        }
+    }
+}
+
+fn size(bytes: u64) -> String {
+    if bytes.is_multiple_of(1024 * 1024) {
+        format!("{}m", bytes / (1024 * 1024))
+    } else {
+        format!("{}k", bytes / 1024)
+    }
+}
+
+/// Humongous objects are at least half a region, larger regions keep them in young regions.
+fn region_size(jfr: &JfrMain, flags: &Flags, cycles: &[GcCycle]) -> Option<Recommendation> {
+    let current = flags.number("G1HeapRegionSize")? as u64;
+    let started = cycles
+        .iter()
+        .filter(|cycle| cycle.cause == "G1 Humongous Allocation")
+        .map(|cycle| cycle.gc_id)
+        .collect::<Vec<_>>();
+    let full = full_gcs(jfr)
+        .full_gcs
+        .into_iter()
+        .filter(|full| {
+            full.causes
+                .iter()
+                .any(|cause| matches!(cause, FullGcCause::HumongousAllocation { .. }))
+        })
+        .map(|full| full.gc_id)
+        .collect::<Vec<_>>();
+    if started.is_empty() && full.is_empty() {
+        return None;
+    }
+    let max = match jfr.jdk_version() {
+        Some(version) if version.feature >= 18 => MAX_REGION_SIZE_18,
+        _ => MAX_REGION_SIZE,
+    };
+    let recommended = (current * 2).min(max);
+    if recommended <= current {
+        return None;
+    }
+    let gc_ids = full.iter().chain(&started).copied().collect::<Vec<_>>();
+    Some(flags.recommend(
+        "G1HeapRegionSize",
+        size(recommended),
+        format!(
+            "{} GCs started by humongous allocations and {} full GCs they explain; objects of {} or more are humongous, {} regions keep objects under {} out of humongous regions",
+            started.len(),
+            full.len(),
+            size(current / 2),
+            size(recommended),
+            size(recommended / 2)
+        ),
+        &gc_ids,
+    ))
+}
+
+/// Young pauses over the target, which G1 misses when the young generation is already at
+/// G1NewSizePercent or the live objects take long to copy.
+fn pause_target(flags: &Flags, young: &[&GcCycle]) -> Option<Recommendation> {
+    let target = flags.number("MaxGCPauseMillis").unwrap_or(200f64);
+    let mut over = young
+        .iter()
+        .filter(|cycle| cycle.sum_of_pauses_ms > target)
+        .collect::<Vec<_>>();
+    if young.is_empty() || (over.len() as f64) < young.len() as f64 * OVER_TARGET_SHARE {
+        return None;
+    }
+    over.sort_by(|a, b| b.sum_of_pauses_ms.total_cmp(&a.sum_of_pauses_ms));
+    let mut pauses = young
+        .iter()
+        .map(|cycle| cycle.sum_of_pauses_ms)
+        .collect::<Vec<_>>();
+    pauses.sort_by(f64::total_cmp);
+    let p95 = percentile(&pauses, 95f64);
+    Some(flags.recommend(
+        "MaxGCPauseMillis",
+        format!("{:.0}", p95.ceil()),
+        format!(
+            "{} of {} young pauses took longer than the {:.0}ms target, p95 {:.1}ms; a target G1 can meet keeps its heuristics stable, or copy less by lowering G1MaxNewSizePercent",
+            over.len(),
+            young.len(),
+            target,
+            p95
+        ),
+        &over.iter().map(|cycle| cycle.gc_id).collect::<Vec<_>>(),
+    ))
+}
+
+/// Short young pauses taking a large share of the recording, a larger young generation
+/// collects as much in fewer GCs.
+fn young_size(jfr: &JfrMain, flags: &Flags, young: &[&GcCycle]) -> Option<Recommendation> {
+    let target = flags.number("MaxGCPauseMillis").unwrap_or(200f64);
+    let max_heap = flags.number("MaxHeapSize")?;
+    let intervals = rate_intervals(jfr);
+    let seconds = intervals.iter().map(|i| i.seconds).sum::<f64>();
+    let paused_s = young
+        .iter()
+        .map(|cycle| cycle.sum_of_pauses_ms)
+        .sum::<f64>()
+        / 1000f64;
+    if young.is_empty() || seconds <= 0f64 || paused_s / (seconds + paused_s) < YOUNG_OVERHEAD {
+        return None;
+    }
+    let mut pauses = young
+        .iter()
+        .map(|cycle| cycle.sum_of_pauses_ms)
+        .collect::<Vec<_>>();
+    pauses.sort_by(f64::total_cmp);
+    let p90 = percentile(&pauses, 90f64);
+    if p90 > target / 2f64 {
+        return None;
+    }
+    let young_bytes = young
+        .iter()
+        .filter_map(|cycle| Some(cycle.before?.young() as f64))
+        .collect::<Vec<_>>();
+    let young_pct =
+        young_bytes.iter().sum::<f64>() / young_bytes.len().max(1) as f64 / max_heap * 100f64;
+    let current = flags.number("G1NewSizePercent").unwrap_or(5f64);
+    let max = flags.number("G1MaxNewSizePercent").unwrap_or(60f64);
+    let mut shortest = intervals.iter().collect::<Vec<_>>();
+    shortest.sort_by(|a, b| a.seconds.total_cmp(&b.seconds));
+    let gc_ids = shortest.iter().map(|i| i.gc_id).collect::<Vec<_>>();
+    let seen = format!(
+        "{} young GCs spent {:.1}% of the time in pauses, every {:.0}ms on average, with p90 {:.1}ms well under the {:.0}ms target; the young generation averaged {:.0}% of the heap before a GC",
+        young.len(),
+        paused_s / (seconds + paused_s) * 100f64,
+        seconds * 1000f64 / intervals.len().max(1) as f64,
+        p90,
+        target,
+        young_pct
+    );
+    // G1 kept the young generation near its minimum, it may grow
+    if young_pct < current + 5f64 {
+        let recommended = (young_pct * 2f64).max(current + 5f64).min(max).ceil();
+        if recommended <= current {
+            return None;
+        }
+        return Some(flags.recommend(
+            "G1NewSizePercent",
+            format!("{:.0}", recommended),
+            format!(
+                "{}. G1NewSizePercent is experimental, add -XX:+UnlockExperimentalVMOptions",
+                seen
+            ),
+            &gc_ids,
+        ));
+    }
+    Some(flags.recommend(
+        "MaxHeapSize",
+        size(max_heap as u64 * 2),
+        format!(
+            "{}, far over G1NewSizePercent {:.0}; the heap is too small for the allocation rate",
+            seen, current
+        ),
+        &gc_ids,
+    ))
+}
+
+/// Full GCs a marking should have prevented, it started too late or never did.
+fn marking(jfr: &JfrMain, flags: &Flags, cycles: &[GcCycle]) -> Vec<Recommendation> {
+    let mut recommendations = Vec::new();
+    let report = full_gcs(jfr);
+    let late = report
+        .full_gcs
+        .iter()
+        .filter(|full| {
+            full.causes.iter().any(|cause| {
+                matches!(
+                    cause,
+                    FullGcCause::MarkingNotFinished { .. } | FullGcCause::AllocationFailure { .. }
+                )
+            })
+        })
+        .map(|full| full.gc_id)
+        .collect::<Vec<_>>();
+    let failed = report
+        .full_gcs
+        .iter()
+        .flat_map(|full| &full.causes)
+        .filter_map(|cause| match cause {
+            FullGcCause::EvacuationFailure { gc_ids, .. } => Some(gc_ids),
+            _ => None,
+        })
+        .flatten()
+        .copied()
+        .collect::<Vec<_>>();
+    let adaptive = flags.number("G1UseAdaptiveIHOP").is_none_or(|on| on > 0f64);
+    if let (false, Some(max_heap)) = (late.is_empty(), flags.number("MaxHeapSize")) {
+        let ihop = flags
+            .number("InitiatingHeapOccupancyPercent")
+            .unwrap_or(45f64);
+        // occupancy when the markings started, at their Concurrent Start young GCs: the
+        // G1Old summary before is taken at the Remark pause, and GC logs have none
+        let started = cycles
+            .iter()
+            .filter(|cycle| cycle.collection_type == CollectionType::ConcurrentStart)
+            .filter_map(|cycle| Some(cycle.before?.used as f64 / max_heap * 100f64))
+            .reduce(f64::min);
+        let recommended = (started.unwrap_or(ihop).min(ihop) - 10f64)
+            .max(5f64)
+            .floor();
+        if recommended < ihop {
+            recommendations.push(flags.recommend(
+                "InitiatingHeapOccupancyPercent",
+                format!("{:.0}", recommended),
+                format!(
+                    "{} full GCs with no marking or one still running, {}{}",
+                    late.len(),
+                    match started {
+                        Some(started) => format!(
+                                "markings started at {:.0}% of the {:.0}MB heap at the earliest",
+                                started,
+                                max_heap / MB
+                            ),
+                        None => "no marking started".to_string(),
+                    },
+                    if adaptive {
+                        "; G1UseAdaptiveIHOP only starts from this value, G1ReservePercent also makes it start earlier"
+                    } else {
+                        ""
+                    }
+                ),
+                &late,
+            ));
+        }
+    }
+    if !failed.is_empty() {
+        let reserve = flags.number("G1ReservePercent").unwrap_or(10f64);
+        if reserve < 50f64 {
+            recommendations.push(flags.recommend(
+                "G1ReservePercent",
+                format!("{:.0}", (reserve + 10f64).min(50f64)),
+                format!(
+                    "{} young GCs before full GCs failed to evacuate, a larger reserve leaves free regions to copy survivors to",
+                    failed.len()
+                ),
+                &failed,
+            ));
+        }
+    }
+    recommendations
+}
+
+/// Full GCs for System.gc() and metaspace, which have flags of their own.
+fn full_gc_causes(jfr: &JfrMain, flags: &Flags) -> Vec<Recommendation> {
+    let report = full_gcs(jfr);
+    let with = |f: &dyn Fn(&FullGcCause) -> bool| {
+        report
+            .full_gcs
+            .iter()
+            .filter(|full| full.causes.iter().any(f))
+            .map(|full| full.gc_id)
+            .collect::<Vec<_>>()
+    };
+    let mut recommendations = Vec::new();
+    let explicit =
+        with(&|cause| matches!(cause, FullGcCause::Explicit { cause } if cause == "System.gc()"));
+    if !explicit.is_empty() && flags.number("ExplicitGCInvokesConcurrent") != Some(1f64) {
+        recommendations.push(flags.recommend(
+            "ExplicitGCInvokesConcurrent",
+            "true".to_string(),
+            format!(
+                "{} full GCs for System.gc(), a concurrent marking does the same without stopping the application",
+                explicit.len()
+            ),
+            &explicit,
+        ));
+    }
+    let metaspace = with(&|cause| matches!(cause, FullGcCause::Metaspace { .. }));
+    if let (false, Some(current)) = (metaspace.is_empty(), flags.number("MetaspaceSize")) {
+        recommendations.push(flags.recommend(
+            "MetaspaceSize",
+            size(current as u64 * 2),
+            format!(
+                "{} full GCs when class metadata reached the metaspace high water mark, starting from {}",
+                metaspace.len(),
+                size(current as u64)
+            ),
+            &metaspace,
+        ));
+    }
+    recommendations
+}
+
+/// The tenuring threshold from survival rates, see `tenuring::advise`.
+fn tenuring_threshold(jfr: &JfrMain, flags: &Flags) -> Option<Recommendation> {
+    let advice = tenuring::advise(jfr);
+    let current = flags
+        .number("MaxTenuringThreshold")
+        .map(|max| max as u64)
+        .or(advice.current_threshold)?;
+    if advice.gc_pairs.is_empty() || advice.recommended_threshold >= current {
+        return None;
+    }
+    let mut gc_ids = advice
+        .gc_pairs
+        .iter()
+        .flat_map(|(gc_id, next)| [*gc_id, *next])
+        .collect::<Vec<_>>();
+    gc_ids.dedup();
+    Some(flags.recommend(
+        "MaxTenuringThreshold",
+        advice.recommended_threshold.to_string(),
+        format!(
+            "from {} pairs of young GCs, {}",
+            advice.gc_pairs.len(),
+            advice.reason
+        ),
+        &gc_ids,
+    ))
+}
+
+pub fn tuning(jfr: &JfrMain) -> TuningReport {
+    let flags = Flags(jfr.flags());
+    let cycles = jfr.gc_cycles();
+    let young = cycles
+        .iter()
+        .filter(|cycle| cycle.name == "G1New")
+        .collect::<Vec<_>>();
+    let mut recommendations = Vec::new();
+    recommendations.extend(region_size(jfr, &flags, &cycles));
+    recommendations.extend(pause_target(&flags, &young));
+    recommendations.extend(young_size(jfr, &flags, &young));
+    recommendations.extend(marking(jfr, &flags, &cycles));
+    recommendations.extend(full_gc_causes(jfr, &flags));
+    recommendations.extend(tenuring_threshold(jfr, &flags));
+    TuningReport { recommendations }
+}
+
+impl fmt::Display for TuningReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.recommendations.is_empty() {
+            return writeln!(f, "no recommendations");
+        }
+        for r in &self.recommendations {
+            write!(f, "{}", r.option)?;
+            match (&r.current, &r.origin) {
+                (Some(current), Some(origin)) => writeln!(f, " (now {}, {})", current, origin)?,
+                _ => writeln!(f, " (not recorded)")?,
+            }
+            writeln!(f, "  {}", r.reason)?;
+            if !r.gc_ids.is_empty() {
+                writeln!(
+                    f,
+                    "  gc {}",
+                    r.gc_ids
+                        .iter()
+                        .map(u64::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::gclog;
+
+    /// Young GCs at threshold 15 whose cohorts all survive from age 1.
+    fn long_lived(gcs: u64) -> JfrMain {
+        let mut log = String::new();
+        for gc_id in 1..=gcs {
+            log += &format!(
+                "[{gc_id}.000s][info ][gc,start] GC({gc_id}) Pause Young (Normal) (G1 Evacuation Pause)\n\
+                 [{gc_id}.000s][debug][gc,age  ] GC({gc_id}) Desired survivor size 3670016 bytes, new threshold 15 (max threshold 15)\n"
+            );
+            for age in 1..=gc_id {
+                log += &format!(
+                    "[{gc_id}.001s][trace][gc,age  ] GC({gc_id}) - age {age:>3}:    1000000 bytes,    1000000 total\n"
+                );
+            }
+            log += &format!(
+                "[{gc_id}.002s][info ][gc      ] GC({gc_id}) Pause Young (Normal) (G1 Evacuation Pause) 50M->10M(96M) 2.000ms\n"
+            );
+        }
+        gclog::parse(log.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn tenuring_threshold_cites_the_gcs_paired() {
+        let jfr = long_lived(4);
+        let flags = Flags(jfr.flags());
+        let recommendation = tenuring_threshold(&jfr, &flags).unwrap();
+        assert_eq!(recommendation.option, "-XX:MaxTenuringThreshold=1");
+        assert_eq!(recommendation.gc_ids, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn cited_gcs_are_capped() {
+        let jfr = long_lived(12);
+        let recommendation = tenuring_threshold(&jfr, &Flags(jfr.flags())).unwrap();
+        assert_eq!(
+            recommendation.gc_ids,
+            (1..=MAX_CITED as u64).collect::<Vec<_>>()
+        );
+    }
+
+    fn ihop(jfr: &JfrMain) -> Option<String> {
+        let flags = Flags(jfr.flags());
+        marking(jfr, &flags, &jfr.gc_cycles())
+            .into_iter()
+            .find(|r| r.flag == "InitiatingHeapOccupancyPercent")
+            .map(|r| r.option)
+    }
+
+    #[test]
+    fn ihop_from_the_concurrent_start_of_a_jfr_recording() {
+        let mut jfr = gclog::parse(&b""[..]).unwrap();
+        let at = |ms: u64| format!("1970-01-01T00:00:{:02}.{:03}Z", ms / 1000, ms % 1000);
+        jfr.recording.push_value(json!({
+            "type": "jdk.UnsignedLongFlag",
+            "values": {"startTime": at(0), "name": "MaxHeapSize", "value": 100 << 20, "origin": "Command line"},
+        }));
+        // a Concurrent Start at 30% of the heap, then the G1Old summary at Remark at 90%
+        for (gc_id, name, cause, type_, start, used) in [
+            (
+                1,
+                "G1New",
+                "G1 Evacuation Pause",
+                "Concurrent Start",
+                1000,
+                30,
+            ),
+            (2, "G1Old", "G1 Evacuation Pause", "Unknown", 1010, 90),
+            (3, "G1Full", "G1 Compaction Pause", "Unknown", 2000, 95),
+        ] {
+            jfr.recording.push_value(json!({
+                "type": "jdk.GarbageCollection",
+                "values": {
+                    "startTime": at(start), "duration": "PT0.01S", "gcId": gc_id, "name": name,
+                    "cause": cause, "sumOfPauses": "PT0.01S", "longestPause": "PT0.01S",
+                },
+            }));
+            if name == "G1New" {
+                jfr.recording.push_value(json!({
+                    "type": "jdk.G1GarbageCollection",
+                    "values": {"startTime": at(start), "gcId": gc_id, "type": type_},
+                }));
+            }
+            for when in ["Before GC", "After GC"] {
+                jfr.recording.push_value(json!({
+                    "type": "jdk.GCHeapSummary",
+                    "values": {"startTime": at(start), "when": when, "gcId": gc_id, "heapUsed": used << 20},
+                }));
+                jfr.recording.push_value(json!({
+                    "type": "jdk.G1HeapSummary",
+                    "values": {
+                        "startTime": at(start), "when": when, "gcId": gc_id,
+                        "edenUsedSize": 0, "edenTotalSize": 0, "survivorUsedSize": 0,
+                    },
+                }));
+            }
+        }
+        assert_eq!(
+            ihop(&jfr).as_deref(),
+            Some("-XX:InitiatingHeapOccupancyPercent=20")
+        );
+    }
+
+    #[test]
+    fn ihop_from_the_concurrent_start_of_a_gc_log() {
+        let log = "\
+[0.003s][info ][gc,init       ] Heap Max Capacity: 100M
+[1.000s][info ][gc,start       ] GC(0) Pause Young (Concurrent Start) (G1 Evacuation Pause)
+[1.003s][info ][gc,heap        ] GC(0) Eden regions: 10->0(10)
+[1.003s][info ][gc,heap        ] GC(0) Survivor regions: 1->1(2)
+[1.003s][info ][gc             ] GC(0) Pause Young (Concurrent Start) (G1 Evacuation Pause) 40M->31M(100M) 3.000ms
+[1.003s][info ][gc             ] GC(1) Concurrent Mark Cycle
+[2.000s][info ][gc,start       ] GC(2) Pause Full (G1 Compaction Pause)
+[2.100s][info ][gc             ] GC(2) Pause Full (G1 Compaction Pause) 95M->30M(100M) 100.000ms
+[3.000s][info ][gc             ] GC(1) Concurrent Mark Cycle 2000.000ms
+";
+        let jfr = gclog::parse(log.as_bytes()).unwrap();
+        assert_eq!(
+            ihop(&jfr).as_deref(),
+            Some("-XX:InitiatingHeapOccupancyPercent=30")
+        );
+    }
+}